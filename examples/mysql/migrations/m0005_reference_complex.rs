@@ -20,6 +20,10 @@ impl Operation<MySql> for M0005Operation {
             .await?;
         Ok(())
     }
+
+    fn is_reversible(&self) -> bool {
+        true
+    }
 }
 
 pub(crate) struct M0005Migration;
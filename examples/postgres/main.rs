@@ -13,9 +13,10 @@ async fn main() {
     migrator.add_migrations(migrations::migrations());
     // There are two way to run migration. Either you can create cli as shown below
     let mut conn = pool.acquire().await.unwrap();
-    MigrationCommand::parse_and_run(&mut *conn, Box::new(migrator))
-        .await
-        .unwrap();
+    if let Err(error) = MigrationCommand::parse_and_run(&mut *conn, Box::new(migrator)).await {
+        eprintln!("{error}");
+        std::process::exit(error.exit_code());
+    }
     // Or you can directly use migrator run function instead of creating
     // cli
     // migrator
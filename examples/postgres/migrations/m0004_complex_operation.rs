@@ -26,6 +26,10 @@ impl Operation<Postgres> for M0004Operation {
             .await?;
         Ok(())
     }
+
+    fn is_reversible(&self) -> bool {
+        true
+    }
 }
 
 pub(crate) struct M0004Migration {
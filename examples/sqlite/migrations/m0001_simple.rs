@@ -18,6 +18,10 @@ impl Operation<Sqlite> for M0001Operation {
         sqlx::query("DROP TABLE sample").execute(connection).await?;
         Ok(())
     }
+
+    fn is_reversible(&self) -> bool {
+        true
+    }
 }
 
 pub(crate) struct M0001Migration;
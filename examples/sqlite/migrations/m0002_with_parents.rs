@@ -20,6 +20,10 @@ impl Operation<Sqlite> for M0002Operation {
             .await?;
         Ok(())
     }
+
+    fn is_reversible(&self) -> bool {
+        true
+    }
 }
 
 pub(crate) struct M0002Migration;
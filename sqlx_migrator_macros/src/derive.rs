@@ -0,0 +1,321 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::meta::ParseNestedMeta;
+use syn::punctuated::Punctuated;
+use syn::{DeriveInput, Expr, LitBool, LitStr, Token, Type};
+
+const ATTRIBUTE_KEYS: &str =
+    "db, app, name, parents, operations, replaces, run_before, atomic, virtual";
+
+/// Values parsed from `#[migration(...)]` attributes
+#[derive(Default)]
+struct MigrationAttributes {
+    dbs: Vec<Type>,
+    app: Option<LitStr>,
+    name: Option<LitStr>,
+    parents: Option<Vec<Expr>>,
+    operations: Option<Vec<Expr>>,
+    replaces: Option<Vec<Expr>>,
+    run_before: Option<Vec<Expr>>,
+    atomic: Option<bool>,
+    is_virtual: Option<bool>,
+}
+
+fn parse_list(meta: &ParseNestedMeta) -> syn::Result<Vec<Expr>> {
+    let content;
+    syn::parenthesized!(content in meta.input);
+    Ok(Punctuated::<Expr, Token![,]>::parse_terminated(&content)?
+        .into_iter()
+        .collect())
+}
+
+/// Parse `key = bool` or only `key` which is treated as true
+fn parse_flag(meta: &ParseNestedMeta) -> syn::Result<bool> {
+    if meta.input.peek(Token![=]) {
+        Ok(meta.value()?.parse::<LitBool>()?.value)
+    } else {
+        Ok(true)
+    }
+}
+
+fn set_once<T>(
+    meta: &ParseNestedMeta,
+    key: &str,
+    field: &mut Option<T>,
+    value: T,
+) -> syn::Result<()> {
+    if field.is_some() {
+        return Err(meta.error(format!("duplicate `{key}` in migration attribute")));
+    }
+    *field = Some(value);
+    Ok(())
+}
+
+impl MigrationAttributes {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut attributes = Self::default();
+        let mut found = false;
+        for attr in input
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("migration"))
+        {
+            found = true;
+            attr.parse_nested_meta(|meta| attributes.parse_meta(&meta))?;
+        }
+        if !found {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "missing `#[migration(...)]` attribute, e.g. `#[migration(db = sqlx::Postgres, \
+                 app = \"main\", name = \"m0001_initial\")]`",
+            ));
+        }
+        if attributes.dbs.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "missing `db` in migration attribute, e.g. `db = sqlx::Postgres`",
+            ));
+        }
+        if attributes.app.is_none() {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "missing `app` in migration attribute, e.g. `app = \"main\"`",
+            ));
+        }
+        if attributes.name.is_none() {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "missing `name` in migration attribute, e.g. `name = \"m0001_initial\"`",
+            ));
+        }
+        Ok(attributes)
+    }
+
+    fn parse_meta(&mut self, meta: &ParseNestedMeta) -> syn::Result<()> {
+        let Some(key) = meta.path.get_ident().map(ToString::to_string) else {
+            return Err(meta.error(format!(
+                "unknown migration attribute, expected one of {ATTRIBUTE_KEYS}"
+            )));
+        };
+        match key.as_str() {
+            // db can be used multiple times to implement migration for multiple database
+            "db" => self.dbs.push(meta.value()?.parse()?),
+            "app" => {
+                let value = meta.value()?.parse()?;
+                set_once(meta, "app", &mut self.app, value)?;
+            }
+            "name" => {
+                let value = meta.value()?.parse()?;
+                set_once(meta, "name", &mut self.name, value)?;
+            }
+            "parents" => {
+                let value = parse_list(meta)?;
+                set_once(meta, "parents", &mut self.parents, value)?;
+            }
+            "operations" => {
+                let value = parse_list(meta)?;
+                set_once(meta, "operations", &mut self.operations, value)?;
+            }
+            "replaces" => {
+                let value = parse_list(meta)?;
+                set_once(meta, "replaces", &mut self.replaces, value)?;
+            }
+            "run_before" => {
+                let value = parse_list(meta)?;
+                set_once(meta, "run_before", &mut self.run_before, value)?;
+            }
+            "atomic" => {
+                let value = parse_flag(meta)?;
+                set_once(meta, "atomic", &mut self.atomic, value)?;
+            }
+            "virtual" => {
+                let value = parse_flag(meta)?;
+                set_once(meta, "virtual", &mut self.is_virtual, value)?;
+            }
+            _ => {
+                return Err(meta.error(format!(
+                    "unknown migration attribute `{key}`, expected one of {ATTRIBUTE_KEYS}"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn migration_list(migration: &TokenStream, list: Option<&Vec<Expr>>) -> TokenStream {
+    let list = list.into_iter().flatten();
+    quote! {
+        vec![#(Box::new(#list) as Box<dyn #migration>),*]
+    }
+}
+
+/// Expand `Migration` derive macro
+pub(crate) fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let attributes = MigrationAttributes::parse(input)?;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let app = &attributes.app;
+    let name = &attributes.name;
+    let impls = attributes.dbs.iter().map(|db| {
+        let migration = quote! { ::sqlx_migrator::migration::Migration<#db> };
+        let operation = quote! { ::sqlx_migrator::operation::Operation<#db> };
+        let parents = migration_list(&migration, attributes.parents.as_ref());
+        let operations = attributes.operations.iter().flatten();
+        let replaces = attributes.replaces.as_ref().map(|replaces| {
+            let replaces = migration_list(&migration, Some(replaces));
+            quote! {
+                fn replaces(&self) -> Vec<Box<dyn #migration>> {
+                    #replaces
+                }
+            }
+        });
+        let run_before = attributes.run_before.as_ref().map(|run_before| {
+            let run_before = migration_list(&migration, Some(run_before));
+            quote! {
+                fn run_before(&self) -> Vec<Box<dyn #migration>> {
+                    #run_before
+                }
+            }
+        });
+        let atomic = attributes.atomic.map(|atomic| {
+            quote! {
+                fn is_atomic(&self) -> bool {
+                    #atomic
+                }
+            }
+        });
+        let is_virtual = attributes.is_virtual.map(|is_virtual| {
+            quote! {
+                fn is_virtual(&self) -> bool {
+                    #is_virtual
+                }
+            }
+        });
+        quote! {
+            impl #impl_generics #migration for #ident #ty_generics
+            #where_clause
+            {
+                fn app(&self) -> &str {
+                    #app
+                }
+
+                fn name(&self) -> &str {
+                    #name
+                }
+
+                fn parents(&self) -> Vec<Box<dyn #migration>> {
+                    #parents
+                }
+
+                fn operations(&self) -> Vec<Box<dyn #operation>> {
+                    vec![#(Box::new(#operations) as Box<dyn #operation>),*]
+                }
+
+                #replaces
+
+                #run_before
+
+                #atomic
+
+                #is_virtual
+            }
+        }
+    });
+    Ok(quote! {
+        #(#impls)*
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::DeriveInput;
+
+    use super::expand;
+
+    fn error(input: &DeriveInput) -> String {
+        expand(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn expand_migration() {
+        let input = syn::parse_quote! {
+            #[migration(db = sqlx::Sqlite, db = sqlx::Postgres, app = "main", name = "m0001")]
+            #[migration(parents(M0000), virtual)]
+            struct M0001;
+        };
+        let expanded = expand(&input).unwrap().to_string();
+        assert!(expanded.contains(":: sqlx_migrator :: migration :: Migration < sqlx :: Sqlite >"));
+        assert!(
+            expanded.contains(":: sqlx_migrator :: migration :: Migration < sqlx :: Postgres >")
+        );
+        assert!(expanded.contains("fn is_virtual (& self) -> bool { true }"));
+        assert!(!expanded.contains("fn is_atomic"));
+    }
+
+    #[test]
+    fn missing_attribute() {
+        assert_eq!(
+            error(&syn::parse_quote! {
+                struct M0001;
+            }),
+            "missing `#[migration(...)]` attribute, e.g. `#[migration(db = sqlx::Postgres, app = \
+             \"main\", name = \"m0001_initial\")]`"
+        );
+        assert_eq!(
+            error(&syn::parse_quote! {
+                #[migration(app = "main", name = "m0001")]
+                struct M0001;
+            }),
+            "missing `db` in migration attribute, e.g. `db = sqlx::Postgres`"
+        );
+        assert_eq!(
+            error(&syn::parse_quote! {
+                #[migration(db = sqlx::Postgres, name = "m0001")]
+                struct M0001;
+            }),
+            "missing `app` in migration attribute, e.g. `app = \"main\"`"
+        );
+        assert_eq!(
+            error(&syn::parse_quote! {
+                #[migration(db = sqlx::Postgres, app = "main")]
+                struct M0001;
+            }),
+            "missing `name` in migration attribute, e.g. `name = \"m0001_initial\"`"
+        );
+    }
+
+    #[test]
+    fn invalid_attribute() {
+        assert_eq!(
+            error(&syn::parse_quote! {
+                #[migration(db = sqlx::Postgres, app = "main", name = "m0001")]
+                #[migration(app = "other")]
+                struct M0001;
+            }),
+            "duplicate `app` in migration attribute"
+        );
+        assert_eq!(
+            error(&syn::parse_quote! {
+                #[migration(db = sqlx::Postgres, app = "main", name = "m0001", atomic, atomic)]
+                struct M0001;
+            }),
+            "duplicate `atomic` in migration attribute"
+        );
+        assert_eq!(
+            error(&syn::parse_quote! {
+                #[migration(db = sqlx::Postgres, app = "main", name = "m0001", parent(M0000))]
+                struct M0001;
+            }),
+            "unknown migration attribute `parent`, expected one of db, app, name, parents, \
+             operations, replaces, run_before, atomic, virtual"
+        );
+        assert_eq!(
+            error(&syn::parse_quote! {
+                #[migration(db = sqlx::Postgres, app = "main", sqlx::name = "m0001")]
+                struct M0001;
+            }),
+            "unknown migration attribute, expected one of db, app, name, parents, operations, \
+             replaces, run_before, atomic, virtual"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::path::PathBuf;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use sqlx_migrator_sql_file::{build_migrations, parse_file_name, SqlFile, SqlFileMigration};
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, LitStr, Token, Type};
+
+/// Input of `embed_migrations` macro
+pub(crate) struct EmbedInput {
+    directory: LitStr,
+    db: Type,
+    app: LitStr,
+}
+
+impl Parse for EmbedInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let directory = input.parse::<LitStr>()?;
+        input.parse::<Token![,]>()?;
+        let db = input.parse::<Type>()?;
+        input.parse::<Token![,]>()?;
+        let key = input.parse::<Ident>()?;
+        if key != "app" {
+            return Err(syn::Error::new(key.span(), "expected `app = \"...\"`"));
+        }
+        input.parse::<Token![=]>()?;
+        let app = input.parse::<LitStr>()?;
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+        }
+        Ok(Self { directory, db, app })
+    }
+}
+
+fn read_migrations(input: &EmbedInput) -> syn::Result<Vec<SqlFileMigration<String>>> {
+    let span = input.directory.span();
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| syn::Error::new(span, "CARGO_MANIFEST_DIR is not set"))?;
+    let directory = PathBuf::from(manifest_dir).join(input.directory.value());
+    let entries = std::fs::read_dir(&directory).map_err(|error| {
+        syn::Error::new(
+            span,
+            format!("failed to read {}: {error}", directory.display()),
+        )
+    })?;
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|error| syn::Error::new(span, error.to_string()))?;
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if parse_file_name(&file_name).is_none() || !path.is_file() {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path).map_err(|error| {
+            syn::Error::new(span, format!("failed to read {file_name}: {error}"))
+        })?;
+        files.push(SqlFile {
+            file_name,
+            content,
+            source: path.to_string_lossy().to_string(),
+        });
+    }
+    build_migrations(files, &input.app.value()).map_err(|message| syn::Error::new(span, message))
+}
+
+/// Expand `embed_migrations` macro
+pub(crate) fn expand(input: &EmbedInput) -> syn::Result<TokenStream> {
+    let db = &input.db;
+    let migrations = read_migrations(input)?.into_iter().map(|migration| {
+        let SqlFileMigration {
+            app,
+            name,
+            parents,
+            up,
+            down,
+            atomic,
+            destructible,
+        } = migration;
+        let up_path = up.source;
+        let down = down.map(|down| {
+            let down_path = down.source;
+            quote! { .down(include_str!(#down_path)) }
+        });
+        let parents = parents
+            .into_iter()
+            .map(|(parent_app, parent_name)| quote! { .parent(#parent_app, #parent_name) });
+        quote! {
+            migrations.push(Box::new(
+                ::sqlx_migrator::sql_migration::SqlMigration::new(
+                    #app,
+                    #name,
+                    include_str!(#up_path),
+                )
+                #down
+                #(#parents)*
+                .atomic(#atomic)
+                .destructible(#destructible)
+            ));
+        }
+    });
+    Ok(quote! {
+        {
+            let mut migrations: Vec<Box<dyn ::sqlx_migrator::migration::Migration<#db>>> =
+                Vec::new();
+            #(#migrations)*
+            migrations
+        }
+    })
+}
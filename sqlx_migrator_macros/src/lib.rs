@@ -0,0 +1,83 @@
+//! Procedural macros for `sqlx_migrator`
+//!
+//! Use these macros through `sqlx_migrator` crate with `macros` feature
+//! enabled instead of depending on this crate directly.
+
+use proc_macro::TokenStream;
+
+mod derive;
+mod embed;
+
+/// Embed SQL migrations present in directory at compile time.
+///
+/// Directory path is relative to `CARGO_MANIFEST_DIR` of crate using macro.
+/// Directory must contain `NNNN_name.up.sql` and optional `NNNN_name.down.sql`
+/// files. Migrations are chained by file name order where each migration uses
+/// previous migration of same app as parent. Up file can use front matter
+/// comments `-- app:`, `-- parents:`, `-- atomic:` and `-- destructible:` to
+/// override values.
+///
+/// Macro expands to `Vec<Box<dyn Migration<DB>>>` and fails compilation if
+/// file name is malformed, same number is used by multiple migrations of app
+/// or front matter is invalid.
+///
+/// ```rust,ignore
+/// let migrations = sqlx_migrator::embed_migrations!("migrations", sqlx::Postgres, app = "main");
+/// migrator.add_migrations(migrations);
+/// ```
+///
+/// Files are embedded using `include_str!` so cargo only rebuilds crate when
+/// already embedded file changes. Adding new file to directory doesn't
+/// trigger rebuild, so add `build.rs` which tells cargo to watch directory
+///
+/// ```rust,ignore
+/// // build.rs
+/// fn main() {
+///     println!("cargo:rerun-if-changed=migrations");
+/// }
+/// ```
+#[proc_macro]
+pub fn embed_migrations(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as embed::EmbedInput);
+    embed::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive [`Migration`] trait using `#[migration(...)]` attribute.
+///
+/// Supported attribute values are
+/// - `db = Type`: database for which migration is implemented. Can be used
+///   multiple times to implement migration for multiple database
+/// - `app = "..."`: app name of migration
+/// - `name = "..."`: name of migration
+/// - `parents(...)`, `replaces(...)`, `run_before(...)`: list of migration
+///   expression, tuple of app and name such as `("main", "m0001")` can also be
+///   used
+/// - `operations(...)`: list of operation expression
+/// - `atomic = bool`, `virtual`: atomic and virtual value of migration
+///
+/// `db`, `app` and `name` are required.
+///
+/// ```rust,ignore
+/// #[derive(sqlx_migrator::Migration)]
+/// #[migration(
+///     db = sqlx::Postgres,
+///     app = "main",
+///     name = "m0002_add_index",
+///     parents(M0001),
+///     operations(AddIndexOperation),
+///     replaces(("old", "m0001_add_index")),
+///     atomic = false
+/// )]
+/// struct M0002;
+/// ```
+///
+/// [`Migration`]: https://docs.rs/sqlx_migrator/latest/sqlx_migrator/migration/trait.Migration.html
+#[proc_macro_derive(Migration, attributes(migration))]
+pub fn derive_migration(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    derive::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
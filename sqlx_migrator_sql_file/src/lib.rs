@@ -0,0 +1,337 @@
+//! Parser of SQL migration files shared by `sqlx_migrator` and
+//! `sqlx_migrator_macros`
+//!
+//! Use SQL migration through `sqlx_migrator` crate instead of depending on
+//! this crate directly.
+
+use std::collections::HashMap;
+
+/// Values present in front matter comments at start of up SQL
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrontMatter {
+    /// App set using `-- app:`
+    pub app: Option<String>,
+    /// Parents set using `-- parents:`. App of parent is `None` if parent is
+    /// written without app
+    pub parents: Option<Vec<(Option<String>, String)>>,
+    /// Atomic value set using `-- atomic:`
+    pub atomic: Option<bool>,
+    /// Destructible value set using `-- destructible:`
+    pub destructible: Option<bool>,
+}
+
+impl FrontMatter {
+    /// Resolve parents where parent without app uses provided app
+    #[must_use]
+    pub fn resolve_parents(&self, app: &str) -> Option<Vec<(String, String)>> {
+        self.parents.as_ref().map(|parents| {
+            let mut resolved = Vec::with_capacity(parents.len());
+            for (parent_app, name) in parents {
+                let parent = (
+                    parent_app.as_deref().unwrap_or(app).to_string(),
+                    name.clone(),
+                );
+                if !resolved.contains(&parent) {
+                    resolved.push(parent);
+                }
+            }
+            resolved
+        })
+    }
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => {
+            Err(format!(
+                "invalid {key} value {value}, expected true or false"
+            ))
+        }
+    }
+}
+
+/// Parse front matter comments present at start of up SQL. Front matter ends
+/// at first line which is not a comment
+///
+/// # Errors
+/// If atomic or destructible value is not boolean
+pub fn parse_front_matter(content: &str) -> Result<FrontMatter, String> {
+    let mut front_matter = FrontMatter::default();
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        let Some(comment) = line.strip_prefix("--") else {
+            break;
+        };
+        let Some((key, value)) = comment.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "app" => front_matter.app = Some(value.to_string()),
+            "parents" => {
+                front_matter.parents = Some(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|parent| !parent.is_empty())
+                        .map(|parent| {
+                            match parent.split_once(':') {
+                                Some((app, name)) => {
+                                    (Some(app.trim().to_string()), name.trim().to_string())
+                                }
+                                None => (None, parent.to_string()),
+                            }
+                        })
+                        .collect(),
+                );
+            }
+            "atomic" => front_matter.atomic = Some(parse_bool("atomic", value)?),
+            "destructible" => {
+                front_matter.destructible = Some(parse_bool("destructible", value)?);
+            }
+            _ => {}
+        }
+    }
+    Ok(front_matter)
+}
+
+/// Parsed name of SQL migration file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileName {
+    /// Number present at start of file name
+    pub number: u64,
+    /// Name of migration which is file name without `.up.sql` or `.down.sql`
+    pub name: String,
+    /// Whether file is up file
+    pub is_up: bool,
+}
+
+/// Split file name of SQL migration into number, name and whether it is up
+/// file. Returns `None` if file is not SQL migration file
+///
+/// # Errors
+/// If file is SQL migration file but doesn't follow `NNNN_name.up.sql` format
+#[must_use]
+pub fn parse_file_name(file_name: &str) -> Option<Result<FileName, String>> {
+    let (name, is_up) = if let Some(name) = file_name.strip_suffix(".up.sql") {
+        (name, true)
+    } else if let Some(name) = file_name.strip_suffix(".down.sql") {
+        (name, false)
+    } else {
+        return None;
+    };
+    let number = name
+        .split_once('_')
+        .filter(|(number, rest)| {
+            !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) && !rest.is_empty()
+        })
+        .and_then(|(number, _)| number.parse::<u64>().ok());
+    Some(
+        number
+            .map(|number| {
+                FileName {
+                    number,
+                    name: name.to_string(),
+                    is_up,
+                }
+            })
+            .ok_or_else(|| {
+                format!("invalid migration file name {file_name}, expected NNNN_name.up.sql")
+            }),
+    )
+}
+
+/// SQL migration file with its content. `source` is any value which caller
+/// wants to carry along with file such as path of file
+#[derive(Debug, Clone)]
+pub struct SqlFile<T> {
+    /// Name of file
+    pub file_name: String,
+    /// Content of file
+    pub content: String,
+    /// Value carried along with file
+    pub source: T,
+}
+
+/// SQL migration built from up and optional down file
+#[derive(Debug, Clone)]
+pub struct SqlFileMigration<T> {
+    /// App of migration
+    pub app: String,
+    /// Name of migration
+    pub name: String,
+    /// App and name of parents
+    pub parents: Vec<(String, String)>,
+    /// Up file
+    pub up: SqlFile<T>,
+    /// Down file
+    pub down: Option<SqlFile<T>>,
+    /// Whether migration is atomic
+    pub atomic: bool,
+    /// Whether up SQL is destructible
+    pub destructible: bool,
+}
+
+/// Build SQL migrations from list of files. Files which are not SQL migration
+/// files are ignored. Migrations are ordered by number of file name and
+/// migration without explicit parents uses previous migration of same app as
+/// parent
+///
+/// # Errors
+/// If file name is invalid, front matter is invalid, down file is present
+/// without up file or same number is used by multiple migrations of app
+pub fn build_migrations<T>(
+    files: Vec<SqlFile<T>>,
+    app: &str,
+) -> Result<Vec<SqlFileMigration<T>>, String> {
+    let mut ups = Vec::new();
+    let mut downs = HashMap::new();
+    for file in files {
+        let Some(parsed) = parse_file_name(&file.file_name) else {
+            continue;
+        };
+        let FileName {
+            number,
+            name,
+            is_up,
+        } = parsed?;
+        if is_up {
+            ups.push((number, name, file));
+        } else {
+            downs.insert(name, file);
+        }
+    }
+    ups.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+    let mut numbers: Vec<(String, u64, String)> = Vec::with_capacity(ups.len());
+    let mut migrations: Vec<SqlFileMigration<T>> = Vec::with_capacity(ups.len());
+    for (number, name, up) in ups {
+        let front_matter =
+            parse_front_matter(&up.content).map_err(|error| format!("{error} in {name}"))?;
+        let migration_app = front_matter.app.clone().unwrap_or_else(|| app.to_string());
+        if let Some((_, _, existing)) = numbers.iter().find(|(added_app, added_number, _)| {
+            added_app == &migration_app && *added_number == number
+        }) {
+            return Err(format!(
+                "migration number {number} of app {migration_app} is used by both {existing} and \
+                 {name}"
+            ));
+        }
+        numbers.push((migration_app.clone(), number, name.clone()));
+        let parents = front_matter
+            .resolve_parents(&migration_app)
+            .unwrap_or_else(|| {
+                migrations
+                    .iter()
+                    .rev()
+                    .find(|added| added.app == migration_app)
+                    .map(|previous| vec![(previous.app.clone(), previous.name.clone())])
+                    .unwrap_or_default()
+            });
+        migrations.push(SqlFileMigration {
+            app: migration_app,
+            down: downs.remove(&name),
+            name,
+            parents,
+            up,
+            atomic: front_matter.atomic.unwrap_or(true),
+            destructible: front_matter.destructible.unwrap_or(false),
+        });
+    }
+    if let Some(name) = downs.keys().next() {
+        return Err(format!("down file for {name} is present without up file"));
+    }
+    Ok(migrations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_migrations, parse_front_matter, SqlFile};
+
+    fn files(files: &[(&str, &str)]) -> Vec<SqlFile<()>> {
+        files
+            .iter()
+            .map(|(file_name, content)| {
+                SqlFile {
+                    file_name: (*file_name).to_string(),
+                    content: (*content).to_string(),
+                    source: (),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn front_matter() {
+        let front_matter = parse_front_matter(
+            "-- app: main\n-- parents: 0001_a, other:0002_b, 0001_a\n\n-- atomic: false\nSELECT \
+             1;\n-- destructible: true",
+        )
+        .unwrap();
+        assert_eq!(front_matter.app.as_deref(), Some("main"));
+        assert_eq!(front_matter.atomic, Some(false));
+        assert_eq!(front_matter.destructible, None);
+        assert_eq!(
+            front_matter.resolve_parents("main").unwrap(),
+            vec![
+                ("main".to_string(), "0001_a".to_string()),
+                ("other".to_string(), "0002_b".to_string())
+            ]
+        );
+        assert_eq!(
+            parse_front_matter("-- atomic: yes").unwrap_err(),
+            "invalid atomic value yes, expected true or false"
+        );
+    }
+
+    #[test]
+    fn chain_and_duplicate_number() {
+        let migrations = build_migrations(
+            files(&[
+                ("0002_b.up.sql", ""),
+                ("0001_a.up.sql", ""),
+                ("0001_a.down.sql", ""),
+                ("0001_c.up.sql", "-- app: other"),
+                ("notes.txt", ""),
+            ]),
+            "main",
+        )
+        .unwrap();
+        let names = migrations
+            .iter()
+            .map(|migration| {
+                (
+                    migration.app.as_str(),
+                    migration.name.as_str(),
+                    migration.parents.clone(),
+                    migration.down.is_some(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("main", "0001_a", vec![], true),
+                ("other", "0001_c", vec![], false),
+                (
+                    "main",
+                    "0002_b",
+                    vec![("main".to_string(), "0001_a".to_string())],
+                    false
+                ),
+            ]
+        );
+        assert_eq!(
+            build_migrations(files(&[("0001_a.up.sql", ""), ("1_b.up.sql", "")]), "main")
+                .unwrap_err(),
+            "migration number 1 of app main is used by both 0001_a and 1_b"
+        );
+        assert_eq!(
+            build_migrations(files(&[("0001_a.down.sql", "")]), "main").unwrap_err(),
+            "down file for 0001_a is present without up file"
+        );
+    }
+}
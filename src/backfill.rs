@@ -0,0 +1,475 @@
+//! Module for running large data migrations in batches
+//!
+//! [`Backfill`] is an [`Operation`] which runs update query for rows of table
+//! in batches ranged by integer key column. Each batch is committed in its own
+//! transaction together with checkpoint of last processed key, so backfill
+//! which fails or gets interrupted resumes from last checkpoint when migration
+//! is run again. Since each batch needs its own transaction backfill can only
+//! be used in non atomic migration.
+//!
+//! Checkpoints are stored in table named `{migrator_table_name}_backfill`. Use
+//! [`Backfill::migrator_table_name`] when migrator uses prefix. Checkpoint is
+//! removed after backfill completes, so update query should be idempotent.
+#![cfg_attr(
+    feature = "postgres",
+    doc = r#"
+# Example
+```rust
+use std::time::Duration;
+
+use sqlx::Postgres;
+use sqlx_migrator::backfill::Backfill;
+use sqlx_migrator::operation::Operation;
+
+let backfill = Backfill::new(
+    "users_lower_name",
+    "users",
+    "id",
+    "UPDATE users SET lower_name = LOWER(name) WHERE id > $1 AND id <= $2",
+)
+.batch_size(500)
+.throttle(Duration::from_millis(100));
+let operations: Vec<Box<dyn Operation<Postgres>>> = vec![Box::new(backfill)];
+```
+"#
+)]
+
+use std::time::Duration;
+
+use sqlx::{ColumnIndex, Connection, Database, Decode, Encode, Executor, IntoArguments, Type};
+
+use crate::error::Error;
+use crate::operation::Operation;
+use crate::schema::Dialect;
+use crate::sleep::sleep;
+
+/// Name of table which stores backfill checkpoints for migrator table
+#[must_use]
+pub fn checkpoint_table_name(migrator_table_name: &str) -> String {
+    format!("{migrator_table_name}_backfill")
+}
+
+/// Query to create checkpoint table using quoted table name
+fn create_checkpoint_table_query(checkpoint_table: &str) -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {checkpoint_table} (name VARCHAR(255) PRIMARY KEY NOT NULL, \
+         last_key BIGINT NOT NULL)"
+    )
+}
+
+/// Operation which runs update query in batches ranged by integer key column
+/// and stores checkpoint after each batch
+#[derive(Debug, Clone)]
+pub struct Backfill {
+    name: String,
+    table: String,
+    key_column: String,
+    update: String,
+    down: Option<String>,
+    batch_size: u32,
+    throttle: Duration,
+    checkpoint_table: String,
+}
+
+impl Backfill {
+    /// Create new backfill operation.
+    ///
+    /// `name` is unique name used for storing checkpoint. `update` query is
+    /// run for each batch with two bind parameters where first parameter is
+    /// exclusive lower bound and second parameter is inclusive upper bound of
+    /// key column, for example `UPDATE users SET lower_name = LOWER(name)
+    /// WHERE id > $1 AND id <= $2`. By default batch size is 1000 and there is
+    /// no throttle between batches
+    #[must_use]
+    pub fn new(
+        name: impl Into<String>,
+        table: impl Into<String>,
+        key_column: impl Into<String>,
+        update: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            table: table.into(),
+            key_column: key_column.into(),
+            update: update.into(),
+            down: None,
+            batch_size: 1000,
+            throttle: Duration::ZERO,
+            checkpoint_table: checkpoint_table_name(crate::migrator::DEFAULT_TABLE_NAME),
+        }
+    }
+
+    /// Set number of rows processed in single batch. Value of 0 is treated as
+    /// 1
+    #[must_use]
+    pub fn batch_size(self, batch_size: u32) -> Self {
+        let mut backfill = self;
+        backfill.batch_size = batch_size.max(1);
+        backfill
+    }
+
+    /// Set duration to wait between batches.
+    ///
+    /// Crate doesn't depend upon any async runtime, so waiting is done by
+    /// spawning short lived OS thread for each batch which sleeps for
+    /// throttle duration and wakes task afterwards. Use batch size large
+    /// enough that cost of spawning thread is small compared to batch
+    #[must_use]
+    pub fn throttle(self, throttle: Duration) -> Self {
+        let mut backfill = self;
+        backfill.throttle = throttle;
+        backfill
+    }
+
+    /// Set table name of migrator for deriving checkpoint table name. Required
+    /// when migrator uses prefix
+    #[must_use]
+    pub fn migrator_table_name(self, migrator_table_name: &str) -> Self {
+        let mut backfill = self;
+        backfill.checkpoint_table = checkpoint_table_name(migrator_table_name);
+        backfill
+    }
+
+    /// Set query which is run once when operation is reverted. Without down
+    /// query backfill is irreversible
+    #[must_use]
+    pub fn down(self, down: impl Into<String>) -> Self {
+        let mut backfill = self;
+        backfill.down = Some(down.into());
+        backfill
+    }
+
+    /// Return name of table used for storing checkpoint
+    #[must_use]
+    pub fn checkpoint_table(&self) -> &str {
+        &self.checkpoint_table
+    }
+}
+
+impl Backfill {
+    /// Quote checkpoint table for dialect. Other migrator tables are created
+    /// without quoting name, so postgres stores them using lowercase name
+    fn quoted_checkpoint_table(&self, dialect: Dialect) -> String {
+        match dialect {
+            Dialect::Postgres => dialect.quote(&self.checkpoint_table.to_lowercase()),
+            _ => dialect.quote(&self.checkpoint_table),
+        }
+    }
+
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        dialect: Dialect,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+        for<'q> i64: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+        for<'q> String: Encode<'q, DB> + Type<DB>,
+        usize: ColumnIndex<<DB as Database>::Row>,
+    {
+        if connection.is_in_transaction() {
+            return Err(Error::BackfillError {
+                message: format!(
+                    "backfill {} can only be run inside non atomic migration",
+                    self.name
+                ),
+            });
+        }
+        let checkpoint_table = self.quoted_checkpoint_table(dialect);
+        sqlx::query(&create_checkpoint_table_query(&checkpoint_table))
+            .execute(&mut *connection)
+            .await?;
+        let checkpoint = sqlx::query_scalar::<_, i64>(&format!(
+            "SELECT last_key FROM {checkpoint_table} WHERE name = {}",
+            dialect.placeholder(1)
+        ))
+        .bind(self.name.clone())
+        .fetch_optional(&mut *connection)
+        .await?;
+        if let Some(last_key) = checkpoint {
+            tracing::info!("resuming backfill {} after key {last_key}", self.name);
+        }
+        let mut last_key = checkpoint.unwrap_or(i64::MIN);
+        let batch_end_query = format!(
+            "SELECT MAX({key}) FROM (SELECT {key} FROM {table} WHERE {key} > {placeholder} ORDER \
+             BY {key} LIMIT {batch_size}) AS backfill_batch",
+            key = dialect.quote(&self.key_column),
+            table = dialect.quote(&self.table),
+            placeholder = dialect.placeholder(1),
+            batch_size = self.batch_size
+        );
+        let delete_checkpoint_query = format!(
+            "DELETE FROM {checkpoint_table} WHERE name = {}",
+            dialect.placeholder(1)
+        );
+        let insert_checkpoint_query = format!(
+            "INSERT INTO {checkpoint_table}(name, last_key) VALUES ({}, {})",
+            dialect.placeholder(1),
+            dialect.placeholder(2)
+        );
+        let mut batch = 0_u64;
+        loop {
+            let batch_end = sqlx::query_scalar::<_, Option<i64>>(&batch_end_query)
+                .bind(last_key)
+                .fetch_one(&mut *connection)
+                .await?;
+            let Some(batch_end) = batch_end else {
+                break;
+            };
+            batch += 1;
+            let mut transaction = connection.begin().await?;
+            sqlx::query(&self.update)
+                .bind(last_key)
+                .bind(batch_end)
+                .execute(&mut *transaction)
+                .await?;
+            sqlx::query(&delete_checkpoint_query)
+                .bind(self.name.clone())
+                .execute(&mut *transaction)
+                .await?;
+            sqlx::query(&insert_checkpoint_query)
+                .bind(self.name.clone())
+                .bind(batch_end)
+                .execute(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
+            tracing::info!(
+                "backfill {} processed batch {batch} up to key {batch_end}",
+                self.name
+            );
+            last_key = batch_end;
+            if !self.throttle.is_zero() {
+                sleep(self.throttle).await;
+            }
+        }
+        sqlx::query(&delete_checkpoint_query)
+            .bind(self.name.clone())
+            .execute(&mut *connection)
+            .await?;
+        tracing::info!("backfill {} completed in {batch} batches", self.name);
+        Ok(())
+    }
+
+    async fn revert<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        dialect: Dialect,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+        for<'q> String: Encode<'q, DB> + Type<DB>,
+    {
+        let Some(down) = &self.down else {
+            return Err(Error::IrreversibleOperation);
+        };
+        sqlx::query(down).execute(&mut *connection).await?;
+        // remove checkpoint of partially completed backfill so backfill starts
+        // from beginning when applied again
+        let checkpoint_table = self.quoted_checkpoint_table(dialect);
+        sqlx::query(&create_checkpoint_table_query(&checkpoint_table))
+            .execute(&mut *connection)
+            .await?;
+        sqlx::query(&format!(
+            "DELETE FROM {checkpoint_table} WHERE name = {}",
+            dialect.placeholder(1)
+        ))
+        .bind(self.name.clone())
+        .execute(&mut *connection)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Implement [`Operation`] for backfill for database
+macro_rules! impl_backfill_operation {
+    ($feature:literal, $db:ty, $dialect:expr) => {
+        #[cfg(feature = $feature)]
+        #[async_trait::async_trait]
+        impl Operation<$db> for Backfill {
+            async fn up(
+                &self,
+                connection: &mut <$db as Database>::Connection,
+            ) -> Result<(), Error> {
+                self.run::<$db>(connection, $dialect).await
+            }
+
+            async fn down(
+                &self,
+                connection: &mut <$db as Database>::Connection,
+            ) -> Result<(), Error> {
+                self.revert::<$db>(connection, $dialect).await
+            }
+
+            fn is_reversible(&self) -> bool {
+                self.down.is_some()
+            }
+        }
+    };
+}
+
+impl_backfill_operation!("postgres", sqlx::Postgres, Dialect::Postgres);
+impl_backfill_operation!("mysql", sqlx::MySql, Dialect::MySql);
+impl_backfill_operation!("sqlite", sqlx::Sqlite, Dialect::Sqlite);
+
+#[cfg(feature = "any")]
+#[async_trait::async_trait]
+impl Operation<sqlx::Any> for Backfill {
+    async fn up(&self, connection: &mut sqlx::AnyConnection) -> Result<(), Error> {
+        let dialect = crate::schema::any_dialect(connection)?;
+        self.run::<sqlx::Any>(connection, dialect).await
+    }
+
+    async fn down(&self, connection: &mut sqlx::AnyConnection) -> Result<(), Error> {
+        let dialect = crate::schema::any_dialect(connection)?;
+        self.revert::<sqlx::Any>(connection, dialect).await
+    }
+
+    fn is_reversible(&self) -> bool {
+        self.down.is_some()
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use sqlx::Sqlite;
+
+    use super::Backfill;
+    use crate::error::Error;
+    use crate::migrator::{Info, Migrate, Migrator, Plan};
+    use crate::test_utils::sqlite_pool;
+    use crate::vec_box;
+
+    #[tokio::test]
+    async fn backfill_resume() {
+        struct A;
+        crate::sqlite_migration!(
+            A,
+            app = "test",
+            name = "a",
+            parents = vec_box![],
+            operations = vec_box![Backfill::new(
+                "double_value",
+                "numbers",
+                "id",
+                "UPDATE numbers SET doubled = value * 2 WHERE id > ? AND id <= ?"
+            )
+            .batch_size(10)
+            .throttle(std::time::Duration::from_millis(1))],
+            atomic = false
+        );
+        struct B;
+        crate::sqlite_migration!(
+            B,
+            app = "test",
+            name = "b",
+            parents = vec_box![],
+            operations = vec_box![Backfill::new(
+                "atomic",
+                "numbers",
+                "id",
+                "UPDATE numbers SET doubled = 0 WHERE id > ? AND id <= ?"
+            )]
+        );
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        sqlx::query(
+            "CREATE TABLE numbers (id INTEGER PRIMARY KEY, value INTEGER NOT NULL, doubled \
+             INTEGER)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+        for id in 1..=25 {
+            sqlx::query("INSERT INTO numbers (id, value) VALUES (?, ?)")
+                .bind(id)
+                .bind(id * 10)
+                .execute(&mut *conn)
+                .await
+                .unwrap();
+        }
+        // checkpoint left by interrupted run so rows up to checkpoint are skipped
+        sqlx::query(
+            "CREATE TABLE _sqlx_migrator_migrations_backfill (name VARCHAR(255) PRIMARY KEY NOT \
+             NULL, last_key BIGINT NOT NULL)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO _sqlx_migrator_migrations_backfill (name, last_key) VALUES \
+             ('double_value', 10)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box![A]);
+        migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+        let (skipped, doubled) = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT COUNT(*) FILTER (WHERE doubled IS NULL), COUNT(*) FILTER (WHERE doubled = \
+             value * 2) FROM numbers",
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+        assert_eq!((skipped, doubled), (10, 15));
+        let checkpoints =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM _sqlx_migrator_migrations_backfill")
+                .fetch_one(&mut *conn)
+                .await
+                .unwrap();
+        assert_eq!(checkpoints, 0);
+
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box![B]);
+        let result = migrator.run(&mut conn, &Plan::apply_all()).await;
+        assert!(matches!(result, Err(Error::BackfillError { .. })));
+    }
+
+    #[tokio::test]
+    async fn backfill_quote_identifiers() {
+        struct A;
+        crate::sqlite_migration!(
+            A,
+            app = "test",
+            name = "a",
+            parents = vec_box![],
+            operations = vec_box![Backfill::new(
+                "reserved",
+                "order",
+                "group",
+                "UPDATE \"order\" SET total = 1 WHERE \"group\" > ? AND \"group\" <= ?"
+            )
+            .migrator_table_name("migrator table")],
+            atomic = false
+        );
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        sqlx::query("CREATE TABLE \"order\" (\"group\" INTEGER PRIMARY KEY, total INTEGER)")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO \"order\" (\"group\") VALUES (1), (2)")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box![A]);
+        migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+        let total = sqlx::query_scalar::<_, i64>("SELECT SUM(total) FROM \"order\"")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(total, 2);
+        let checkpoints =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM \"migrator table_backfill\"")
+                .fetch_one(&mut *conn)
+                .await
+                .unwrap();
+        assert_eq!(checkpoints, 0);
+    }
+}
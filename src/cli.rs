@@ -21,17 +21,29 @@
 //!     Migrator(sqlx_migrator::cli::MigrationCommand),
 //! }
 //! ```
-use std::io::Write;
+use std::future::Future;
+use std::io::{IsTerminal, Write};
+use std::time::Duration;
 
 use clap::{Parser, Subcommand};
+use serde::Serialize;
 use sqlx::Database;
 
 use crate::error::Error;
-use crate::migrator::{Migrate, Plan};
+use crate::graph::{EdgeKind, MigrationGraph};
+use crate::import::{ImportSource, ImportStatus, Importer};
+use crate::migration::{AppliedMigrationSqlRow, Migration};
+use crate::migrator::{is_reversible, Migrate, Plan};
+use crate::sleep::sleep;
+use crate::snapshot;
+use crate::squash::SquashedMigration;
+use crate::tenant::{FailurePolicy, TenantOutcome, TenantRunner, Tenants};
 
 /// Migration command for performing rust based sqlx migrations
 #[derive(Parser, Debug)]
 pub struct MigrationCommand {
+    #[command(flatten)]
+    connect_options: ConnectOptions,
     #[command(subcommand)]
     sub_command: SubCommand,
 }
@@ -61,14 +73,152 @@ impl MigrationCommand {
         connection: &mut <DB as Database>::Connection,
         migrator: Box<dyn Migrate<DB>>,
     ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        self.run_with_prompt(connection, migrator, &StdinPrompt)
+            .await
+    }
+
+    /// Run migration command line interface using custom prompt for
+    /// confirmation of destructible and revert migrations
+    ///
+    /// # Errors
+    /// If migration command fails to complete and raise some issue
+    pub async fn run_with_prompt<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+        prompt: &dyn Prompt,
+    ) -> Result<(), Error>
     where
         DB: Database,
     {
         self.sub_command
-            .handle_subcommand(migrator, connection)
+            .handle_subcommand(migrator, connection, prompt, &self.connect_options)
             .await?;
         Ok(())
     }
+
+    /// Parse [`MigrationCommand`], connect to database using database url
+    /// and run migration command line interface
+    ///
+    /// # Errors
+    /// If database url is not provided, connection to database fails or
+    /// migration command fails to complete and raise some issue
+    pub async fn parse_and_run_with_url<DB>(migrator: Box<dyn Migrate<DB>>) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let migration_command = Self::parse();
+        migration_command.run_with_url(migrator).await
+    }
+
+    /// Connect to database using `--database-url` or `DATABASE_URL`
+    /// environment variable and run migration command line interface. Default
+    /// drivers are installed when database is [`sqlx::Any`]
+    ///
+    /// # Errors
+    /// If database url is not provided, connection to database fails or
+    /// migration command fails to complete and raise some issue
+    pub async fn run_with_url<DB>(&self, migrator: Box<dyn Migrate<DB>>) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let pool = self.connect_options.connect::<DB>(1).await?;
+        let mut connection = pool.acquire().await?;
+        let result = self.run(&mut *connection, migrator).await;
+        drop(connection);
+        pool.close().await;
+        result
+    }
+}
+
+/// Options used for connecting to database using database url
+#[derive(clap::Args, Debug)]
+struct ConnectOptions {
+    /// Database url used by `parse_and_run_with_url` and `run_with_url`.
+    /// Falls back to `DATABASE_URL` environment variable when not provided
+    #[arg(long, global = true)]
+    database_url: Option<String>,
+    /// Number of seconds to wait for each connection attempt
+    #[arg(long, global = true, default_value_t = 30)]
+    connect_timeout: u64,
+    /// Number of times to retry connecting to database after failed attempt.
+    /// Delay between retries starts at 1 second and doubles after each retry
+    /// up to 30 seconds
+    #[arg(long, global = true, default_value_t = 0)]
+    connect_retries: u32,
+}
+
+impl ConnectOptions {
+    /// Return database url from options or `DATABASE_URL` environment
+    /// variable
+    fn database_url(&self) -> Result<String, Error> {
+        match &self.database_url {
+            Some(database_url) => Ok(database_url.clone()),
+            None => std::env::var("DATABASE_URL").map_err(|_| Error::DatabaseUrlNotProvided),
+        }
+    }
+
+    /// Create pool for database url. Each attempt waits for connect timeout
+    /// and failed attempt is retried connect retries times. Default drivers
+    /// are installed when database is [`sqlx::Any`]
+    async fn connect<DB>(&self, max_connections: u32) -> Result<sqlx::Pool<DB>, Error>
+    where
+        DB: Database,
+    {
+        let database_url = self.database_url()?;
+        #[cfg(feature = "any")]
+        if DB::NAME == sqlx::Any::NAME {
+            sqlx::any::install_default_drivers();
+        }
+        retry_connection(self.connect_retries, CONNECT_RETRY_DELAY, || {
+            async {
+                Ok(sqlx::pool::PoolOptions::<DB>::new()
+                    .max_connections(max_connections)
+                    .acquire_timeout(Duration::from_secs(self.connect_timeout))
+                    .connect(&database_url)
+                    .await?)
+            }
+        })
+        .await
+    }
+}
+
+/// Delay before first retry of connecting to database
+const CONNECT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Maximum delay between retries of connecting to database
+const CONNECT_RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Run connect until it succeeds, fails with error which is not connection
+/// error or retries are exhausted. Delay between attempts starts at provided
+/// delay and doubles after each retry up to [`CONNECT_RETRY_MAX_DELAY`]
+async fn retry_connection<T, F, Fut>(retries: u32, delay: Duration, connect: F) -> Result<T, Error>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut attempt = 0;
+    let mut delay = delay;
+    loop {
+        match connect().await {
+            Ok(value) => return Ok(value),
+            Err(error) => {
+                if attempt >= retries || !error.is_connection_error() {
+                    return Err(error);
+                }
+                attempt += 1;
+                tracing::warn!(
+                    "failed to connect to database: {error}, retrying in {delay:?} \
+                     ({attempt}/{retries})"
+                );
+                sleep(delay).await;
+                delay = (delay * 2).min(CONNECT_RETRY_MAX_DELAY);
+            }
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -80,13 +230,58 @@ enum SubCommand {
     /// reverted else raises error
     #[command()]
     Drop,
+    /// Compare database schema against committed snapshot to detect changes
+    /// applied directly to database without migration
+    #[command()]
+    Drift(Drift),
+    /// Render dependency graph of migrations as Graphviz DOT or Mermaid
+    #[command()]
+    Graph(Graph),
+    /// Import migrations applied by other migration tool such as sqlx,
+    /// refinery or diesel as applied migrations
+    #[command()]
+    Import(Import),
     /// List migrations along with their status and time applied if migrations
     /// is already applied
     #[command()]
-    List,
+    List(List),
+    /// Mark migration as applied without running its operations and without
+    /// checking plan. Warns when its parents are not applied
+    #[command()]
+    MarkApplied(MarkApplied),
+    /// Create new migration file for app with latest migrations of app as
+    /// parents and add it to migration list of app
+    #[command()]
+    New(New),
     /// Revert migrations
     #[command()]
     Revert(Revert),
+    /// Show parents, children, replaces, run before relations, operations and
+    /// applied status of migration
+    #[command()]
+    Show(Show),
+    /// Write snapshot of database schema for currently applied migrations.
+    /// Run after apply and commit snapshot alongside migrations
+    #[command()]
+    Snapshot(Snapshot),
+    /// Squash consecutive migrations of app into new migration which replaces
+    /// them. Only migrations whose operations provide SQL can be squashed
+    #[command()]
+    Squash(Squash),
+    /// Report count of applied, pending, replaced and unknown migrations for
+    /// each app. Exits with distinct code when unknown migrations, pending
+    /// migrations or schema drift is present
+    #[command()]
+    Status(Status),
+    /// Run migrations for multiple tenants where each tenant is a schema
+    /// containing same migrations
+    #[command(subcommand)]
+    Tenants(TenantsSubCommand),
+    /// Remove migration from applied migrations without reverting its
+    /// operations and without checking plan. Warns when its children are
+    /// applied
+    #[command()]
+    Unmark(Unmark),
 }
 
 impl SubCommand {
@@ -94,20 +289,80 @@ impl SubCommand {
         &self,
         migrator: Box<dyn Migrate<DB>>,
         connection: &mut <DB as Database>::Connection,
+        prompt: &dyn Prompt,
+        connect_options: &ConnectOptions,
     ) -> Result<(), Error>
     where
         DB: Database,
     {
         match self {
-            SubCommand::Apply(apply) => apply.run(connection, migrator).await?,
+            SubCommand::Apply(apply) => apply.run(connection, migrator, prompt).await?,
             SubCommand::Drop => drop_migrations(connection, migrator).await?,
-            SubCommand::List => list_migrations(connection, migrator).await?,
-            SubCommand::Revert(revert) => revert.run(connection, migrator).await?,
+            SubCommand::Drift(drift) => drift.run(connection, migrator).await?,
+            SubCommand::Graph(graph) => graph.run(connection, migrator).await?,
+            SubCommand::Import(import) => import.run(connection, migrator).await?,
+            SubCommand::List(list) => list.run(connection, migrator).await?,
+            SubCommand::MarkApplied(mark_applied) => mark_applied.run(connection, migrator).await?,
+            SubCommand::New(new) => new.run(connection, migrator).await?,
+            SubCommand::Revert(revert) => revert.run(connection, migrator, prompt).await?,
+            SubCommand::Snapshot(snapshot) => snapshot.run(connection, migrator).await?,
+            SubCommand::Show(show) => show.run(connection, migrator).await?,
+            SubCommand::Squash(squash) => squash.run(connection, migrator).await?,
+            SubCommand::Status(status) => status.run(connection, migrator).await?,
+            SubCommand::Tenants(tenants) => {
+                tenants
+                    .run(connection, migrator, prompt, connect_options)
+                    .await?;
+            }
+            SubCommand::Unmark(unmark) => unmark.run(connection, migrator).await?,
         }
         Ok(())
     }
 }
 
+/// Prompt used by cli to confirm applying destructible migrations and
+/// reverting migrations. Custom prompt can be passed to
+/// [`MigrationCommand::run_with_prompt`] to ask confirmation from TUI or web
+/// interface
+#[async_trait::async_trait]
+pub trait Prompt: Send + Sync {
+    /// Ask confirmation for question. Migrations is list of app and name of
+    /// migrations which will be affected. Returns whether action is confirmed
+    ///
+    /// # Errors
+    /// If confirmation cannot be asked or read
+    async fn confirm(&self, question: &str, migrations: &[(String, String)])
+        -> Result<bool, Error>;
+}
+
+/// Default prompt which reads confirmation from stdin. Refuses confirmation
+/// with [`Error::NonInteractivePrompt`] instead of waiting for input when
+/// stdin is not terminal
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdinPrompt;
+
+#[async_trait::async_trait]
+impl Prompt for StdinPrompt {
+    async fn confirm(
+        &self,
+        question: &str,
+        migrations: &[(String, String)],
+    ) -> Result<bool, Error> {
+        if !std::io::stdin().is_terminal() {
+            return Err(Error::NonInteractivePrompt);
+        }
+        println!("{question} (y/N)");
+        for (position, (app, name)) in migrations.iter().enumerate() {
+            println!("{position}. {app} : {name}");
+        }
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        let input_trimmed = input.trim().to_ascii_lowercase();
+        Ok(["y", "yes"].contains(&input_trimmed.as_str()))
+    }
+}
+
 async fn drop_migrations<DB>(
     connection: &mut <DB as Database>::Connection,
     migrator: Box<dyn Migrate<DB>>,
@@ -128,67 +383,481 @@ where
     Ok(())
 }
 
-async fn list_migrations<DB>(
+/// Output format of migration list and plan
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+enum OutputFormat {
+    /// Human readable table
+    #[default]
+    Table,
+    /// JSON array of migrations
+    Json,
+    /// CSV with header row
+    Csv,
+}
+
+/// Status of migration present in list or plan
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum MigrationStatus {
+    Applied,
+    Pending,
+    Replaced,
+}
+
+impl MigrationStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            MigrationStatus::Applied => "applied",
+            MigrationStatus::Pending => "pending",
+            MigrationStatus::Replaced => "replaced",
+        }
+    }
+
+    fn glyph(self) -> &'static str {
+        match self {
+            MigrationStatus::Applied => "\u{2713}",
+            MigrationStatus::Pending => "\u{2717}",
+            MigrationStatus::Replaced => "\u{2194}",
+        }
+    }
+}
+
+/// Row of list and plan output. Fields are part of JSON and CSV output so
+/// existing fields should not be renamed or removed
+#[derive(Debug, Serialize)]
+struct MigrationRow {
+    app: String,
+    name: String,
+    status: MigrationStatus,
+    id: Option<i32>,
+    applied_time: Option<String>,
+    atomic: bool,
+    destructible: bool,
+    reversible: bool,
+    replaced_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasons: Option<Vec<String>>,
+}
+
+impl MigrationRow {
+    /// Create rows for migrations. Migration which is neither applied nor
+    /// pending is replaced by other migration
+    #[expect(clippy::borrowed_box)]
+    fn from_migrations<DB>(
+        migrator: &dyn Migrate<DB>,
+        migrations: &[&Box<dyn Migration<DB>>],
+        applied_migrations: &[AppliedMigrationSqlRow],
+        pending_migrations: &[&Box<dyn Migration<DB>>],
+    ) -> Vec<Self>
+    where
+        DB: Database,
+    {
+        migrations
+            .iter()
+            .map(|&migration| {
+                let applied = applied_migrations
+                    .iter()
+                    .find(|&applied_migration| applied_migration == migration);
+                let status = if applied.is_some() {
+                    MigrationStatus::Applied
+                } else if pending_migrations.contains(&migration) {
+                    MigrationStatus::Pending
+                } else {
+                    MigrationStatus::Replaced
+                };
+                let replaced_by = migrator
+                    .migrations()
+                    .iter()
+                    .find(|replacing| replacing.replaces().contains(migration))
+                    .map(|replacing| format!("{}:{}", replacing.app(), replacing.name()));
+                Self {
+                    app: migration.app().to_string(),
+                    name: migration.name().to_string(),
+                    status,
+                    id: applied.map(AppliedMigrationSqlRow::id),
+                    applied_time: applied.map(|row| row.applied_time().to_string()),
+                    atomic: migration.is_atomic(),
+                    destructible: migration
+                        .operations()
+                        .iter()
+                        .any(|operation| operation.is_destructible()),
+                    reversible: is_reversible(migration),
+                    replaced_by,
+                    reasons: None,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Column of table output
+#[derive(Clone, Copy)]
+enum Column {
+    Id,
+    App,
+    Name,
+    Status,
+    AppliedTime,
+    Reversible,
+    Reasons,
+}
+
+impl Column {
+    fn header(self) -> &'static str {
+        match self {
+            Column::Id => "ID",
+            Column::App => "App",
+            Column::Name => "Name",
+            Column::Status => "Status",
+            Column::AppliedTime => "Applied time",
+            Column::Reversible => "Reversible",
+            Column::Reasons => "Reasons",
+        }
+    }
+
+    fn value(self, row: &MigrationRow) -> String {
+        match self {
+            Column::Id => {
+                row.id
+                    .map_or_else(|| "N/A".to_string(), |id| id.to_string())
+            }
+            Column::App => row.app.clone(),
+            Column::Name => row.name.clone(),
+            Column::Status => row.status.glyph().to_string(),
+            Column::AppliedTime => {
+                row.applied_time
+                    .clone()
+                    .unwrap_or_else(|| "N/A".to_string())
+            }
+            Column::Reversible => {
+                if row.reversible {
+                    "\u{2713}".to_string()
+                } else {
+                    "\u{2717}".to_string()
+                }
+            }
+            Column::Reasons => row.reasons.clone().unwrap_or_default().join("; "),
+        }
+    }
+}
+
+/// Add reasons of plan to rows created from same plan
+async fn add_plan_reasons<DB>(
+    rows: &mut [MigrationRow],
+    migrator: &dyn Migrate<DB>,
     connection: &mut <DB as Database>::Connection,
-    migrator: Box<dyn Migrate<DB>>,
+    plan: &Plan,
 ) -> Result<(), Error>
 where
     DB: Database,
 {
-    let migration_plan = migrator.generate_migration_plan(connection, None).await?;
-
-    let apply_plan = migrator
-        .generate_migration_plan(connection, Some(&Plan::apply_all()))
-        .await?;
-    let applied_migrations = migrator.fetch_applied_migration_from_db(connection).await?;
-
-    let widths = [5, 10, 50, 10, 40];
-    let full_width = widths.iter().sum::<usize>() + widths.len() * 3;
-
-    let first_width = widths[0];
-    let second_width = widths[1];
-    let third_width = widths[2];
-    let fourth_width = widths[3];
-    let fifth_width = widths[4];
-
-    println!(
-        "{:^first_width$} | {:^second_width$} | {:^third_width$} | {:^fourth_width$} | \
-         {:^fifth_width$}",
-        "ID", "App", "Name", "Status", "Applied time"
-    );
-
-    println!("{:^full_width$}", "-".repeat(full_width));
-    for migration in migration_plan {
-        let mut id = String::from("N/A");
-        let mut status = "\u{2717}";
-        let mut applied_time = String::from("N/A");
-
-        let find_applied_migrations = applied_migrations
-            .iter()
-            .find(|&applied_migration| applied_migration == migration);
+    let explained = migrator.explain_migration_plan(connection, plan).await?;
+    for (row, (_, reasons)) in rows.iter_mut().zip(explained) {
+        row.reasons = Some(reasons.iter().map(ToString::to_string).collect());
+    }
+    Ok(())
+}
+
+/// Quote CSV field if it contains separator, quote or new line
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
 
-        if let Some(sqlx_migration) = find_applied_migrations {
-            id = sqlx_migration.id().to_string();
-            status = "\u{2713}";
-            applied_time = sqlx_migration.applied_time().to_string();
-        } else if !apply_plan
+/// Render table with centered cells where each column is as wide as its header
+/// and longest value
+fn render_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let widths = headers
+        .iter()
+        .enumerate()
+        .map(|(index, header)| {
+            rows.iter()
+                .filter_map(|row| row.get(index))
+                .map(|cell| cell.chars().count())
+                .chain([header.chars().count()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    let full_width = widths
+        .iter()
+        .map(|width| width + 3)
+        .sum::<usize>()
+        .saturating_sub(3);
+    let line = |cells: &[String]| {
+        cells
             .iter()
-            .any(|&plan_migration| plan_migration == migration)
-        {
-            status = "\u{2194}";
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:^width$}"))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    };
+    let mut lines = vec![
+        line(&headers.iter().map(ToString::to_string).collect::<Vec<_>>()),
+        "-".repeat(full_width),
+    ];
+    for row in rows {
+        lines.push(line(row));
+    }
+    lines.join("\n")
+}
+
+/// Render rows in provided format. Columns are only used for table format
+/// whereas JSON and CSV format always contains all fields. Empty message is
+/// rendered instead of table when there are no rows
+fn render_migration_rows(
+    rows: &[MigrationRow],
+    format: OutputFormat,
+    columns: &[Column],
+    empty_message: &str,
+) -> Result<String, Error> {
+    let mut lines = vec![];
+    match format {
+        OutputFormat::Table => {
+            if rows.is_empty() {
+                return Ok(empty_message.to_string());
+            }
+            let headers = columns
+                .iter()
+                .map(|column| column.header())
+                .collect::<Vec<_>>();
+            let cells = rows
+                .iter()
+                .map(|row| columns.iter().map(|column| column.value(row)).collect())
+                .collect::<Vec<_>>();
+            lines.push(render_table(&headers, &cells));
+        }
+        OutputFormat::Json => {
+            lines.push(
+                serde_json::to_string_pretty(rows).map_err(|error| Error::Box(Box::new(error)))?,
+            );
+        }
+        OutputFormat::Csv => {
+            let explained = rows.iter().any(|row| row.reasons.is_some());
+            let reasons_header = if explained { ",reasons" } else { "" };
+            lines.push(format!(
+                "app,name,status,id,applied_time,atomic,destructible,reversible,\
+                 replaced_by{reasons_header}"
+            ));
+            for row in rows {
+                let reasons = row
+                    .reasons
+                    .as_ref()
+                    .map(|reasons| format!(",{}", csv_field(&reasons.join("; "))))
+                    .unwrap_or_default();
+                lines.push(format!(
+                    "{},{},{},{},{},{},{},{},{}{reasons}",
+                    csv_field(&row.app),
+                    csv_field(&row.name),
+                    row.status.as_str(),
+                    row.id.map(|id| id.to_string()).unwrap_or_default(),
+                    csv_field(row.applied_time.as_deref().unwrap_or_default()),
+                    row.atomic,
+                    row.destructible,
+                    row.reversible,
+                    csv_field(row.replaced_by.as_deref().unwrap_or_default()),
+                ));
+            }
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+#[derive(Parser, Debug)]
+struct List {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+}
+
+impl List {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        println!("{}", self.output(connection, migrator.as_ref()).await?);
+        Ok(())
+    }
+
+    async fn output<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: &dyn Migrate<DB>,
+    ) -> Result<String, Error>
+    where
+        DB: Database,
+    {
+        let migration_plan = migrator.generate_migration_plan(connection, None).await?;
+
+        let apply_plan = migrator
+            .generate_migration_plan(connection, Some(&Plan::apply_all()))
+            .await?;
+        let applied_migrations = migrator.fetch_applied_migration_from_db(connection).await?;
+
+        let rows = MigrationRow::from_migrations(
+            migrator,
+            &migration_plan,
+            &applied_migrations,
+            &apply_plan,
+        );
+        render_migration_rows(
+            &rows,
+            self.format,
+            &[
+                Column::Id,
+                Column::App,
+                Column::Name,
+                Column::Status,
+                Column::AppliedTime,
+            ],
+            "No migration exists",
+        )
+    }
+}
+
+/// Format of rendered migration graph
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+enum GraphFormat {
+    /// Graphviz DOT
+    #[default]
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
+#[derive(Parser, Debug)]
+struct Graph {
+    /// Format of rendered graph
+    #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+    format: GraphFormat,
+    /// Colour migrations according to applied status present in database
+    #[arg(long)]
+    status: bool,
+}
+
+impl Graph {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let mut graph = MigrationGraph::from_migrations(migrator.migrations());
+        if self.status {
+            migrator.ensure_migration_table_exists(connection).await?;
+            let applied_migrations = migrator.fetch_applied_migration_from_db(connection).await?;
+            graph.set_applied(&applied_migrations);
+        }
+        match self.format {
+            GraphFormat::Dot => println!("{}", graph.to_dot()),
+            GraphFormat::Mermaid => println!("{}", graph.to_mermaid()),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+struct Import {
+    /// Mapping of version of other migration tool to migration in format
+    /// `VERSION=APP:NAME`. Can be used multiple times
+    #[arg(long = "map", value_parser = parse_import_mapping)]
+    mappings: Vec<(String, String, String)>,
+    /// File containing mapping with one `VERSION=APP:NAME` mapping per line.
+    /// Empty lines and lines starting with `#` are ignored
+    #[arg(long)]
+    map_file: Option<std::path::PathBuf>,
+    /// Only show how migrations will be imported without importing them
+    #[arg(long)]
+    preview: bool,
+    /// Migration tool from which migrations are imported. Supported values are
+    /// sqlx, refinery and diesel
+    #[arg(long)]
+    source: ImportSource,
+}
+
+fn parse_import_mapping(value: &str) -> Result<(String, String, String), String> {
+    let parse = || {
+        let (version, migration) = value.split_once('=')?;
+        let (app, name) = migration.split_once(':')?;
+        Some((
+            version.trim().to_string(),
+            app.trim().to_string(),
+            name.trim().to_string(),
+        ))
+    };
+    parse().ok_or_else(|| format!("invalid mapping {value}, expected VERSION=APP:NAME"))
+}
+
+impl Import {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let mut importer = Importer::new(migrator.as_ref(), self.source);
+        if let Some(map_file) = &self.map_file {
+            let content = std::fs::read_to_string(map_file)?;
+            for line in content.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let (version, app, name) =
+                    parse_import_mapping(line).map_err(|message| Error::ImportError { message })?;
+                importer = importer.map(version, &app, &name);
+            }
         }
+        for (version, app, name) in &self.mappings {
+            importer = importer.map(version, app, name);
+        }
+        let entries = if self.preview {
+            importer.preview(connection).await?
+        } else {
+            importer.import(connection).await?
+        };
 
+        let cells = entries
+            .iter()
+            .map(|entry| {
+                let migration = entry.migration().map_or_else(
+                    || String::from("N/A"),
+                    |(app, name)| format!("{app}:{name}"),
+                );
+                let status = match (entry.status(), self.preview) {
+                    (ImportStatus::Import, true) => "will import",
+                    (ImportStatus::Import, false) => "imported",
+                    (ImportStatus::AlreadyApplied, _) => "already applied",
+                    (ImportStatus::Unmapped, _) => "unmapped",
+                    (ImportStatus::UnknownMigration, _) => "unknown migration",
+                };
+                vec![
+                    entry.version().to_string(),
+                    entry.applied_time().unwrap_or("N/A").to_string(),
+                    migration,
+                    status.to_string(),
+                ]
+            })
+            .collect::<Vec<_>>();
         println!(
-            "{:^first_width$} | {:^second_width$} | {:^third_width$} | {:^fourth_width$} | \
-             {:^fifth_width$}",
-            id,
-            migration.app(),
-            migration.name(),
-            status,
-            applied_time
+            "{}",
+            render_table(&["Version", "Applied time", "Migration", "Status"], &cells)
         );
+        if !self.preview {
+            println!("Successfully imported migrations");
+        }
+        Ok(())
     }
-    Ok(())
 }
 
 #[derive(Parser, Debug)]
@@ -209,7 +878,7 @@ struct Apply {
     fake: bool,
     /// Force run apply operation without asking question if migration is
     /// destructible
-    #[arg(long)]
+    #[arg(long, visible_alias = "yes")]
     force: bool,
     /// Apply migration till provided migration. Requires app options to be
     /// present
@@ -218,25 +887,24 @@ struct Apply {
     /// Show plan
     #[arg(long)]
     plan: bool,
+    /// Output format of plan
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table, requires = "plan")]
+    format: OutputFormat,
+    /// Show why each migration is present in plan
+    #[arg(long, requires = "plan")]
+    explain: bool,
 }
 impl Apply {
     async fn run<DB>(
         &self,
         connection: &mut <DB as Database>::Connection,
         migrator: Box<dyn Migrate<DB>>,
+        prompt: &dyn Prompt,
     ) -> Result<(), Error>
     where
         DB: Database,
     {
-        let plan;
-        if let Some(count) = self.count {
-            plan = Plan::apply_count(count);
-        } else if let Some(app) = &self.app {
-            plan = Plan::apply_name(app, &self.migration);
-        } else {
-            plan = Plan::apply_all();
-        }
-        let plan = plan.fake(self.fake);
+        let plan = self.plan();
         let migrations = migrator
             .generate_migration_plan(connection, Some(&plan))
             .await?;
@@ -244,41 +912,25 @@ impl Apply {
             return Err(Error::PendingMigrationPresent);
         }
         if self.plan {
-            if migrations.is_empty() {
-                println!("No migration exists for applying");
-            } else {
-                let first_width = 10;
-                let second_width = 50;
-                let full_width = first_width + second_width + 3;
-                println!("{:^first_width$} | {:^second_width$}", "App", "Name");
-                println!("{:^full_width$}", "-".repeat(full_width));
-                for migration in migrations {
-                    println!(
-                        "{:^first_width$} | {:^second_width$}",
-                        migration.app(),
-                        migration.name(),
-                    );
-                }
-            }
+            let output = self
+                .plan_output(connection, migrator.as_ref(), &plan, &migrations)
+                .await?;
+            println!("{output}");
         } else {
             let destructible_migrations = migrations
                 .iter()
                 .filter(|m| m.operations().iter().any(|o| o.is_destructible()))
+                .copied()
                 .collect::<Vec<_>>();
             if !self.force && !destructible_migrations.is_empty() && !self.fake {
-                let mut input = String::new();
-                println!(
-                    "Do you want to apply destructible migrations {} (y/N)",
+                let question = format!(
+                    "Do you want to apply destructible migrations {}",
                     destructible_migrations.len()
                 );
-                for (position, migration) in destructible_migrations.iter().enumerate() {
-                    println!("{position}. {} : {}", migration.app(), migration.name());
-                }
-                std::io::stdout().flush()?;
-                std::io::stdin().read_line(&mut input)?;
-                let input_trimmed = input.trim().to_ascii_lowercase();
-                // If answer is not y or yes then return
-                if !["y", "yes"].contains(&input_trimmed.as_str()) {
+                if !prompt
+                    .confirm(&question, &migration_keys(&destructible_migrations))
+                    .await?
+                {
                     return Ok(());
                 }
             }
@@ -287,26 +939,365 @@ impl Apply {
         }
         Ok(())
     }
+
+    fn plan(&self) -> Plan {
+        let plan;
+        if let Some(count) = self.count {
+            plan = Plan::apply_count(count);
+        } else if let Some(app) = &self.app {
+            plan = Plan::apply_name(app, &self.migration);
+        } else {
+            plan = Plan::apply_all();
+        }
+        plan.fake(self.fake)
+    }
+
+    #[expect(clippy::borrowed_box)]
+    async fn plan_output<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: &dyn Migrate<DB>,
+        plan: &Plan,
+        migrations: &[&Box<dyn Migration<DB>>],
+    ) -> Result<String, Error>
+    where
+        DB: Database,
+    {
+        let applied_migrations = migrator.fetch_applied_migration_from_db(connection).await?;
+        let mut rows =
+            MigrationRow::from_migrations(migrator, migrations, &applied_migrations, migrations);
+        let mut columns = vec![Column::App, Column::Name];
+        if self.explain {
+            add_plan_reasons(&mut rows, migrator, connection, plan).await?;
+            columns.push(Column::Reasons);
+        }
+        render_migration_rows(
+            &rows,
+            self.format,
+            &columns,
+            "No migration exists for applying",
+        )
+    }
 }
 
 #[derive(Parser, Debug)]
-#[expect(clippy::struct_excessive_bools)]
-struct Revert {
-    /// Revert all migration. Conflicts with app args
-    #[arg(long, conflicts_with = "app")]
-    all: bool,
-    /// Revert migration till app migrations is reverted. If it is present
-    /// alongside migration options than only till migration is reverted
-    #[arg(long)]
-    app: Option<String>,
-    /// Number of migration to revert. Conflicts with all and app args
-    #[arg(long, conflicts_with_all = ["all", "app"])]
-    count: Option<usize>,
-    /// Make migration reverted without running revert operation
-    #[arg(long)]
-    fake: bool,
-    /// Force run revert operation without asking question
-    #[arg(long)]
+struct MarkApplied {
+    /// App of migration
+    app: String,
+    /// Name of migration
+    name: String,
+}
+
+impl MarkApplied {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let warnings = migrator
+            .mark_applied(connection, &self.app, &self.name)
+            .await?;
+        println!("Marked {}:{} as applied", self.app, self.name);
+        print_warnings(&warnings);
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+struct Unmark {
+    /// App of migration
+    app: String,
+    /// Name of migration
+    name: String,
+}
+
+impl Unmark {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let warnings = migrator
+            .unmark_applied(connection, &self.app, &self.name)
+            .await?;
+        println!("Unmarked {}:{} as applied", self.app, self.name);
+        print_warnings(&warnings);
+        Ok(())
+    }
+}
+
+/// Return app and name of migrations which are passed to prompt
+#[expect(clippy::borrowed_box)]
+fn migration_keys<DB>(migrations: &[&Box<dyn Migration<DB>>]) -> Vec<(String, String)> {
+    migrations
+        .iter()
+        .map(|migration| (migration.app().to_string(), migration.name().to_string()))
+        .collect()
+}
+
+fn print_warnings(warnings: &[String]) {
+    for warning in warnings {
+        println!("Warning: {warning}");
+    }
+}
+
+#[derive(Parser, Debug)]
+struct New {
+    /// App of new migration
+    app: String,
+    /// Name of new migration without number prefix
+    name: String,
+    /// Directory containing migrations of app
+    #[arg(long, default_value = "src/migrations")]
+    dir: std::path::PathBuf,
+    /// Create up and down SQL file pair instead of rust file
+    #[arg(long)]
+    sql: bool,
+}
+
+impl New {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        validate_migration_name(&self.name)?;
+        // plan cannot be generated when migrator doesn't contain any migration
+        let parents = if migrator.migrations().is_empty() {
+            vec![]
+        } else {
+            let plan = migrator.generate_migration_plan(connection, None).await?;
+            leaf_migrations(migrator.as_ref(), &plan, &self.app)
+        };
+
+        std::fs::create_dir_all(&self.dir)?;
+        let number = next_migration_number(&self.dir)?;
+        if self.sql {
+            let name = format!("{number:04}_{}", self.name);
+            let parents = parents
+                .iter()
+                .map(|(app, name)| format!("{app}:{name}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let up = if parents.is_empty() {
+                format!("-- app: {}\n", self.app)
+            } else {
+                format!("-- app: {}\n-- parents: {parents}\n", self.app)
+            };
+            let up_path = self.dir.join(format!("{name}.up.sql"));
+            let down_path = self.dir.join(format!("{name}.down.sql"));
+            write_new_file(&up_path, &up)?;
+            write_new_file(&down_path, "")?;
+            println!("Created {}", up_path.display());
+            println!("Created {}", down_path.display());
+        } else {
+            let module = format!("m{number:04}_{}", self.name);
+            let struct_name = format!("M{number:04}Migration");
+            let database = database_type::<DB>()?;
+            let parents = parents
+                .iter()
+                .map(|(app, name)| format!("({app:?}, {name:?})"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let content = format!(
+                "pub(crate) struct {struct_name};\n\nsqlx_migrator::migration!(\n    \
+                 {database},\n    {struct_name},\n    app = {app:?},\n    name = {module:?},\n    \
+                 parents = sqlx_migrator::vec_box![{parents}],\n    operations = \
+                 sqlx_migrator::vec_box![(\"-- up SQL\", \"-- down SQL\")]\n);\n",
+                app = self.app,
+            );
+            let path = self.dir.join(format!("{module}.rs"));
+            write_new_file(&path, &content)?;
+            println!("Created {}", path.display());
+            register_migration_file(&self.dir, &module, &struct_name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Validate name of migration is non empty and only contains ascii
+/// alphanumeric and underscore character so it can be used as module name
+fn validate_migration_name(name: &str) -> Result<(), Error> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| char::is_ascii_alphanumeric(&c) || c == '_')
+    {
+        return Err(Error::NonAsciiAlphaNumeric);
+    }
+    Ok(())
+}
+
+/// Return type path of database used in generated rust source
+fn database_type<DB>() -> Result<&'static str, Error>
+where
+    DB: Database,
+{
+    match <DB as Database>::NAME {
+        "PostgreSQL" => Ok("sqlx::Postgres"),
+        "SQLite" => Ok("sqlx::Sqlite"),
+        "MySQL" => Ok("sqlx::MySql"),
+        "Any" => Ok("sqlx::Any"),
+        _ => Err(Error::UnsupportedDatabase),
+    }
+}
+
+/// Register migration module in `mod.rs` present in directory else print
+/// message to register it manually
+fn register_migration_file(
+    dir: &std::path::Path,
+    module: &str,
+    struct_name: &str,
+) -> Result<(), Error> {
+    let mod_path = dir.join("mod.rs");
+    if mod_path.is_file() {
+        let mod_content = std::fs::read_to_string(&mod_path)?;
+        if let Some(updated) = register_module(&mod_content, module, struct_name) {
+            std::fs::write(&mod_path, updated)?;
+            println!("Updated {}", mod_path.display());
+            return Ok(());
+        }
+    }
+    println!("Add `mod {module};` and `{module}::{struct_name}` to migration list manually");
+    Ok(())
+}
+
+/// Return app and name of migrations of app which are not parent of any other
+/// migration of app. Replaced migrations are ignored
+#[expect(clippy::borrowed_box)]
+fn leaf_migrations<DB>(
+    migrator: &dyn Migrate<DB>,
+    plan: &[&Box<dyn Migration<DB>>],
+    app: &str,
+) -> Vec<(String, String)>
+where
+    DB: Database,
+{
+    let migrations = plan
+        .iter()
+        .filter(|migration| migration.app() == app)
+        .filter(|&&migration| {
+            !migrator
+                .migrations()
+                .iter()
+                .any(|replacing| replacing.replaces().contains(migration))
+        })
+        .collect::<Vec<_>>();
+    migrations
+        .iter()
+        .filter(|&&&migration| {
+            !migrations
+                .iter()
+                .any(|child| child.parents().contains(migration))
+        })
+        .map(|migration| (migration.app().to_string(), migration.name().to_string()))
+        .collect()
+}
+
+/// Return number for new migration which is one more than largest number
+/// prefix of rust or SQL migration files present in directory
+fn next_migration_number(dir: &std::path::Path) -> Result<u64, Error> {
+    let mut largest = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let file_name = entry?.file_name().to_string_lossy().to_string();
+        let without_prefix = file_name.strip_prefix('m').unwrap_or(&file_name);
+        if let Some(number) = without_prefix
+            .split_once('_')
+            .and_then(|(number, _)| number.parse::<u64>().ok())
+        {
+            largest = largest.max(number);
+        }
+    }
+    Ok(largest + 1)
+}
+
+/// Write file failing if file already exists
+fn write_new_file(path: &std::path::Path, content: &str) -> Result<(), Error> {
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+/// Add module declaration after last module declaration and migration at end
+/// of first `vec_box!` list of `mod.rs` content. Returns `None` if content
+/// doesn't contain module declaration or `vec_box!` list
+fn register_module(content: &str, module: &str, struct_name: &str) -> Option<String> {
+    let mut lines = content.lines().map(str::to_string).collect::<Vec<_>>();
+    let mod_line = lines.iter().rposition(|line| {
+        let line = line.trim();
+        line.strip_prefix("pub(crate) ")
+            .or_else(|| line.strip_prefix("pub "))
+            .unwrap_or(line)
+            .starts_with("mod ")
+            && line.ends_with(';')
+    })?;
+    let visibility = lines[mod_line]
+        .trim_start()
+        .split("mod ")
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    lines.insert(mod_line + 1, format!("{visibility}mod {module};"));
+    let list_start = lines.iter().position(|line| line.contains("vec_box!["))?;
+    let migration = format!("{module}::{struct_name}");
+    let (before, items) = lines[list_start].split_once("vec_box![")?;
+    // list which is opened and closed in same line
+    if let Some((items, after)) = items.split_once(']') {
+        let items = items.trim().trim_end_matches(',');
+        let items = if items.is_empty() {
+            migration
+        } else {
+            format!("{items}, {migration}")
+        };
+        lines[list_start] = format!("{before}vec_box![{items}]{after}");
+    } else {
+        let list_end = list_start
+            + lines[list_start..]
+                .iter()
+                .position(|line| line.trim_start().starts_with(']'))?;
+        let indent = lines[list_end]
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect::<String>();
+        lines.insert(list_end, format!("{indent}    {migration},"));
+    }
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    Some(updated)
+}
+
+#[derive(Parser, Debug)]
+#[expect(clippy::struct_excessive_bools)]
+struct Revert {
+    /// Revert all migration. Conflicts with app args
+    #[arg(long, conflicts_with = "app")]
+    all: bool,
+    /// Revert migration till app migrations is reverted. If it is present
+    /// alongside migration options than only till migration is reverted
+    #[arg(long)]
+    app: Option<String>,
+    /// Number of migration to revert. Conflicts with all and app args
+    #[arg(long, conflicts_with_all = ["all", "app"])]
+    count: Option<usize>,
+    /// Make migration reverted without running revert operation
+    #[arg(long)]
+    fake: bool,
+    /// Force run revert operation without asking question
+    #[arg(long, visible_alias = "yes")]
     force: bool,
     /// Revert migration till provided migration. Requires app options to be
     /// present
@@ -315,16 +1306,53 @@ struct Revert {
     /// Show plan
     #[arg(long)]
     plan: bool,
+    /// Output format of plan
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table, requires = "plan")]
+    format: OutputFormat,
+    /// Show why each migration is present in plan
+    #[arg(long, requires = "plan")]
+    explain: bool,
 }
 impl Revert {
     async fn run<DB>(
         &self,
         connection: &mut <DB as Database>::Connection,
         migrator: Box<dyn Migrate<DB>>,
+        prompt: &dyn Prompt,
     ) -> Result<(), Error>
     where
         DB: Database,
     {
+        let plan = self.plan();
+        let revert_migrations = migrator
+            .generate_migration_plan(connection, Some(&plan))
+            .await?;
+
+        if self.plan {
+            let output = self
+                .plan_output(connection, migrator.as_ref(), &plan, &revert_migrations)
+                .await?;
+            println!("{output}");
+        } else {
+            if !self.force && !revert_migrations.is_empty() && !self.fake {
+                let question = format!(
+                    "Do you want to revert {} migrations",
+                    revert_migrations.len()
+                );
+                if !prompt
+                    .confirm(&question, &migration_keys(&revert_migrations))
+                    .await?
+                {
+                    return Ok(());
+                }
+            }
+            migrator.run(connection, &plan).await?;
+            println!("Successfully reverted migrations according to plan");
+        }
+        Ok(())
+    }
+
+    fn plan(&self) -> Plan {
         let plan;
         if let Some(count) = self.count {
             plan = Plan::revert_count(count);
@@ -335,49 +1363,1347 @@ impl Revert {
         } else {
             plan = Plan::revert_count(1);
         }
-        let plan = plan.fake(self.fake);
-        let revert_migrations = migrator
-            .generate_migration_plan(connection, Some(&plan))
+        // Plan is only listed so irreversible migrations are allowed and marked in list
+        plan.fake(self.fake).allow_irreversible(self.plan)
+    }
+
+    #[expect(clippy::borrowed_box)]
+    async fn plan_output<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: &dyn Migrate<DB>,
+        plan: &Plan,
+        revert_migrations: &[&Box<dyn Migration<DB>>],
+    ) -> Result<String, Error>
+    where
+        DB: Database,
+    {
+        let applied_migrations = migrator.fetch_applied_migration_from_db(connection).await?;
+        let mut rows =
+            MigrationRow::from_migrations(migrator, revert_migrations, &applied_migrations, &[]);
+        let mut columns = vec![Column::App, Column::Name, Column::Reversible];
+        if self.explain {
+            add_plan_reasons(&mut rows, migrator, connection, plan).await?;
+            columns.push(Column::Reasons);
+        }
+        render_migration_rows(
+            &rows,
+            self.format,
+            &columns,
+            "No migration exists for reverting",
+        )
+    }
+}
+
+#[derive(Parser, Debug)]
+struct Show {
+    /// App of migration
+    app: String,
+    /// Name of migration
+    name: String,
+}
+
+impl Show {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        println!("{}", self.output(connection, migrator.as_ref()).await?);
+        Ok(())
+    }
+
+    async fn output<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: &dyn Migrate<DB>,
+    ) -> Result<String, Error>
+    where
+        DB: Database,
+    {
+        let Some(migration) = migrator
+            .migrations()
+            .iter()
+            .find(|migration| migration.app() == self.app && migration.name() == self.name)
+        else {
+            return Err(Error::MigrationNotFound {
+                app: self.app.clone(),
+                name: self.name.clone(),
+            });
+        };
+        migrator.ensure_migration_table_exists(connection).await?;
+        let applied_migrations = migrator.fetch_applied_migration_from_db(connection).await?;
+        let graph = MigrationGraph::from_migrations(migrator.migrations());
+        let related = |kind: EdgeKind, outgoing: bool| {
+            let related = graph
+                .edges()
+                .iter()
+                .filter(|edge| edge.kind() == kind)
+                .filter_map(|edge| {
+                    let (node, other) = if outgoing {
+                        (edge.source(), edge.target())
+                    } else {
+                        (edge.target(), edge.source())
+                    };
+                    let node = &graph.nodes()[node];
+                    (node.app() == self.app && node.name() == self.name)
+                        .then(|| graph.nodes()[other].to_string())
+                })
+                .collect::<Vec<_>>();
+            if related.is_empty() {
+                "-".to_string()
+            } else {
+                related.join(", ")
+            }
+        };
+
+        let mut lines = vec![];
+        lines.push(format!("App: {}", migration.app()));
+        lines.push(format!("Name: {}", migration.name()));
+        lines.push(format!("Parents: {}", related(EdgeKind::Parent, false)));
+        lines.push(format!("Children: {}", related(EdgeKind::Parent, true)));
+        lines.push(format!("Replaces: {}", related(EdgeKind::Replaces, true)));
+        lines.push(format!(
+            "Replaced by: {}",
+            related(EdgeKind::Replaces, false)
+        ));
+        lines.push(format!(
+            "Runs before: {}",
+            related(EdgeKind::RunBefore, true)
+        ));
+        lines.push(format!(
+            "Runs after: {}",
+            related(EdgeKind::RunBefore, false)
+        ));
+        lines.push(format!("Atomic: {}", migration.is_atomic()));
+        lines.push(format!("Virtual: {}", migration.is_virtual()));
+        match applied_migrations
+            .iter()
+            .find(|&applied_migration| applied_migration == migration)
+        {
+            Some(applied) => {
+                lines.push(format!(
+                    "Applied: id {} at {}",
+                    applied.id(),
+                    applied.applied_time()
+                ));
+            }
+            None => lines.push("Applied: no".to_string()),
+        }
+        let operations = migration.operations();
+        lines.push(format!("Operations: {}", operations.len()));
+        for (position, operation) in operations.iter().enumerate() {
+            lines.push(format!(
+                "  {position}. destructible: {}, reversible: {}",
+                operation.is_destructible(),
+                operation.is_reversible()
+            ));
+            match operation.sql() {
+                Some((up, down)) => {
+                    lines.push("     up SQL:".to_string());
+                    for line in up.trim().lines() {
+                        lines.push(format!("       {line}"));
+                    }
+                    if let Some(down) = down {
+                        lines.push("     down SQL:".to_string());
+                        for line in down.trim().lines() {
+                            lines.push(format!("       {line}"));
+                        }
+                    }
+                }
+                None => lines.push("     SQL not available".to_string()),
+            }
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+#[derive(Parser, Debug)]
+struct Snapshot {
+    /// File to which snapshot is written
+    #[arg(long, default_value = "schema_snapshot.json")]
+    file: std::path::PathBuf,
+}
+
+impl Snapshot {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let snapshot = migrator.take_snapshot(connection).await?;
+        std::fs::write(&self.file, snapshot.to_json()? + "\n")?;
+        println!(
+            "Wrote snapshot of {} tables for {} applied migrations to {}",
+            snapshot.tables().len(),
+            snapshot.migrations().len(),
+            self.file.display()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+struct Drift {
+    /// Committed snapshot file against which database is compared
+    #[arg(long, default_value = "schema_snapshot.json")]
+    file: std::path::PathBuf,
+}
+
+impl Drift {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let snapshot = snapshot::Snapshot::from_json(&std::fs::read_to_string(&self.file)?)?;
+        let drifts = migrator.detect_drift(connection, &snapshot).await?;
+        if drifts.is_empty() {
+            println!("No schema drift detected");
+            return Ok(());
+        }
+        for drift in &drifts {
+            println!("{drift}");
+        }
+        Err(Error::SchemaDriftDetected {
+            count: drifts.len(),
+        })
+    }
+}
+
+/// Count of migrations of app according to their status
+#[derive(Debug, Default, Serialize)]
+struct AppStatus {
+    app: String,
+    applied: usize,
+    pending: usize,
+    replaced: usize,
+    unknown: usize,
+}
+
+impl AppStatus {
+    fn find_or_insert<'a>(statuses: &'a mut Vec<Self>, app: &str) -> &'a mut Self {
+        let position = statuses
+            .iter()
+            .position(|status| status.app == app)
+            .unwrap_or_else(|| {
+                statuses.push(Self {
+                    app: app.to_string(),
+                    ..Self::default()
+                });
+                statuses.len() - 1
+            });
+        &mut statuses[position]
+    }
+}
+
+#[derive(Parser, Debug)]
+struct Squash {
+    /// App of migrations to squash
+    app: String,
+    /// First migration to squash
+    from: String,
+    /// Last migration to squash
+    to: String,
+    /// Name of squashed migration without number prefix
+    #[arg(long)]
+    name: String,
+    /// Directory containing migrations of app
+    #[arg(long, default_value = "src/migrations")]
+    dir: std::path::PathBuf,
+}
+
+impl Squash {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        validate_migration_name(&self.name)?;
+        let plan = migrator.generate_migration_plan(connection, None).await?;
+        std::fs::create_dir_all(&self.dir)?;
+        let number = next_migration_number(&self.dir)?;
+        let module = format!("m{number:04}_{}", self.name);
+        let struct_name = format!("M{number:04}Migration");
+        let squashed = SquashedMigration::new(&plan, &self.app, &self.from, &self.to, &module)?;
+        let path = self.dir.join(format!("{module}.rs"));
+        write_new_file(
+            &path,
+            &squashed.to_rust_source(database_type::<DB>()?, &struct_name),
+        )?;
+        println!(
+            "Created {} replacing {} migrations",
+            path.display(),
+            squashed.replaces().len()
+        );
+        register_migration_file(&self.dir, &module, &struct_name)?;
+        if squashed.down_sql().is_none() {
+            println!(
+                "Squashed migration is irreversible since some squashed operations are \
+                 irreversible"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+struct Status {
+    /// Committed snapshot file against which database is compared for drift.
+    /// Drift is only checked when provided
+    #[arg(long)]
+    snapshot: Option<std::path::PathBuf>,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+}
+
+impl Status {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let statuses = Self::app_statuses(connection, migrator.as_ref()).await?;
+        println!("{}", render_app_statuses(&statuses, self.format)?);
+        self.check(connection, migrator.as_ref(), &statuses).await
+    }
+
+    /// Count migrations of each app according to their status. Statuses are
+    /// sorted by app name
+    async fn app_statuses<DB>(
+        connection: &mut <DB as Database>::Connection,
+        migrator: &dyn Migrate<DB>,
+    ) -> Result<Vec<AppStatus>, Error>
+    where
+        DB: Database,
+    {
+        let apply_plan = migrator
+            .generate_migration_plan(connection, Some(&Plan::apply_all()))
             .await?;
+        let applied_migrations = migrator.fetch_applied_migration_from_db(connection).await?;
 
-        if self.plan {
-            if revert_migrations.is_empty() {
-                println!("No migration exists for reverting");
+        let mut statuses = Vec::<AppStatus>::new();
+        for migration in migrator.migrations() {
+            let status = AppStatus::find_or_insert(&mut statuses, migration.app());
+            if applied_migrations
+                .iter()
+                .any(|applied_migration| applied_migration == migration)
+            {
+                status.applied += 1;
+            } else if apply_plan.contains(&migration) {
+                status.pending += 1;
             } else {
-                let first_width = 10;
-                let second_width = 50;
-                let full_width = first_width + second_width + 3;
-                println!("{:^first_width$} | {:^second_width$}", "App", "Name");
-                println!("{:^full_width$}", "-".repeat(full_width));
-                for migration in revert_migrations {
-                    println!(
-                        "{:^first_width$} | {:^second_width$}",
-                        migration.app(),
-                        migration.name(),
-                    );
+                status.replaced += 1;
+            }
+        }
+        for applied_migration in &applied_migrations {
+            if !migrator
+                .migrations()
+                .iter()
+                .any(|migration| applied_migration == migration)
+            {
+                AppStatus::find_or_insert(&mut statuses, applied_migration.app()).unknown += 1;
+            }
+        }
+        statuses.sort_by(|a, b| a.app.cmp(&b.app));
+        Ok(statuses)
+    }
+
+    /// Return error when unknown or pending migrations are present or when
+    /// database schema drifted from snapshot
+    async fn check<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: &dyn Migrate<DB>,
+        statuses: &[AppStatus],
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let unknown = statuses.iter().map(|status| status.unknown).sum::<usize>();
+        if unknown > 0 {
+            return Err(Error::UnknownMigrationPresent { count: unknown });
+        }
+        if statuses.iter().any(|status| status.pending > 0) {
+            return Err(Error::PendingMigrationPresent);
+        }
+        if let Some(file) = &self.snapshot {
+            let snapshot = snapshot::Snapshot::from_json(&std::fs::read_to_string(file)?)?;
+            let drifts = migrator.detect_drift(connection, &snapshot).await?;
+            if !drifts.is_empty() {
+                if matches!(self.format, OutputFormat::Table) {
+                    for drift in &drifts {
+                        println!("{drift}");
+                    }
                 }
+                return Err(Error::SchemaDriftDetected {
+                    count: drifts.len(),
+                });
             }
+        }
+        Ok(())
+    }
+}
+
+fn render_app_statuses(statuses: &[AppStatus], format: OutputFormat) -> Result<String, Error> {
+    let mut lines = vec![];
+    match format {
+        OutputFormat::Table => {
+            let cells = statuses
+                .iter()
+                .map(|status| {
+                    vec![
+                        status.app.clone(),
+                        status.applied.to_string(),
+                        status.pending.to_string(),
+                        status.replaced.to_string(),
+                        status.unknown.to_string(),
+                    ]
+                })
+                .collect::<Vec<_>>();
+            lines.push(render_table(
+                &["App", "Applied", "Pending", "Replaced", "Unknown"],
+                &cells,
+            ));
+        }
+        OutputFormat::Json => {
+            lines.push(
+                serde_json::to_string_pretty(statuses)
+                    .map_err(|error| Error::Box(Box::new(error)))?,
+            );
+        }
+        OutputFormat::Csv => {
+            lines.push("app,applied,pending,replaced,unknown".to_string());
+            for status in statuses {
+                lines.push(format!(
+                    "{},{},{},{},{}",
+                    csv_field(&status.app),
+                    status.applied,
+                    status.pending,
+                    status.replaced,
+                    status.unknown
+                ));
+            }
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+#[derive(Subcommand, Debug)]
+enum TenantsSubCommand {
+    /// Apply migrations for each tenant
+    #[command()]
+    Apply(TenantsApply),
+}
+
+impl TenantsSubCommand {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+        prompt: &dyn Prompt,
+        connect_options: &ConnectOptions,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        match self {
+            TenantsSubCommand::Apply(apply) => {
+                apply
+                    .run(connection, migrator, prompt, connect_options)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+struct TenantsApply {
+    /// App name up to which migration needs to be applied. If migration option
+    /// is also present than only till migration is applied
+    #[arg(long)]
+    app: Option<String>,
+    /// Number of tenants for which migrations are applied concurrently. Each
+    /// tenant uses its own connection created using database url when value
+    /// is more than 1
+    #[arg(long, default_value_t = 1)]
+    concurrency: usize,
+    /// Continue applying migrations for remaining tenants when applying
+    /// migrations for a tenant fails
+    #[arg(long)]
+    continue_on_failure: bool,
+    /// Number of migration to apply. Conflicts with app args
+    #[arg(long, conflicts_with = "app")]
+    count: Option<usize>,
+    /// Make migration applied without running migration operations
+    #[arg(long)]
+    fake: bool,
+    /// Force run apply operation without asking question if migration is
+    /// destructible
+    #[arg(long, visible_alias = "yes")]
+    force: bool,
+    /// Apply migration till provided migration. Requires app options to be
+    /// present
+    #[arg(long, requires = "app")]
+    migration: Option<String>,
+    /// Query which returns single text column of tenant schema name. Conflicts
+    /// with schema args
+    #[arg(long, conflicts_with = "schemas", required_unless_present = "schemas")]
+    query: Option<String>,
+    /// Tenant schema name. Can be used multiple times
+    #[arg(long = "schema")]
+    schemas: Vec<String>,
+}
+
+impl TenantsApply {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+        prompt: &dyn Prompt,
+        connect_options: &ConnectOptions,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let plan;
+        if let Some(count) = self.count {
+            plan = Plan::apply_count(count);
+        } else if let Some(app) = &self.app {
+            plan = Plan::apply_name(app, &self.migration);
         } else {
-            if !self.force && !revert_migrations.is_empty() && !self.fake {
-                let mut input = String::new();
-                println!(
-                    "Do you want to revert {} migrations (y/N)",
-                    revert_migrations.len()
+            plan = Plan::apply_all();
+        }
+        let plan = plan.fake(self.fake);
+        let tenants = if let Some(query) = &self.query {
+            Tenants::Query(query.clone())
+        } else {
+            Tenants::Schemas(self.schemas.clone())
+        };
+        let failure_policy = if self.continue_on_failure {
+            FailurePolicy::Continue
+        } else {
+            FailurePolicy::Stop
+        };
+        let runner = TenantRunner::new(migrator.as_ref())
+            .concurrency(self.concurrency)
+            .failure_policy(failure_policy);
+        // resolve tenants once so same tenants are confirmed and applied
+        let tenants = Tenants::Schemas(runner.resolve_tenants(connection, &tenants).await?);
+        if !self.force && !self.fake {
+            let destructible_migrations = runner
+                .destructible_migrations(connection, &tenants, &plan)
+                .await?;
+            if !destructible_migrations.is_empty() {
+                let question = format!(
+                    "Do you want to apply destructible migrations {} for tenants",
+                    destructible_migrations.len()
                 );
-                for (position, migration) in revert_migrations.iter().enumerate() {
-                    println!("{position}. {} : {}", migration.app(), migration.name());
-                }
-                std::io::stdout().flush()?;
-                std::io::stdin().read_line(&mut input)?;
-                let input_trimmed = input.trim().to_ascii_lowercase();
-                // If answer is not y or yes then return
-                if !["y", "yes"].contains(&input_trimmed.as_str()) {
+                if !prompt.confirm(&question, &destructible_migrations).await? {
                     return Ok(());
                 }
             }
-            migrator.run(connection, &plan).await?;
-            println!("Successfully reverted migrations according to plan");
         }
+        let reports = if self.concurrency > 1 {
+            let max_connections = u32::try_from(self.concurrency).unwrap_or(u32::MAX);
+            let pool = connect_options.connect::<DB>(max_connections).await?;
+            let reports = runner.run(&pool, &tenants, &plan).await;
+            pool.close().await;
+            reports?
+        } else {
+            runner
+                .run_on_connection(connection, &tenants, &plan)
+                .await?
+        };
+
+        let mut failed_count = 0;
+        let cells = reports
+            .iter()
+            .map(|report| {
+                let status = match report.outcome() {
+                    TenantOutcome::Succeeded => String::from("\u{2713}"),
+                    TenantOutcome::Failed(error) => {
+                        failed_count += 1;
+                        format!("\u{2717} {error}")
+                    }
+                    TenantOutcome::Skipped => String::from("skipped"),
+                };
+                vec![
+                    report.tenant().to_string(),
+                    report.migrations().len().to_string(),
+                    status,
+                ]
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            render_table(&["Tenant", "Migrations", "Status"], &cells)
+        );
+        if failed_count > 0 {
+            return Err(Error::TenantFailed {
+                count: failed_count,
+            });
+        }
+        println!("Successfully applied migrations for all tenants");
         Ok(())
     }
 }
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use std::cell::Cell;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use clap::Parser;
+    use sqlx::pool::PoolConnection;
+    use sqlx::Sqlite;
+
+    use super::{
+        csv_field, leaf_migrations, next_migration_number, register_module, render_app_statuses,
+        retry_connection, MigrationCommand, OutputFormat, Prompt, Status, SubCommand,
+    };
+    use crate::error::Error;
+    use crate::migrator::{DatabaseOperation, Info, Migrate, Migrator, Plan};
+    use crate::test_utils::{migration, sqlite_pool};
+    use crate::vec_box;
+
+    #[tokio::test]
+    async fn cli_custom_prompt() {
+        type Question = (String, Vec<(String, String)>);
+
+        struct RecordingPrompt {
+            answer: bool,
+            questions: Mutex<Vec<Question>>,
+        }
+
+        #[async_trait::async_trait]
+        impl Prompt for RecordingPrompt {
+            async fn confirm(
+                &self,
+                question: &str,
+                migrations: &[(String, String)],
+            ) -> Result<bool, Error> {
+                self.questions
+                    .lock()
+                    .unwrap()
+                    .push((question.to_string(), migrations.to_vec()));
+                Ok(self.answer)
+            }
+        }
+
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        struct B;
+        migration!(B, "b", vec_box!(A), vec_box!(), vec_box!());
+        let migrations = || {
+            let mut migrator = Migrator::<Sqlite>::default();
+            migrator.add_migrations(vec_box!(A, B));
+            Box::new(migrator)
+        };
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        migrations()
+            .run(&mut conn, &Plan::apply_all())
+            .await
+            .unwrap();
+
+        let command = MigrationCommand::try_parse_from(["migrator", "revert", "--all"]).unwrap();
+        let refuse = RecordingPrompt {
+            answer: false,
+            questions: Mutex::new(vec![]),
+        };
+        command
+            .run_with_prompt(&mut *conn, migrations(), &refuse)
+            .await
+            .unwrap();
+        assert_eq!(
+            refuse.questions.into_inner().unwrap(),
+            vec![(
+                "Do you want to revert 2 migrations".to_string(),
+                vec![
+                    ("test".to_string(), "b".to_string()),
+                    ("test".to_string(), "a".to_string())
+                ]
+            )]
+        );
+        let applied = migrations()
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(applied.len(), 2);
+
+        let confirm = RecordingPrompt {
+            answer: true,
+            questions: Mutex::new(vec![]),
+        };
+        command
+            .run_with_prompt(&mut *conn, migrations(), &confirm)
+            .await
+            .unwrap();
+        assert_eq!(confirm.questions.into_inner().unwrap().len(), 1);
+        let applied = migrations()
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap();
+        assert!(applied.is_empty());
+
+        migrations()
+            .run(&mut conn, &Plan::apply_all())
+            .await
+            .unwrap();
+        let command =
+            MigrationCommand::try_parse_from(["migrator", "revert", "--all", "--yes"]).unwrap();
+        let unused = RecordingPrompt {
+            answer: false,
+            questions: Mutex::new(vec![]),
+        };
+        command
+            .run_with_prompt(&mut *conn, migrations(), &unused)
+            .await
+            .unwrap();
+        assert!(unused.questions.into_inner().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn cli_database_url() {
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        let command = MigrationCommand::try_parse_from([
+            "migrator",
+            "--database-url",
+            "sqlite::memory:",
+            "list",
+        ])
+        .unwrap();
+        assert_eq!(
+            command.connect_options.database_url().unwrap(),
+            "sqlite::memory:"
+        );
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box!(A));
+        command.run_with_url(Box::new(migrator)).await.unwrap();
+
+        // only test which uses DATABASE_URL environment variable
+        let command = MigrationCommand::try_parse_from(["migrator", "list"]).unwrap();
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        assert_eq!(
+            command.connect_options.database_url().unwrap(),
+            "sqlite::memory:"
+        );
+        std::env::remove_var("DATABASE_URL");
+        assert!(matches!(
+            command.connect_options.database_url(),
+            Err(Error::DatabaseUrlNotProvided)
+        ));
+    }
+
+    #[tokio::test]
+    async fn cli_connect_retry() {
+        let attempts = Cell::new(0);
+        let result = retry_connection(3, Duration::from_millis(1), || {
+            attempts.set(attempts.get() + 1);
+            let attempt = attempts.get();
+            async move {
+                if attempt < 3 {
+                    return Err(Error::Sqlx(sqlx::Error::PoolTimedOut));
+                }
+                Ok(attempt)
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+
+        attempts.set(0);
+        let result = retry_connection(2, Duration::from_millis(1), || {
+            attempts.set(attempts.get() + 1);
+            async { Err::<(), _>(Error::Sqlx(sqlx::Error::PoolTimedOut)) }
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(Error::Sqlx(sqlx::Error::PoolTimedOut))
+        ));
+        assert_eq!(attempts.get(), 3);
+
+        // error which is not connection error is not retried
+        attempts.set(0);
+        let result = retry_connection(2, Duration::from_millis(1), || {
+            attempts.set(attempts.get() + 1);
+            async { Err::<(), _>(Error::Sqlx(sqlx::Error::Configuration("invalid".into()))) }
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(Error::Sqlx(sqlx::Error::Configuration(_)))
+        ));
+        assert_eq!(attempts.get(), 1);
+    }
+
+    /// Return output of list, apply plan and revert plan in format
+    async fn command_outputs(
+        connection: &mut sqlx::SqliteConnection,
+        migrator: &Migrator<Sqlite>,
+        format: &str,
+    ) -> [String; 3] {
+        let command =
+            MigrationCommand::try_parse_from(["migrator", "list", "--format", format]).unwrap();
+        let SubCommand::List(list) = command.sub_command else {
+            unreachable!()
+        };
+        let list_output = list.output(connection, migrator).await.unwrap();
+
+        let command = MigrationCommand::try_parse_from([
+            "migrator",
+            "apply",
+            "--plan",
+            "--explain",
+            "--format",
+            format,
+        ])
+        .unwrap();
+        let SubCommand::Apply(apply) = command.sub_command else {
+            unreachable!()
+        };
+        let plan = apply.plan();
+        let migrations = migrator
+            .generate_migration_plan(connection, Some(&plan))
+            .await
+            .unwrap();
+        let apply_output = apply
+            .plan_output(connection, migrator, &plan, &migrations)
+            .await
+            .unwrap();
+
+        let command = MigrationCommand::try_parse_from([
+            "migrator", "revert", "--all", "--plan", "--format", format,
+        ])
+        .unwrap();
+        let SubCommand::Revert(revert) = command.sub_command else {
+            unreachable!()
+        };
+        let plan = revert.plan();
+        let migrations = migrator
+            .generate_migration_plan(connection, Some(&plan))
+            .await
+            .unwrap();
+        let revert_output = revert
+            .plan_output(connection, migrator, &plan, &migrations)
+            .await
+            .unwrap();
+        [list_output, apply_output, revert_output]
+    }
+
+    /// Create migrator with two applied migrations and one pending migration
+    /// whose name needs quoting in CSV
+    async fn output_fixture() -> (Migrator<Sqlite>, PoolConnection<Sqlite>) {
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        struct B;
+        migration!(B, "b,\"quoted\"", vec_box!(A), vec_box!(), vec_box!());
+        struct C;
+        migration!(C, "c_with_long_name", vec_box!(B), vec_box!(), vec_box!());
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box!(A, B, C));
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        migrator
+            .ensure_migration_table_exists(&mut conn)
+            .await
+            .unwrap();
+        for migration in &migrator.migrations()[..2] {
+            migrator
+                .add_migration_to_db_table_with_applied_time(
+                    &mut conn,
+                    migration,
+                    "2024-01-02 03:04:05",
+                )
+                .await
+                .unwrap();
+        }
+        (migrator, conn)
+    }
+
+    #[tokio::test]
+    async fn cli_table_output() {
+        let (migrator, mut conn) = output_fixture().await;
+        let [list, apply, revert] = command_outputs(&mut conn, &migrator, "table").await;
+        assert_eq!(
+            list,
+            [
+                "ID  | App  |       Name       | Status |    Applied time    ",
+                "------------------------------------------------------------",
+                " 1  | test |        a         |   \u{2713}    | 2024-01-02 03:04:05",
+                " 2  | test |    b,\"quoted\"    |   \u{2713}    | 2024-01-02 03:04:05",
+                "N/A | test | c_with_long_name |   \u{2717}    |         N/A        ",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            apply,
+            [
+                "App  |       Name       |      Reasons     ",
+                "-------------------------------------------",
+                "test | c_with_long_name | targeted directly",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            revert,
+            [
+                "App  |    Name    | Reversible",
+                "------------------------------",
+                "test | b,\"quoted\" |     \u{2713}     ",
+                "test |     a      |     \u{2713}     ",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[tokio::test]
+    async fn cli_json_output() {
+        let (migrator, mut conn) = output_fixture().await;
+        let row = |name: &str, id: Option<i32>| {
+            serde_json::json!({
+                "app": "test",
+                "name": name,
+                "status": if id.is_some() { "applied" } else { "pending" },
+                "id": id,
+                "applied_time": id.map(|_| "2024-01-02 03:04:05"),
+                "atomic": true,
+                "destructible": false,
+                "reversible": true,
+                "replaced_by": null,
+            })
+        };
+        let [list, apply, revert] = command_outputs(&mut conn, &migrator, "json")
+            .await
+            .map(|output| serde_json::from_str::<serde_json::Value>(&output).unwrap());
+        assert_eq!(
+            list,
+            serde_json::json!([
+                row("a", Some(1)),
+                row("b,\"quoted\"", Some(2)),
+                row("c_with_long_name", None)
+            ])
+        );
+        let mut explained = row("c_with_long_name", None);
+        explained["reasons"] = serde_json::json!(["targeted directly"]);
+        assert_eq!(apply, serde_json::json!([explained]));
+        assert_eq!(
+            revert,
+            serde_json::json!([row("b,\"quoted\"", Some(2)), row("a", Some(1))])
+        );
+    }
+
+    #[tokio::test]
+    async fn cli_csv_output() {
+        let (migrator, mut conn) = output_fixture().await;
+        let [list, apply, revert] = command_outputs(&mut conn, &migrator, "csv").await;
+        assert_eq!(
+            list,
+            [
+                "app,name,status,id,applied_time,atomic,destructible,reversible,replaced_by",
+                "test,a,applied,1,2024-01-02 03:04:05,true,false,true,",
+                "test,\"b,\"\"quoted\"\"\",applied,2,2024-01-02 03:04:05,true,false,true,",
+                "test,c_with_long_name,pending,,,true,false,true,",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            apply,
+            [
+                "app,name,status,id,applied_time,atomic,destructible,reversible,replaced_by,\
+                 reasons",
+                "test,c_with_long_name,pending,,,true,false,true,,targeted directly",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            revert,
+            [
+                "app,name,status,id,applied_time,atomic,destructible,reversible,replaced_by",
+                "test,\"b,\"\"quoted\"\"\",applied,2,2024-01-02 03:04:05,true,false,true,",
+                "test,a,applied,1,2024-01-02 03:04:05,true,false,true,",
+            ]
+            .join("\n")
+        );
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+        assert_eq!(csv_field("a; b"), "a; b");
+    }
+
+    /// Create empty directory inside temporary directory for test
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("sqlx_migrator_{}_{name}", std::process::id()));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn cli_leaf_migrations() {
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        struct B;
+        migration!(B, "b", vec_box!(A), vec_box!(), vec_box!());
+        struct C;
+        migration!(C, "c", vec_box!(A), vec_box!(), vec_box!());
+        struct D;
+        migration!(D, "d", vec_box!(A), vec_box!(C), vec_box!());
+        struct E;
+        crate::sqlite_migration!(
+            E,
+            app = "other",
+            name = "e",
+            parents = vec_box![B],
+            operations = vec_box![]
+        );
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box!(A, B, C, D, E));
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        let plan = migrator
+            .generate_migration_plan(&mut conn, None)
+            .await
+            .unwrap();
+        // replaced migration c is ignored and migration of other app doesn't
+        // make b parent
+        assert_eq!(
+            leaf_migrations(&migrator, &plan, "test"),
+            [
+                ("test".to_string(), "b".to_string()),
+                ("test".to_string(), "d".to_string())
+            ]
+        );
+        assert_eq!(
+            leaf_migrations(&migrator, &plan, "other"),
+            [("other".to_string(), "e".to_string())]
+        );
+        assert!(leaf_migrations(&migrator, &plan, "missing").is_empty());
+    }
+
+    #[test]
+    fn cli_next_migration_number() {
+        let dir = test_dir("next_migration_number");
+        assert_eq!(next_migration_number(&dir).unwrap(), 1);
+        for file_name in [
+            "mod.rs",
+            "m0002_initial.rs",
+            "0007_books.up.sql",
+            "0007_books.down.sql",
+            "m_helper.rs",
+            "README.md",
+        ] {
+            std::fs::write(dir.join(file_name), "").unwrap();
+        }
+        assert_eq!(next_migration_number(&dir).unwrap(), 8);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cli_register_module() {
+        let content = "pub(crate) mod m0001_initial;\n\npub(crate) fn migrations() -> Vec<Box<dyn \
+                       Migration<Sqlite>>> {\n    vec_box![\n        \
+                       m0001_initial::M0001Migration,\n    ]\n}\n";
+        assert_eq!(
+            register_module(content, "m0002_books", "M0002Migration").unwrap(),
+            "pub(crate) mod m0001_initial;\npub(crate) mod m0002_books;\n\npub(crate) fn \
+             migrations() -> Vec<Box<dyn Migration<Sqlite>>> {\n    vec_box![\n        \
+             m0001_initial::M0001Migration,\n        m0002_books::M0002Migration,\n    ]\n}\n"
+        );
+        assert_eq!(
+            register_module(
+                "mod m0001_initial;\nmod tests {}\nlet migrations = \
+                 vec_box![m0001_initial::M0001Migration];",
+                "m0002_books",
+                "M0002Migration"
+            )
+            .unwrap(),
+            "mod m0001_initial;\nmod m0002_books;\nmod tests {}\nlet migrations = \
+             vec_box![m0001_initial::M0001Migration, m0002_books::M0002Migration];"
+        );
+        assert_eq!(
+            register_module("mod helper;\nvec_box![]", "m0001_initial", "M0001Migration").unwrap(),
+            "mod helper;\nmod m0001_initial;\nvec_box![m0001_initial::M0001Migration]"
+        );
+        assert!(register_module("vec_box![]", "m0001_initial", "M0001Migration").is_none());
+        assert!(register_module("mod helper;", "m0001_initial", "M0001Migration").is_none());
+    }
+
+    #[tokio::test]
+    async fn cli_new_migration() {
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        struct B;
+        migration!(B, "b", vec_box!(A), vec_box!(), vec_box!(A));
+        let dir = test_dir("new_migration");
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        let command = MigrationCommand::try_parse_from([
+            "migrator",
+            "new",
+            "test",
+            "books",
+            "--sql",
+            "--dir",
+            dir.to_str().unwrap(),
+        ])
+        .unwrap();
+
+        command
+            .run(&mut *conn, Box::new(Migrator::<Sqlite>::default()))
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("0001_books.up.sql")).unwrap(),
+            "-- app: test\n"
+        );
+
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box!(A));
+        command.run(&mut *conn, Box::new(migrator)).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("0002_books.up.sql")).unwrap(),
+            "-- app: test\n-- parents: test:a\n"
+        );
+
+        // error of generating plan is returned instead of creating migration
+        // without parents
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box!(A, B));
+        assert!(matches!(
+            command.run(&mut *conn, Box::new(migrator)).await,
+            Err(Error::PlanError { .. })
+        ));
+        assert!(!dir.join("0003_books.up.sql").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cli_show() {
+        struct A;
+        crate::sqlite_migration!(
+            A,
+            app = "test",
+            name = "a",
+            parents = vec_box![],
+            operations = vec_box![(
+                "CREATE TABLE a (id INTEGER);\nCREATE INDEX a_id ON a (id);",
+                "DROP TABLE a;"
+            )]
+        );
+        struct B;
+        migration!(B, "b", vec_box!(A), vec_box!(), vec_box!());
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box!(A, B));
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        migrator
+            .ensure_migration_table_exists(&mut conn)
+            .await
+            .unwrap();
+        migrator
+            .add_migration_to_db_table_with_applied_time(
+                &mut conn,
+                &migrator.migrations()[0],
+                "2024-01-02 03:04:05",
+            )
+            .await
+            .unwrap();
+
+        let show = |name: &str| {
+            let command =
+                MigrationCommand::try_parse_from(["migrator", "show", "test", name]).unwrap();
+            let SubCommand::Show(show) = command.sub_command else {
+                unreachable!()
+            };
+            show
+        };
+        assert_eq!(
+            show("a").output(&mut *conn, &migrator).await.unwrap(),
+            [
+                "App: test",
+                "Name: a",
+                "Parents: -",
+                "Children: test:b",
+                "Replaces: -",
+                "Replaced by: -",
+                "Runs before: -",
+                "Runs after: -",
+                "Atomic: true",
+                "Virtual: false",
+                "Applied: id 1 at 2024-01-02 03:04:05",
+                "Operations: 1",
+                "  0. destructible: false, reversible: true",
+                "     up SQL:",
+                "       CREATE TABLE a (id INTEGER);",
+                "       CREATE INDEX a_id ON a (id);",
+                "     down SQL:",
+                "       DROP TABLE a;",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            show("b").output(&mut *conn, &migrator).await.unwrap(),
+            [
+                "App: test",
+                "Name: b",
+                "Parents: test:a",
+                "Children: -",
+                "Replaces: -",
+                "Replaced by: -",
+                "Runs before: -",
+                "Runs after: -",
+                "Atomic: true",
+                "Virtual: false",
+                "Applied: no",
+                "Operations: 0",
+            ]
+            .join("\n")
+        );
+        assert!(matches!(
+            show("missing").output(&mut *conn, &migrator).await,
+            Err(Error::MigrationNotFound { .. })
+        ));
+    }
+
+    /// Create migrator with one applied and one pending migration of test app,
+    /// one replaced and one pending migration of other app and return unknown
+    /// migration which is applied
+    async fn status_fixture() -> (
+        Migrator<Sqlite>,
+        PoolConnection<Sqlite>,
+        Box<dyn crate::migration::Migration<Sqlite>>,
+    ) {
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        struct B;
+        migration!(B, "b", vec_box!(A), vec_box!(), vec_box!());
+        struct C;
+        crate::sqlite_migration!(
+            C,
+            app = "other",
+            name = "c",
+            parents = vec_box![],
+            operations = vec_box![]
+        );
+        struct D;
+        crate::sqlite_migration!(
+            D,
+            app = "other",
+            name = "d",
+            parents = vec_box![],
+            operations = vec_box![("CREATE TABLE d (id INTEGER)", "DROP TABLE d")],
+            replaces = vec_box![C]
+        );
+        struct Gone;
+        migration!(Gone, "gone", vec_box!(), vec_box!(), vec_box!());
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box!(A, B, C, D));
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        migrator
+            .ensure_migration_table_exists(&mut conn)
+            .await
+            .unwrap();
+        migrator
+            .add_migration_to_db_table(&mut conn, &migrator.migrations()[0])
+            .await
+            .unwrap();
+        let gone: Box<dyn crate::migration::Migration<Sqlite>> = Box::new(Gone);
+        migrator
+            .add_migration_to_db_table(&mut conn, &gone)
+            .await
+            .unwrap();
+        (migrator, conn, gone)
+    }
+
+    #[tokio::test]
+    async fn cli_status() {
+        let (migrator, mut conn, gone) = status_fixture().await;
+        let status = |args: &[&str]| {
+            let command =
+                MigrationCommand::try_parse_from(["migrator", "status"].iter().chain(args))
+                    .unwrap();
+            let SubCommand::Status(status) = command.sub_command else {
+                unreachable!()
+            };
+            status
+        };
+        let statuses = Status::app_statuses(&mut *conn, &migrator).await.unwrap();
+        assert_eq!(
+            render_app_statuses(&statuses, OutputFormat::Table).unwrap(),
+            [
+                " App  | Applied | Pending | Replaced | Unknown",
+                "----------------------------------------------",
+                "other |    0    |    1    |    1     |    0   ",
+                "test  |    1    |    1    |    0     |    1   ",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            render_app_statuses(&statuses, OutputFormat::Csv).unwrap(),
+            "app,applied,pending,replaced,unknown\nother,0,1,1,0\ntest,1,1,0,1"
+        );
+        assert!(matches!(
+            status(&[]).check(&mut *conn, &migrator, &statuses).await,
+            Err(Error::UnknownMigrationPresent { count: 1 })
+        ));
+
+        migrator
+            .delete_migration_from_db_table(&mut conn, &gone)
+            .await
+            .unwrap();
+        let statuses = Status::app_statuses(&mut *conn, &migrator).await.unwrap();
+        assert!(matches!(
+            status(&[]).check(&mut *conn, &migrator, &statuses).await,
+            Err(Error::PendingMigrationPresent)
+        ));
+
+        migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+        let statuses = Status::app_statuses(&mut *conn, &migrator).await.unwrap();
+        assert_eq!(
+            render_app_statuses(&statuses, OutputFormat::Csv).unwrap(),
+            "app,applied,pending,replaced,unknown\nother,1,0,1,0\ntest,2,0,0,0"
+        );
+        let dir = test_dir("status");
+        let snapshot_file = dir.join("snapshot.json");
+        let snapshot = migrator.take_snapshot(&mut conn).await.unwrap();
+        std::fs::write(&snapshot_file, snapshot.to_json().unwrap()).unwrap();
+        let snapshot_arg = snapshot_file.to_str().unwrap();
+        assert!(status(&["--snapshot", snapshot_arg])
+            .check(&mut *conn, &migrator, &statuses)
+            .await
+            .is_ok());
+        sqlx::query("CREATE TABLE drifted (id INTEGER)")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        assert!(matches!(
+            status(&["--snapshot", snapshot_arg])
+                .check(&mut *conn, &migrator, &statuses)
+                .await,
+            Err(Error::SchemaDriftDetected { count: 1 })
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
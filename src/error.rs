@@ -20,17 +20,103 @@ pub enum Error {
         /// Message for error
         message: String,
     },
+    /// Error generated while importing migrations from other migration tool
+    #[error("import error: {message}")]
+    ImportError {
+        /// Message for error
+        message: String,
+    },
+    /// Error generated while loading SQL migrations
+    #[error("sql migration error: {message}")]
+    SqlMigrationError {
+        /// Message for error
+        message: String,
+    },
+    /// Error generated while running backfill operation
+    #[error("backfill error: {message}")]
+    BackfillError {
+        /// Message for error
+        message: String,
+    },
+    /// Error generated while taking or comparing schema snapshot
+    #[error("snapshot error: {message}")]
+    SnapshotError {
+        /// Message for error
+        message: String,
+    },
+    /// Error generated while squashing migrations
+    #[error("squash error: {message}")]
+    SquashError {
+        /// Message for error
+        message: String,
+    },
+    /// Error when schema operation is not supported by database
+    #[error("{operation} is not supported by {database}")]
+    UnsupportedSchemaOperation {
+        /// Schema operation which is not supported
+        operation: String,
+        /// Name of database
+        database: String,
+    },
     /// Error for irreversible operation
     #[error("operation is irreversible")]
     IrreversibleOperation,
+    /// Error when migration is not added to migrator
+    #[error("migration {app}:{name} doesn't exists")]
+    MigrationNotFound {
+        /// App of migration
+        app: String,
+        /// Name of migration
+        name: String,
+    },
     /// Error for pending migration present
     #[cfg(feature = "cli")]
     #[error("pending migration present")]
     PendingMigrationPresent,
+    /// Error when migrations which are not added to migrator are applied
+    #[cfg(feature = "cli")]
+    #[error("{count} applied migrations are unknown to migrator")]
+    UnknownMigrationPresent {
+        /// Number of unknown applied migrations
+        count: usize,
+    },
     /// Error when applied migrations exists
     #[cfg(feature = "cli")]
     #[error("applied migrations exists. Revert all using revert subcommand")]
     AppliedMigrationExists,
+    /// Error when database schema differs from snapshot
+    #[cfg(feature = "cli")]
+    #[error("schema drift detected for {count} objects")]
+    SchemaDriftDetected {
+        /// Number of differences
+        count: usize,
+    },
+    /// Error when running plan fails for tenants
+    #[cfg(feature = "cli")]
+    #[error("running plan failed for {count} tenants")]
+    TenantFailed {
+        /// Number of failed tenants
+        count: usize,
+    },
+    /// Error when confirmation is required but stdin is not terminal
+    #[cfg(feature = "cli")]
+    #[error("confirmation required but stdin is not a terminal. Use --yes or --force to confirm")]
+    NonInteractivePrompt,
+    /// Error when database url is not provided to cli
+    #[cfg(feature = "cli")]
+    #[error("database url not provided. Use --database-url or DATABASE_URL environment variable")]
+    DatabaseUrlNotProvided,
+    /// Error when migrator table layout was created by newer version of crate
+    #[error(
+        "migrator table version {found} is newer than version {supported} supported by this \
+         version of crate"
+    )]
+    NewerMigratorTableVersion {
+        /// Version of layout found in database
+        found: i32,
+        /// Latest version of layout supported by crate
+        supported: i32,
+    },
     /// Error when unsupported database is used as any database
     #[error("database not supported")]
     UnsupportedDatabase,
@@ -38,3 +124,83 @@ pub enum Error {
     #[error("prefix can only be ascii alphanumeric and underscore character")]
     NonAsciiAlphaNumeric,
 }
+
+impl Error {
+    /// Return whether error was raised since connection to database could not
+    /// be established or was lost
+    #[must_use]
+    pub fn is_connection_error(&self) -> bool {
+        matches!(
+            self,
+            Self::Sqlx(
+                sqlx::Error::Io(_)
+                    | sqlx::Error::Tls(_)
+                    | sqlx::Error::PoolTimedOut
+                    | sqlx::Error::PoolClosed
+                    | sqlx::Error::WorkerCrashed,
+            )
+        )
+    }
+}
+
+#[cfg(feature = "cli")]
+impl Error {
+    /// Return exit code for error which can be used by cli to exit process.
+    /// Code is stable so it can be used by deployment pipelines
+    ///
+    /// | Code | Condition |
+    /// |------|-----------|
+    /// | 1 | Any other error |
+    /// | 2 | Pending migration present |
+    /// | 3 | Applied migration unknown to migrator present |
+    /// | 4 | Schema drift detected |
+    /// | 5 | Failed to connect to database |
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::PendingMigrationPresent => 2,
+            Self::UnknownMigrationPresent { .. } => 3,
+            Self::SchemaDriftDetected { .. } => 4,
+            _ if self.is_connection_error() => 5,
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn error_exit_code() {
+        assert_eq!(
+            Error::PlanError {
+                message: String::new()
+            }
+            .exit_code(),
+            1
+        );
+        assert_eq!(Error::PendingMigrationPresent.exit_code(), 2);
+        assert_eq!(Error::UnknownMigrationPresent { count: 1 }.exit_code(), 3);
+        assert_eq!(Error::SchemaDriftDetected { count: 1 }.exit_code(), 4);
+        assert_eq!(Error::Sqlx(sqlx::Error::PoolTimedOut).exit_code(), 5);
+        assert_eq!(
+            Error::Sqlx(sqlx::Error::Configuration("invalid url".into())).exit_code(),
+            1
+        );
+    }
+
+    #[test]
+    fn connection_error() {
+        assert!(Error::Sqlx(sqlx::Error::PoolTimedOut).is_connection_error());
+        assert!(Error::Sqlx(sqlx::Error::Io(
+            std::io::ErrorKind::ConnectionRefused.into()
+        ))
+        .is_connection_error());
+        assert!(
+            !Error::Sqlx(sqlx::Error::Configuration("invalid url".into())).is_connection_error()
+        );
+        assert!(!Error::UnsupportedDatabase.is_connection_error());
+    }
+}
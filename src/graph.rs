@@ -0,0 +1,423 @@
+//! Module for inspecting dependency graph of migrations
+//!
+//! [`MigrationGraph`] is built from migrations added to migrator using
+//! [`Info::migrations`](crate::migrator::Info::migrations). Each migration is
+//! a [`Node`] and each parent, run before and replaces relation is a typed
+//! [`Edge`]. Graph can be queried for roots and leaves of app along with
+//! ancestors and descendants of migration, and rendered as Graphviz DOT or
+//! Mermaid flowchart.
+#![cfg_attr(
+    feature = "sqlite",
+    doc = r#"
+# Example
+```rust,no_run
+use sqlx::Sqlite;
+use sqlx_migrator::graph::MigrationGraph;
+use sqlx_migrator::migrator::{Info, Migrator};
+
+let migrator = Migrator::<Sqlite>::default();
+let graph = MigrationGraph::from_migrations(migrator.migrations());
+for leaf in graph.leaves("main") {
+    println!("{leaf}");
+}
+println!("{}", graph.to_dot());
+```
+"#
+)]
+
+use std::collections::VecDeque;
+
+use crate::migration::{AppliedMigrationSqlRow, Migration};
+
+/// Kind of relation between two migrations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EdgeKind {
+    /// Source migration is parent of target migration
+    Parent,
+    /// Source migration runs before target migration
+    RunBefore,
+    /// Source migration replaces target migration
+    Replaces,
+}
+
+/// Migration present in graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    app: String,
+    name: String,
+    applied: Option<bool>,
+}
+
+impl Node {
+    /// Return app of migration
+    #[must_use]
+    pub fn app(&self) -> &str {
+        &self.app
+    }
+
+    /// Return name of migration
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return whether migration is applied. Returns `None` if applied status
+    /// was not set using [`MigrationGraph::set_applied`]
+    #[must_use]
+    pub fn applied(&self) -> Option<bool> {
+        self.applied
+    }
+}
+
+impl std::fmt::Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.app, self.name)
+    }
+}
+
+/// Relation between two nodes of graph. Source and target are index of node
+/// in [`MigrationGraph::nodes`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    source: usize,
+    target: usize,
+    kind: EdgeKind,
+}
+
+impl Edge {
+    /// Return index of source node
+    #[must_use]
+    pub fn source(&self) -> usize {
+        self.source
+    }
+
+    /// Return index of target node
+    #[must_use]
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    /// Return kind of edge
+    #[must_use]
+    pub fn kind(&self) -> EdgeKind {
+        self.kind
+    }
+}
+
+/// Dependency graph of migrations
+#[derive(Debug, Clone, Default)]
+pub struct MigrationGraph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+impl MigrationGraph {
+    /// Create graph from migrations. Migrations which are only referenced as
+    /// parent, run before or replaced migration are also added as node
+    #[must_use]
+    pub fn from_migrations<DB>(migrations: &[Box<dyn Migration<DB>>]) -> Self {
+        let mut graph = Self::default();
+        for migration in migrations {
+            graph.node_index(migration.app(), migration.name());
+        }
+        for migration in migrations {
+            let index = graph.node_index(migration.app(), migration.name());
+            for parent in migration.parents() {
+                let parent_index = graph.node_index(parent.app(), parent.name());
+                graph.add_edge(parent_index, index, EdgeKind::Parent);
+            }
+            for run_before in migration.run_before() {
+                let run_before_index = graph.node_index(run_before.app(), run_before.name());
+                graph.add_edge(index, run_before_index, EdgeKind::RunBefore);
+            }
+            for replace in migration.replaces() {
+                let replace_index = graph.node_index(replace.app(), replace.name());
+                graph.add_edge(index, replace_index, EdgeKind::Replaces);
+            }
+        }
+        graph
+    }
+
+    fn node_index(&mut self, app: &str, name: &str) -> usize {
+        self.position(app, name).unwrap_or_else(|| {
+            self.nodes.push(Node {
+                app: app.to_string(),
+                name: name.to_string(),
+                applied: None,
+            });
+            self.nodes.len() - 1
+        })
+    }
+
+    fn add_edge(&mut self, source: usize, target: usize, kind: EdgeKind) {
+        let edge = Edge {
+            source,
+            target,
+            kind,
+        };
+        if !self.edges.contains(&edge) {
+            self.edges.push(edge);
+        }
+    }
+
+    fn position(&self, app: &str, name: &str) -> Option<usize> {
+        self.nodes
+            .iter()
+            .position(|node| node.app == app && node.name == name)
+    }
+
+    /// Set applied status of all nodes using applied migrations fetched from
+    /// database
+    pub fn set_applied(&mut self, applied_migrations: &[AppliedMigrationSqlRow]) {
+        for node in &mut self.nodes {
+            node.applied = Some(
+                applied_migrations
+                    .iter()
+                    .any(|applied| applied.app() == node.app && applied.name() == node.name),
+            );
+        }
+    }
+
+    /// Return all nodes of graph
+    #[must_use]
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Return all edges of graph
+    #[must_use]
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Return node for app and name
+    #[must_use]
+    pub fn node(&self, app: &str, name: &str) -> Option<&Node> {
+        self.position(app, name).map(|index| &self.nodes[index])
+    }
+
+    /// Return migrations of app which doesn't have any parent from same app
+    #[must_use]
+    pub fn roots(&self, app: &str) -> Vec<&Node> {
+        self.app_nodes_without_edge(app, |edge, index| edge.target == index)
+    }
+
+    /// Return migrations of app which aren't parent of any other migration of
+    /// same app
+    #[must_use]
+    pub fn leaves(&self, app: &str) -> Vec<&Node> {
+        self.app_nodes_without_edge(app, |edge, index| edge.source == index)
+    }
+
+    fn app_nodes_without_edge(&self, app: &str, matches: fn(&Edge, usize) -> bool) -> Vec<&Node> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(index, node)| {
+                node.app == app
+                    && !self.edges.iter().any(|edge| {
+                        edge.kind == EdgeKind::Parent
+                            && matches(edge, *index)
+                            && self.nodes[edge.source].app == app
+                            && self.nodes[edge.target].app == app
+                    })
+            })
+            .map(|(_, node)| node)
+            .collect()
+    }
+
+    /// Return migrations which need to be applied before migration through
+    /// parent and run before relations. Returns empty list if migration is
+    /// not present in graph
+    #[must_use]
+    pub fn ancestors(&self, app: &str, name: &str) -> Vec<&Node> {
+        self.traverse(app, name, |edge| (edge.target, edge.source))
+    }
+
+    /// Return migrations which need to be reverted before migration through
+    /// parent and run before relations. Returns empty list if migration is
+    /// not present in graph
+    #[must_use]
+    pub fn descendants(&self, app: &str, name: &str) -> Vec<&Node> {
+        self.traverse(app, name, |edge| (edge.source, edge.target))
+    }
+
+    /// Breadth first traversal over ordering edges. Direction returns `(from,
+    /// to)` index of edge in direction of traversal
+    fn traverse(
+        &self,
+        app: &str,
+        name: &str,
+        direction: fn(&Edge) -> (usize, usize),
+    ) -> Vec<&Node> {
+        let Some(start) = self.position(app, name) else {
+            return vec![];
+        };
+        let mut visited = vec![false; self.nodes.len()];
+        visited[start] = true;
+        let mut queue = VecDeque::from([start]);
+        let mut found = vec![];
+        while let Some(current) = queue.pop_front() {
+            for edge in &self.edges {
+                if edge.kind == EdgeKind::Replaces {
+                    continue;
+                }
+                let (from, to) = direction(edge);
+                if from == current && !visited[to] {
+                    visited[to] = true;
+                    found.push(&self.nodes[to]);
+                    queue.push_back(to);
+                }
+            }
+        }
+        found
+    }
+
+    /// Render graph as Graphviz DOT. Migrations of each app are grouped in
+    /// cluster. Run before edges are dashed and replaces edges are dotted.
+    /// Applied migrations are filled green and unapplied migrations are
+    /// filled red when applied status is set
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec!["digraph migrations {".to_string()];
+        lines.push("    node [shape=box];".to_string());
+        for (cluster, app) in self.apps().into_iter().enumerate() {
+            lines.push(format!("    subgraph cluster_{cluster} {{"));
+            lines.push(format!("        label={app:?};"));
+            for (index, node) in self.nodes.iter().enumerate() {
+                if node.app != app {
+                    continue;
+                }
+                let fill = match node.applied {
+                    Some(true) => ", style=filled, fillcolor=palegreen",
+                    Some(false) => ", style=filled, fillcolor=lightpink",
+                    None => "",
+                };
+                lines.push(format!(
+                    "        n{index} [label={:?}{fill}];",
+                    node.to_string()
+                ));
+            }
+            lines.push("    }".to_string());
+        }
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::Parent => "",
+                EdgeKind::RunBefore => " [style=dashed, label=\"run before\"]",
+                EdgeKind::Replaces => " [style=dotted, label=\"replaces\"]",
+            };
+            lines.push(format!("    n{} -> n{}{style};", edge.source, edge.target));
+        }
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+
+    /// Render graph as Mermaid flowchart. Migrations of each app are grouped
+    /// in subgraph. Run before edges are dotted and replaces edges are thick.
+    /// Applied and unapplied migrations use `applied` and `pending` class when
+    /// applied status is set
+    #[must_use]
+    pub fn to_mermaid(&self) -> String {
+        let mut lines = vec!["flowchart TD".to_string()];
+        for (cluster, app) in self.apps().into_iter().enumerate() {
+            lines.push(format!("    subgraph app{cluster} [\"{app}\"]"));
+            for (index, node) in self.nodes.iter().enumerate() {
+                if node.app == app {
+                    lines.push(format!("        n{index}[\"{node}\"]"));
+                }
+            }
+            lines.push("    end".to_string());
+        }
+        for edge in &self.edges {
+            let arrow = match edge.kind {
+                EdgeKind::Parent => "-->",
+                EdgeKind::RunBefore => "-. run before .->",
+                EdgeKind::Replaces => "== replaces ==>",
+            };
+            lines.push(format!("    n{} {arrow} n{}", edge.source, edge.target));
+        }
+        for (class, applied, fill) in [("applied", true, "#98fb98"), ("pending", false, "#ffb6c1")]
+        {
+            let members = self
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| node.applied == Some(applied))
+                .map(|(index, _)| format!("n{index}"))
+                .collect::<Vec<_>>();
+            if !members.is_empty() {
+                lines.push(format!("    classDef {class} fill:{fill}"));
+                lines.push(format!("    class {} {class}", members.join(",")));
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn apps(&self) -> Vec<&str> {
+        let mut apps = Vec::new();
+        for node in &self.nodes {
+            if !apps.contains(&node.app.as_str()) {
+                apps.push(node.app.as_str());
+            }
+        }
+        apps
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use sqlx::Sqlite;
+
+    use super::{EdgeKind, MigrationGraph, Node};
+    use crate::migration::{AppliedMigrationSqlRow, Migration};
+    use crate::test_utils::migration;
+    use crate::vec_box;
+
+    #[test]
+    fn migration_graph() {
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        struct B;
+        migration!(B, "b", vec_box!(A), vec_box!(), vec_box!());
+        struct C;
+        migration!(C, "c", vec_box!(A), vec_box!(), vec_box!(B));
+        struct D;
+        migration!(D, "d", vec_box!(A), vec_box!(B, C), vec_box!());
+        let migrations: Vec<Box<dyn Migration<Sqlite>>> = vec_box!(A, B, C, D);
+        let mut graph = MigrationGraph::from_migrations(&migrations);
+
+        let names = |nodes: Vec<&Node>| {
+            nodes
+                .iter()
+                .map(|node| node.name().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(graph.nodes().len(), 4);
+        assert_eq!(
+            graph
+                .edges()
+                .iter()
+                .filter(|edge| edge.kind() == EdgeKind::Replaces)
+                .count(),
+            2
+        );
+        assert_eq!(names(graph.roots("test")), vec!["a"]);
+        assert_eq!(names(graph.leaves("test")), vec!["b", "c", "d"]);
+        assert_eq!(names(graph.ancestors("test", "b")), vec!["a", "c"]);
+        assert_eq!(names(graph.descendants("test", "a")), vec!["b", "c", "d"]);
+        assert!(graph.descendants("test", "missing").is_empty());
+
+        graph.set_applied(&[AppliedMigrationSqlRow::new(1, "test", "a")]);
+        assert_eq!(graph.node("test", "a").unwrap().applied(), Some(true));
+        assert_eq!(graph.node("test", "b").unwrap().applied(), Some(false));
+        let dot = graph.to_dot();
+        assert!(dot.contains("n0 -> n1;"));
+        assert!(dot.contains("n2 -> n1 [style=dashed, label=\"run before\"];"));
+        assert!(dot.contains("n3 -> n1 [style=dotted, label=\"replaces\"];"));
+        let mermaid = graph.to_mermaid();
+        assert!(mermaid.contains("n3 == replaces ==> n2"));
+        assert!(mermaid.contains("class n0 applied"));
+        assert!(mermaid.contains("class n1,n2,n3 pending"));
+    }
+}
@@ -0,0 +1,585 @@
+//! Module for adopting database previously managed by other migration tools
+//!
+//! [`Importer`] reads applied versions from migration table of other tool
+//! (`sqlx::migrate!`, refinery or diesel), maps each version to migration
+//! using user supplied mapping and marks mapped migrations as applied using
+//! original applied time without running their operations.
+#![cfg_attr(
+    feature = "postgres",
+    doc = r#"
+# Example
+```rust,no_run
+use sqlx::Postgres;
+use sqlx_migrator::import::{ImportSource, Importer};
+use sqlx_migrator::migrator::Migrator;
+
+# async fn run(connection: &mut sqlx::PgConnection) -> Result<(), sqlx_migrator::Error> {
+let migrator = Migrator::<Postgres>::default();
+let importer = Importer::new(&migrator, ImportSource::Sqlx)
+    .map("20240101000000", "main", "m0001_simple")
+    .map("20240102000000", "main", "m0002_with_parents");
+// check what will be imported
+for entry in importer.preview(connection).await? {
+    println!("{} => {:?}", entry.version(), entry.status());
+}
+importer.import(connection).await?;
+# Ok(())
+# }
+```
+"#
+)]
+
+use std::collections::HashMap;
+
+use sqlx::{Connection, Database};
+
+use crate::error::Error;
+use crate::migration::Migration;
+use crate::migrator::Migrate;
+
+/// Migration table of other migration tool from which applied migrations are
+/// imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ImportSource {
+    /// `_sqlx_migrations` table created by `sqlx::migrate!`. Only successful
+    /// migrations are imported
+    Sqlx,
+    /// `refinery_schema_history` table created by refinery
+    Refinery,
+    /// `__diesel_schema_migrations` table created by diesel
+    Diesel,
+}
+
+impl ImportSource {
+    /// Name of migration table used by source
+    #[must_use]
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            ImportSource::Sqlx => "_sqlx_migrations",
+            ImportSource::Refinery => "refinery_schema_history",
+            ImportSource::Diesel => "__diesel_schema_migrations",
+        }
+    }
+
+    /// Query to fetch version and applied time as text ordered by version.
+    /// `text_type` is type used for casting value to text for database
+    #[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+    pub(crate) fn fetch_rows_query(self, text_type: &str) -> String {
+        let table_name = self.table_name();
+        match self {
+            ImportSource::Sqlx => {
+                format!(
+                    "SELECT CAST(version AS {text_type}) AS version, CAST(installed_on AS \
+                     {text_type}) AS applied_time FROM {table_name} WHERE success ORDER BY version"
+                )
+            }
+            ImportSource::Refinery => {
+                format!(
+                    "SELECT CAST(version AS {text_type}) AS version, applied_on AS applied_time \
+                     FROM {table_name} ORDER BY version"
+                )
+            }
+            ImportSource::Diesel => {
+                format!(
+                    "SELECT version, CAST(run_on AS {text_type}) AS applied_time FROM \
+                     {table_name} ORDER BY version"
+                )
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for ImportSource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sqlx" => Ok(ImportSource::Sqlx),
+            "refinery" => Ok(ImportSource::Refinery),
+            "diesel" => Ok(ImportSource::Diesel),
+            _ => {
+                Err(Error::ImportError {
+                    message: format!("unknown import source {s}"),
+                })
+            }
+        }
+    }
+}
+
+/// Struct representing a row of migration table of other migration tool
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct ImportSourceRow {
+    version: String,
+    applied_time: Option<String>,
+}
+
+impl ImportSourceRow {
+    /// Return version of migration
+    #[must_use]
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Return time when migration was applied by other migration tool
+    #[must_use]
+    pub fn applied_time(&self) -> Option<&str> {
+        self.applied_time.as_deref()
+    }
+}
+
+/// Status of importing a version
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ImportStatus {
+    /// Migration will be marked as applied
+    Import,
+    /// Migration is already marked as applied
+    AlreadyApplied,
+    /// No mapping exists for version so version is skipped
+    Unmapped,
+    /// Mapped migration is not present in migrator
+    UnknownMigration,
+}
+
+/// Entry describing how single version of other migration tool is imported
+#[derive(Debug, Clone)]
+pub struct ImportEntry {
+    version: String,
+    applied_time: Option<String>,
+    migration: Option<(String, String)>,
+    status: ImportStatus,
+}
+
+impl ImportEntry {
+    /// Return version of other migration tool
+    #[must_use]
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Return original applied time
+    #[must_use]
+    pub fn applied_time(&self) -> Option<&str> {
+        self.applied_time.as_deref()
+    }
+
+    /// Return app and name of mapped migration
+    #[must_use]
+    pub fn migration(&self) -> Option<(&str, &str)> {
+        self.migration
+            .as_ref()
+            .map(|(app, name)| (app.as_str(), name.as_str()))
+    }
+
+    /// Return import status
+    #[must_use]
+    pub fn status(&self) -> &ImportStatus {
+        &self.status
+    }
+}
+
+/// Importer which marks migrations applied by other migration tool as applied
+pub struct Importer<'migrator, DB>
+where
+    DB: Database,
+{
+    migrator: &'migrator dyn Migrate<DB>,
+    source: ImportSource,
+    mapping: HashMap<String, (String, String)>,
+}
+
+impl<'migrator, DB> Importer<'migrator, DB>
+where
+    DB: Database,
+{
+    /// Create new importer for migrator which imports from source
+    #[must_use]
+    pub fn new(migrator: &'migrator dyn Migrate<DB>, source: ImportSource) -> Self {
+        Self {
+            migrator,
+            source,
+            mapping: HashMap::new(),
+        }
+    }
+
+    /// Map version of other migration tool to migration with app and name
+    #[must_use]
+    pub fn map(self, version: impl Into<String>, app: &str, name: &str) -> Self {
+        let mut importer = self;
+        importer
+            .mapping
+            .insert(version.into(), (app.to_string(), name.to_string()));
+        importer
+    }
+
+    /// Generate list of import entries without marking any migration as
+    /// applied. Preview doesn't create migrator table, if migrator table
+    /// doesn't exist no migration is considered as applied
+    ///
+    /// # Errors
+    /// If rows cannot be fetched from source table or migrator table
+    pub async fn preview(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<Vec<ImportEntry>, Error> {
+        let source_rows = self
+            .migrator
+            .fetch_import_source_rows(connection, self.source)
+            .await?;
+        let applied_migrations = if self.migrator.migration_table_exists(connection).await? {
+            self.migrator
+                .fetch_applied_migration_from_db(connection)
+                .await?
+        } else {
+            vec![]
+        };
+        let mut entries = Vec::with_capacity(source_rows.len());
+        for row in source_rows {
+            let migration = self.mapping.get(&row.version).cloned();
+            let status = match &migration {
+                None => ImportStatus::Unmapped,
+                Some((app, name))
+                    if applied_migrations
+                        .iter()
+                        .any(|applied| applied.app() == app && applied.name() == name) =>
+                {
+                    ImportStatus::AlreadyApplied
+                }
+                Some((app, name)) if self.find_migration(app, name).is_some() => {
+                    ImportStatus::Import
+                }
+                Some(_) => ImportStatus::UnknownMigration,
+            };
+            entries.push(ImportEntry {
+                version: row.version,
+                applied_time: row.applied_time,
+                migration,
+                status,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Import mapped migrations as applied while holding migrator lock.
+    ///
+    /// Returns list of import entries
+    ///
+    /// # Errors
+    /// If any version is mapped to migration which is not present in migrator
+    /// or if database operation fails
+    pub async fn import(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<Vec<ImportEntry>, Error> {
+        self.migrator.lock(connection).await?;
+        let result = self.import_entries(connection).await;
+        self.migrator.unlock(connection).await?;
+        result
+    }
+
+    async fn import_entries(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<Vec<ImportEntry>, Error> {
+        self.migrator
+            .ensure_migration_table_exists(connection)
+            .await?;
+        let entries = self.preview(connection).await?;
+        if let Some(unknown) = entries
+            .iter()
+            .find(|entry| entry.status == ImportStatus::UnknownMigration)
+        {
+            let (app, name) = unknown.migration().unwrap_or_default();
+            return Err(Error::ImportError {
+                message: format!(
+                    "version {} is mapped to migration {app}:{name} which doesn't exists",
+                    unknown.version
+                ),
+            });
+        }
+        // import all entries or none of them
+        let mut transaction = connection.begin().await?;
+        for entry in &entries {
+            if entry.status != ImportStatus::Import {
+                continue;
+            }
+            let Some(migration) = entry
+                .migration()
+                .and_then(|(app, name)| self.find_migration(app, name))
+            else {
+                continue;
+            };
+            tracing::debug!(
+                "importing version {} as {} : {}",
+                entry.version,
+                migration.app(),
+                migration.name()
+            );
+            match &entry.applied_time {
+                Some(applied_time) => {
+                    self.migrator
+                        .add_migration_to_db_table_with_applied_time(
+                            &mut transaction,
+                            migration,
+                            applied_time,
+                        )
+                        .await?;
+                }
+                None => {
+                    self.migrator
+                        .add_migration_to_db_table(&mut transaction, migration)
+                        .await?;
+                }
+            }
+        }
+        transaction.commit().await?;
+        Ok(entries)
+    }
+
+    /// Find non virtual migration of migrator which can be imported
+    #[expect(clippy::borrowed_box)]
+    fn find_migration(&self, app: &str, name: &str) -> Option<&Box<dyn Migration<DB>>> {
+        self.migrator.migrations().iter().find(|migration| {
+            !migration.is_virtual() && migration.app() == app && migration.name() == name
+        })
+    }
+}
+
+/// Convert RFC3339 time such as `2024-01-02T03:04:05.123+05:30` to UTC time
+/// in `YYYY-MM-DD HH:MM:SS` format. Fraction of second is dropped. Returns
+/// `None` if value is not RFC3339 time
+#[cfg(feature = "mysql")]
+pub(crate) fn rfc3339_to_utc(value: &str) -> Option<String> {
+    let number = |start: usize, end: usize| {
+        let digits = value.get(start..end)?;
+        if digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            digits.parse::<i64>().ok()
+        } else {
+            None
+        }
+    };
+    let separators = [(4, b'-'), (7, b'-'), (13, b':'), (16, b':')];
+    let bytes = value.as_bytes();
+    if bytes.len() < 20
+        || !matches!(bytes[10], b'T' | b't')
+        || separators
+            .iter()
+            .any(|&(index, separator)| bytes[index] != separator)
+    {
+        return None;
+    }
+    let (year, month, day) = (number(0, 4)?, number(5, 7)?, number(8, 10)?);
+    let (hour, minute, second) = (number(11, 13)?, number(14, 16)?, number(17, 19)?);
+    let mut offset = &value[19..];
+    if let Some(fraction) = offset.strip_prefix('.') {
+        offset = fraction.trim_start_matches(|c: char| c.is_ascii_digit());
+    }
+    let offset_minutes = match offset {
+        "Z" | "z" => 0,
+        _ if offset.len() == 6 && offset.as_bytes()[3] == b':' => {
+            let minutes = number(value.len() - 5, value.len() - 3)? * 60
+                + number(value.len() - 2, value.len())?;
+            match offset.as_bytes()[0] {
+                b'+' => minutes,
+                b'-' => -minutes,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second
+        - offset_minutes * 60;
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let time = seconds.rem_euclid(86_400);
+    Some(format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    ))
+}
+
+/// Number of days since 1970-01-01 for proleptic gregorian date
+#[cfg(feature = "mysql")]
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic gregorian date for number of days since 1970-01-01
+#[cfg(feature = "mysql")]
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use sqlx::Sqlite;
+
+    #[cfg(feature = "mysql")]
+    use super::rfc3339_to_utc;
+    use super::{ImportEntry, ImportSource, ImportStatus, Importer};
+    use crate::error::Error;
+    use crate::migrator::{DatabaseOperation, Info, Migrator};
+    use crate::test_utils::{migration, sqlite_pool};
+    use crate::vec_box;
+
+    #[tokio::test]
+    async fn import_from_sqlx() {
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        struct B;
+        migration!(B, "b", vec_box!(A), vec_box!(), vec_box!());
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box!(A, B, ("test", "c")));
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        sqlx::query(
+            "CREATE TABLE _sqlx_migrations (version BIGINT PRIMARY KEY, description TEXT NOT \
+             NULL, installed_on TIMESTAMP NOT NULL, success BOOLEAN NOT NULL)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO _sqlx_migrations VALUES (1, 'a', '2024-01-01 10:00:00', TRUE), (2, 'b', \
+             '2024-01-02 10:00:00', TRUE), (3, 'c', '2024-01-03 10:00:00', TRUE)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+        let importer = Importer::new(&migrator, ImportSource::Sqlx)
+            .map("1", "test", "a")
+            .map("2", "test", "b");
+        let preview = importer.preview(&mut conn).await.unwrap();
+        let statuses = preview.iter().map(ImportEntry::status).collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                &ImportStatus::Import,
+                &ImportStatus::Import,
+                &ImportStatus::Unmapped
+            ]
+        );
+        // preview doesn't create migrator table
+        assert!(!migrator.migration_table_exists(&mut conn).await.unwrap());
+        importer.import(&mut conn).await.unwrap();
+        let applied = migrator
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied[0].applied_time(), "2024-01-01 10:00:00");
+        // virtual migration cannot be imported
+        let unknown = Importer::new(&migrator, ImportSource::Sqlx).map("3", "test", "c");
+        assert_eq!(
+            unknown.preview(&mut conn).await.unwrap()[2].status(),
+            &ImportStatus::UnknownMigration
+        );
+        assert!(matches!(
+            unknown.import(&mut conn).await,
+            Err(Error::ImportError { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn import_is_atomic() {
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box!(A));
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        sqlx::query(
+            "CREATE TABLE _sqlx_migrations (version BIGINT PRIMARY KEY, description TEXT NOT \
+             NULL, installed_on TIMESTAMP NOT NULL, success BOOLEAN NOT NULL)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO _sqlx_migrations VALUES (1, 'a', '2024-01-01 10:00:00', TRUE), (2, 'a', \
+             '2024-01-02 10:00:00', TRUE)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+        // second version inserts same migration again which fails unique constraint
+        let importer = Importer::new(&migrator, ImportSource::Sqlx)
+            .map("1", "test", "a")
+            .map("2", "test", "a");
+        assert!(importer.import(&mut conn).await.is_err());
+        assert!(migrator
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[cfg(feature = "mysql")]
+    #[test]
+    fn refinery_applied_time_to_utc() {
+        assert_eq!(
+            rfc3339_to_utc("2024-01-02T03:04:05Z").as_deref(),
+            Some("2024-01-02 03:04:05")
+        );
+        assert_eq!(
+            rfc3339_to_utc("2024-03-01T01:30:00.123456789+05:30").as_deref(),
+            Some("2024-02-29 20:00:00")
+        );
+        assert_eq!(
+            rfc3339_to_utc("2023-12-31T22:00:00-03:00").as_deref(),
+            Some("2024-01-01 01:00:00")
+        );
+        // leap day stays same when offset doesn't cross midnight
+        assert_eq!(
+            rfc3339_to_utc("2024-02-29T12:00:00+01:00").as_deref(),
+            Some("2024-02-29 11:00:00")
+        );
+        // negative offset moves time across midnight into leap day
+        assert_eq!(
+            rfc3339_to_utc("2024-02-28T20:30:00-04:00").as_deref(),
+            Some("2024-02-29 00:30:00")
+        );
+        // non leap years including century which isn't divisible by 400
+        assert_eq!(
+            rfc3339_to_utc("2023-03-01T00:15:00+00:30").as_deref(),
+            Some("2023-02-28 23:45:00")
+        );
+        assert_eq!(
+            rfc3339_to_utc("2100-02-28T23:00:00-02:00").as_deref(),
+            Some("2100-03-01 01:00:00")
+        );
+        assert_eq!(
+            rfc3339_to_utc("2000-02-28T23:00:00-02:00").as_deref(),
+            Some("2000-02-29 01:00:00")
+        );
+        assert_eq!(
+            rfc3339_to_utc("1999-12-31t23:59:59.5z").as_deref(),
+            Some("1999-12-31 23:59:59")
+        );
+        assert_eq!(rfc3339_to_utc("2024-01-02T03:04:05+0530"), None);
+        assert_eq!(rfc3339_to_utc("2024-01-02T03:04:05*05:30"), None);
+        assert_eq!(rfc3339_to_utc("2024-01-02 03:04:05"), None);
+        assert_eq!(rfc3339_to_utc("2024-01-02T03:04:05"), None);
+    }
+}
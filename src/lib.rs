@@ -5,6 +5,15 @@
 //! Check `README.MD` for more detailed information of how to use a crate
 //! and visit [`Operation`], [`Migration`] and [`Migrator`]
 
+// tokio is only used by tests which requires sqlite feature
+#[cfg(feature = "macros")]
+pub use sqlx_migrator_macros::embed_migrations;
+#[cfg(all(test, not(feature = "sqlite")))]
+use tokio as _;
+
+// allow macros which generate `::sqlx_migrator` path to be used inside crate
+extern crate self as sqlx_migrator;
+
 #[cfg(feature = "cli")]
 pub use crate::cli::MigrationCommand;
 pub use crate::error::Error;
@@ -12,10 +21,33 @@ pub use crate::migration::Migration;
 pub use crate::migrator::{Info, Migrate, Migrator, Plan};
 pub use crate::operation::Operation;
 
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+pub mod backfill;
 #[cfg(feature = "cli")]
 pub mod cli;
 pub mod error;
+pub mod graph;
+pub mod import;
 mod macros;
 pub mod migration;
 pub mod migrator;
+pub mod multi_database;
 pub mod operation;
+pub mod schema;
+pub mod script;
+#[cfg(any(
+    feature = "cli",
+    feature = "postgres",
+    feature = "mysql",
+    feature = "sqlite"
+))]
+mod sleep;
+pub mod snapshot;
+pub mod sql_migration;
+pub mod squash;
+pub mod tenant;
+/// Module containing helpers shared by tests
+#[cfg(all(test, feature = "sqlite"))]
+mod test_utils;
+#[cfg(feature = "testing")]
+pub mod testing;
@@ -10,17 +10,103 @@ macro_rules! vec_box {
 /// Macro for implementing the [Migration](crate::migration::Migration) trait
 /// for the provided database.
 ///
-/// This macro will use current file name as name for migration
-///
-/// This macro expects the following arguments:
+/// Macro can be used in two forms. First form expects following positional
+/// arguments:
 /// - `$db:ty`: the type of database
 /// - `$op:ty`: The type for which the migration is being implemented
 /// - `$app_name:literal`: Name of app to be used for app variable
-/// - `$migration_name:literal`: Name of app to be used for app variable
+/// - `$migration_name:literal`: Name of migration
 /// - `$parents:expr`: List of parents migration.
 /// - `$operations:expr`: List of operations
+///
+/// Second form expects `$db:ty` and `$op:ty` followed by keyed arguments in
+/// below order
+/// - `app = $app:expr`: Optional name of app. If not provided name of parent
+///   module of module where macro is used is used as app name
+/// - `name = $name:expr`: Optional name of migration. If not provided current
+///   file name without extension is used as name. For `mod.rs` file name of its
+///   directory is used
+/// - `parents = $parents:expr`: List of parents migration
+/// - `operations = $operations:expr`: List of operations
+/// - `replaces = $replaces:expr`: Optional list of replaced migration
+/// - `run_before = $run_before:expr`: Optional list of run before migration
+/// - `atomic = $atomic:expr`: Optional atomic value of migration
+///
+/// ```rust,ignore
+/// // inside file migrations/m0003_use_macros.rs
+/// sqlx_migrator::migration!(
+///     sqlx::Postgres,
+///     M0003Migration,
+///     app = "main",
+///     parents = sqlx_migrator::vec_box![("main", "m0002_with_parents")],
+///     operations = sqlx_migrator::vec_box![M0003Operation],
+///     atomic = false
+/// );
+/// ```
 #[macro_export]
 macro_rules! migration {
+    (@value_or $value:expr, $default:expr) => {
+        $value
+    };
+    (@value_or , $default:expr) => {
+        $default
+    };
+    (
+        $db:ty,
+        $op:ty,
+        $(app = $app_name:expr,)?
+        $(name = $migration_name:expr,)?
+        parents = $parents:expr,
+        operations = $operations:expr
+        $(, replaces = $replaces:expr)?
+        $(, run_before = $run_before:expr)?
+        $(, atomic = $atomic:expr)?
+        $(,)?
+    ) => {
+        #[async_trait::async_trait]
+        impl sqlx_migrator::migration::Migration<$db> for $op {
+            fn app(&self) -> &str {
+                sqlx_migrator::migration!(@value_or $($app_name)?, {
+                    const APP: &str =
+                        sqlx_migrator::migration::app_from_module_path(module_path!());
+                    APP
+                })
+            }
+
+            fn name(&self) -> &str {
+                sqlx_migrator::migration!(@value_or $($migration_name)?, {
+                    const NAME: &str = sqlx_migrator::migration::name_from_file(file!());
+                    NAME
+                })
+            }
+
+            fn parents(&self) -> Vec<Box<dyn sqlx_migrator::migration::Migration<$db>>> {
+                $parents
+            }
+
+            fn operations(&self) -> Vec<Box<dyn sqlx_migrator::operation::Operation<$db>>> {
+                $operations
+            }
+
+            $(
+                fn replaces(&self) -> Vec<Box<dyn sqlx_migrator::migration::Migration<$db>>> {
+                    $replaces
+                }
+            )?
+
+            $(
+                fn run_before(&self) -> Vec<Box<dyn sqlx_migrator::migration::Migration<$db>>> {
+                    $run_before
+                }
+            )?
+
+            $(
+                fn is_atomic(&self) -> bool {
+                    $atomic
+                }
+            )?
+        }
+    };
     (
         $db:ty, $op:ty, $app_name:literal, $migration_name:literal, $parents:expr, $operations:expr
     ) => {
@@ -49,12 +135,16 @@ macro_rules! migration {
 ///
 /// This macro calls [`migration`] macro with db value already set as
 /// `sqlx::Any`
+/// and supports both positional and keyed form of [`migration`] macro
 #[macro_export]
 #[cfg(all(
     any(feature = "postgres", feature = "mysql", feature = "sqlite"),
     feature = "any"
 ))]
 macro_rules! any_migration {
+    ($op:ty, $($key:ident = $value:expr),+ $(,)?) => {
+        sqlx_migrator::migration!(sqlx::Any, $op, $($key = $value),+);
+    };
     ($op:ty, $app_name:expr, $migration_name:expr, $parents:expr, $operations:expr) => {
         sqlx_migrator::migration!(
             sqlx::Any,
@@ -71,9 +161,13 @@ macro_rules! any_migration {
 ///
 /// This macro calls [`migration`] macro with db value already set as
 /// `sqlx::MySql`
+/// and supports both positional and keyed form of [`migration`] macro
 #[macro_export]
 #[cfg(feature = "mysql")]
 macro_rules! mysql_migration {
+    ($op:ty, $($key:ident = $value:expr),+ $(,)?) => {
+        sqlx_migrator::migration!(sqlx::MySql, $op, $($key = $value),+);
+    };
     ($op:ty, $app_name:expr, $migration_name:expr, $parents:expr, $operations:expr) => {
         sqlx_migrator::migration!(
             sqlx::MySql,
@@ -90,9 +184,13 @@ macro_rules! mysql_migration {
 ///
 /// This macro calls [`migration`] macro with db value already set as
 /// `sqlx::Postgres`
+/// and supports both positional and keyed form of [`migration`] macro
 #[macro_export]
 #[cfg(feature = "postgres")]
 macro_rules! postgres_migration {
+    ($op:ty, $($key:ident = $value:expr),+ $(,)?) => {
+        sqlx_migrator::migration!(sqlx::Postgres, $op, $($key = $value),+);
+    };
     ($op:ty, $app_name:expr, $migration_name:expr, $parents:expr, $operations:expr) => {
         sqlx_migrator::migration!(
             sqlx::Postgres,
@@ -109,9 +207,13 @@ macro_rules! postgres_migration {
 ///
 /// This macro calls [`migration`] macro with db value already set as
 /// `sqlx::Sqlite`
+/// and supports both positional and keyed form of [`migration`] macro
 #[macro_export]
 #[cfg(feature = "sqlite")]
 macro_rules! sqlite_migration {
+    ($op:ty, $($key:ident = $value:expr),+ $(,)?) => {
+        sqlx_migrator::migration!(sqlx::Sqlite, $op, $($key = $value),+);
+    };
     ($op:ty, $app_name:expr, $migration_name:expr, $parents:expr, $operations:expr) => {
         sqlx_migrator::migration!(
             sqlx::Sqlite,
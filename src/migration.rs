@@ -55,6 +55,9 @@ impl Migration<Sqlite> for ExampleMigration {
 
 use std::hash::Hash;
 
+#[cfg(feature = "macros")]
+pub use sqlx_migrator_macros::Migration;
+
 use crate::operation::Operation;
 
 /// Trait for defining database migration
@@ -185,7 +188,7 @@ where
 ///
 /// This struct corresponds to the id, app, name, and applied time fields in the
 /// database. It is used to list the migrations that have been applied.
-#[derive(sqlx::FromRow, Clone)]
+#[derive(sqlx::FromRow, Clone, Debug)]
 pub struct AppliedMigrationSqlRow {
     id: i32,
     app: String,
@@ -194,7 +197,7 @@ pub struct AppliedMigrationSqlRow {
 }
 
 impl AppliedMigrationSqlRow {
-    #[cfg(test)]
+    #[cfg(any(all(test, feature = "sqlite"), feature = "testing"))]
     pub(crate) fn new(id: i32, app: &str, name: &str) -> Self {
         Self {
             id,
@@ -212,6 +215,18 @@ impl AppliedMigrationSqlRow {
         self.id
     }
 
+    /// Return app name of migration
+    #[must_use]
+    pub fn app(&self) -> &str {
+        &self.app
+    }
+
+    /// Return name of migration
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     /// Return migration applied time
     #[must_use]
     pub fn applied_time(&self) -> &str {
@@ -224,3 +239,72 @@ impl<DB> PartialEq<Box<dyn Migration<DB>>> for AppliedMigrationSqlRow {
         self.app == other.app() && self.name == other.name()
     }
 }
+
+/// Return sub slice of str between `start` and `end` byte index. Both index
+/// must be at char boundary
+const fn sub_str(value: &str, start: usize, end: usize) -> &str {
+    let (_, rest) = value.as_bytes().split_at(start);
+    let (sub, _) = rest.split_at(end - start);
+    match std::str::from_utf8(sub) {
+        Ok(sub) => sub,
+        Err(_) => panic!("index is not at char boundary"),
+    }
+}
+
+/// Return migration name from file path, which is file name without
+/// extension. For `mod.rs` file name of parent directory is used.
+///
+/// Used by [`migration`](crate::migration!) macro to derive name from
+/// `file!()` at compile time
+#[must_use]
+pub const fn name_from_file(file: &str) -> &str {
+    let bytes = file.as_bytes();
+    let mut previous_start = 0;
+    let mut start = 0;
+    let mut end = bytes.len();
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'/' || bytes[index] == b'\\' {
+            previous_start = start;
+            start = index + 1;
+            end = bytes.len();
+        } else if bytes[index] == b'.' && index > start {
+            end = index;
+        }
+        index += 1;
+    }
+    let name = sub_str(file, start, end);
+    let is_mod = name.len() == 3
+        && bytes[start] == b'm'
+        && bytes[start + 1] == b'o'
+        && bytes[start + 2] == b'd';
+    if is_mod && start > 0 {
+        return sub_str(file, previous_start, start - 1);
+    }
+    name
+}
+
+/// Return name of parent module from module path. If module path contains
+/// only crate name than crate name is returned.
+///
+/// Used by [`migration`](crate::migration!) macro to derive app from
+/// `module_path!()` at compile time
+#[must_use]
+pub const fn app_from_module_path(module_path: &str) -> &str {
+    let bytes = module_path.as_bytes();
+    let mut previous_start = 0;
+    let mut start = 0;
+    let mut index = 0;
+    while index + 1 < bytes.len() {
+        if bytes[index] == b':' && bytes[index + 1] == b':' {
+            previous_start = start;
+            start = index + 2;
+            index += 1;
+        }
+        index += 1;
+    }
+    if start == 0 {
+        return module_path;
+    }
+    sub_str(module_path, previous_start, start - 2)
+}
@@ -5,7 +5,7 @@ use sqlx::MySql;
 use sqlx::Postgres;
 #[cfg(feature = "sqlite")]
 use sqlx::Sqlite;
-use sqlx::{Any, Arguments, Database};
+use sqlx::{Any, Arguments, Database, Executor};
 
 #[cfg(feature = "mysql")]
 use super::mysql;
@@ -13,9 +13,12 @@ use super::mysql;
 use super::postgres;
 #[cfg(feature = "sqlite")]
 use super::sqlite;
-use super::{DatabaseOperation, Migrator};
+use super::table_version::{ensure_latest_migrator_table, migrator_table_exists};
+use super::{migrator_table_names, DatabaseOperation, Migrator};
 use crate::error::Error;
+use crate::import::{ImportSource, ImportSourceRow};
 use crate::migration::{AppliedMigrationSqlRow, Migration};
+use crate::snapshot::{fetch_snapshot, Snapshot};
 
 /// get database name
 async fn get_database_name(
@@ -46,35 +49,52 @@ impl DatabaseOperation<Any> for Migrator<Any> {
         &self,
         connection: &mut <Any as Database>::Connection,
     ) -> Result<(), Error> {
-        let sql_query = match connection.backend_name() {
+        let queries = match connection.backend_name() {
             #[cfg(feature = "postgres")]
-            <Postgres as Database>::NAME => {
-                postgres::create_migrator_table_query(self.table_name())
-            }
+            <Postgres as Database>::NAME => postgres::table_version_queries(self.table_name()),
             #[cfg(feature = "sqlite")]
-            <Sqlite as Database>::NAME => sqlite::create_migrator_table_query(self.table_name()),
+            <Sqlite as Database>::NAME => sqlite::table_version_queries(self.table_name()),
             #[cfg(feature = "mysql")]
-            <MySql as Database>::NAME => mysql::create_migrator_table_query(self.table_name()),
+            <MySql as Database>::NAME => mysql::table_version_queries(self.table_name()),
             _ => return Err(Error::UnsupportedDatabase),
         };
-        sqlx::query(&sql_query).execute(connection).await?;
-        Ok(())
+        ensure_latest_migrator_table(self, connection, &queries).await
+    }
+
+    async fn migration_table_exists(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+    ) -> Result<bool, Error> {
+        let queries = match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => postgres::table_version_queries(self.table_name()),
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => sqlite::table_version_queries(self.table_name()),
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => mysql::table_version_queries(self.table_name()),
+            _ => return Err(Error::UnsupportedDatabase),
+        };
+        migrator_table_exists::<Any>(connection, &queries).await
     }
 
     async fn drop_migration_table_if_exists(
         &self,
         connection: &mut <Any as Database>::Connection,
     ) -> Result<(), Error> {
-        let sql_query = match connection.backend_name() {
+        let drop_table_query: fn(&str) -> String = match connection.backend_name() {
             #[cfg(feature = "postgres")]
-            <Postgres as Database>::NAME => postgres::drop_table_query(self.table_name()),
+            <Postgres as Database>::NAME => postgres::drop_table_query,
             #[cfg(feature = "sqlite")]
-            <Sqlite as Database>::NAME => sqlite::drop_table_query(self.table_name()),
+            <Sqlite as Database>::NAME => sqlite::drop_table_query,
             #[cfg(feature = "mysql")]
-            <MySql as Database>::NAME => mysql::drop_table_query(self.table_name()),
+            <MySql as Database>::NAME => mysql::drop_table_query,
             _ => return Err(Error::UnsupportedDatabase),
         };
-        sqlx::query(&sql_query).execute(connection).await?;
+        for table_name in migrator_table_names(self.table_name()) {
+            sqlx::query(&drop_table_query(&table_name))
+                .execute(&mut *connection)
+                .await?;
+        }
         Ok(())
     }
 
@@ -100,6 +120,42 @@ impl DatabaseOperation<Any> for Migrator<Any> {
         Ok(())
     }
 
+    async fn add_migration_to_db_table_with_applied_time(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        migration: &Box<dyn Migration<Any>>,
+        applied_time: &str,
+    ) -> Result<(), Error> {
+        let (sql_query, applied_time) = match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => {
+                (
+                    postgres::add_migration_with_applied_time_query(self.table_name()),
+                    applied_time.to_string(),
+                )
+            }
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => {
+                (
+                    sqlite::add_migration_with_applied_time_query(self.table_name()),
+                    applied_time.to_string(),
+                )
+            }
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => {
+                mysql::add_migration_with_applied_time_query(self.table_name(), applied_time)
+            }
+            _ => return Err(Error::UnsupportedDatabase),
+        };
+        sqlx::query(&sql_query)
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(applied_time)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
     async fn delete_migration_from_db_table(
         &self,
         connection: &mut <Any as Database>::Connection,
@@ -141,6 +197,48 @@ impl DatabaseOperation<Any> for Migrator<Any> {
             .await?)
     }
 
+    async fn fetch_import_source_rows(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        source: ImportSource,
+    ) -> Result<Vec<ImportSourceRow>, Error> {
+        let query = match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => postgres::import_source_rows_query(source),
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => sqlite::import_source_rows_query(source),
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => mysql::import_source_rows_query(source),
+            _ => return Err(Error::UnsupportedDatabase),
+        };
+        Ok(sqlx::query_as::<_, ImportSourceRow>(&query)
+            .fetch_all(connection)
+            .await?)
+    }
+
+    async fn fetch_schema_snapshot(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+    ) -> Result<Snapshot, Error> {
+        let (queries, table_name) = match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => {
+                (
+                    postgres::snapshot_queries(),
+                    self.table_name().to_lowercase(),
+                )
+            }
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => {
+                (sqlite::snapshot_queries(), self.table_name().to_string())
+            }
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => (mysql::snapshot_queries(), self.table_name().to_string()),
+            _ => return Err(Error::UnsupportedDatabase),
+        };
+        fetch_snapshot::<Any>(connection, &queries, &migrator_table_names(&table_name)).await
+    }
+
     async fn lock(&self, connection: &mut <Any as Database>::Connection) -> Result<(), Error> {
         let database_name = get_database_name(connection).await?;
         if let Some(name) = database_name {
@@ -148,7 +246,15 @@ impl DatabaseOperation<Any> for Migrator<Any> {
             let query = match connection.backend_name() {
                 #[cfg(feature = "postgres")]
                 <Postgres as Database>::NAME => {
-                    arguments.add(postgres::get_lock_id(&name, self.table_name()))?;
+                    let (tenant_schema,) =
+                        sqlx::query_as::<_, (Option<String>,)>(postgres::tenant_schema_query())
+                            .fetch_one(&mut *connection)
+                            .await?;
+                    arguments.add(postgres::get_lock_id(
+                        &name,
+                        tenant_schema.as_deref(),
+                        self.table_name(),
+                    ))?;
                     postgres::lock_database_query()
                 }
                 #[cfg(feature = "sqlite")]
@@ -174,7 +280,15 @@ impl DatabaseOperation<Any> for Migrator<Any> {
             let query = match connection.backend_name() {
                 #[cfg(feature = "postgres")]
                 <Postgres as Database>::NAME => {
-                    arguments.add(postgres::get_lock_id(&name, self.table_name()))?;
+                    let (tenant_schema,) =
+                        sqlx::query_as::<_, (Option<String>,)>(postgres::tenant_schema_query())
+                            .fetch_one(&mut *connection)
+                            .await?;
+                    arguments.add(postgres::get_lock_id(
+                        &name,
+                        tenant_schema.as_deref(),
+                        self.table_name(),
+                    ))?;
                     postgres::unlock_database_query()
                 }
                 #[cfg(feature = "sqlite")]
@@ -192,4 +306,83 @@ impl DatabaseOperation<Any> for Migrator<Any> {
         }
         Ok(())
     }
+
+    async fn switch_schema(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        schema: &str,
+    ) -> Result<(), Error> {
+        let sql_query = match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => postgres::switch_schema_query(schema),
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => mysql::switch_schema_query(schema),
+            _ => return Err(Error::UnsupportedDatabase),
+        };
+        // use query without arguments so it runs with simple text protocol
+        connection.execute(sql_query.as_str()).await?;
+        Ok(())
+    }
+
+    async fn fetch_schemas(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        query: &str,
+    ) -> Result<Vec<String>, Error> {
+        match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => {}
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => {}
+            _ => return Err(Error::UnsupportedDatabase),
+        }
+        let rows = sqlx::query_as::<_, (String,)>(query)
+            .fetch_all(connection)
+            .await?;
+        Ok(rows.into_iter().map(|(schema,)| schema).collect())
+    }
+
+    async fn fetch_current_schema(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+    ) -> Result<Option<String>, Error> {
+        let sql_query = match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => postgres::current_schema_query(),
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => mysql::current_database_query(),
+            _ => return Ok(None),
+        };
+        let (schema,) = sqlx::query_as::<_, (Option<String>,)>(sql_query)
+            .fetch_one(connection)
+            .await?;
+        Ok(schema)
+    }
+
+    async fn restore_schema(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        schema: Option<&str>,
+    ) -> Result<(), Error> {
+        let Some(schema) = schema else {
+            return Ok(());
+        };
+        match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => {
+                sqlx::query(postgres::restore_schema_query())
+                    .bind(schema)
+                    .execute(connection)
+                    .await?;
+            }
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => {
+                connection
+                    .execute(mysql::switch_schema_query(schema).as_str())
+                    .await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
 }
@@ -54,6 +54,16 @@ impl DatabaseOperation<Postgres> for CustomMigrator {
         Ok(())
     }
 
+    async fn migration_table_exists(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+    ) -> Result<bool, Error> {
+        let exists = sqlx::query_scalar("SELECT to_regclass('_custom_table_name') IS NOT NULL")
+            .fetch_one(connection)
+            .await?;
+        Ok(exists)
+    }
+
     async fn drop_migration_table_if_exists(
         &self,
         connection: &mut <Postgres as Database>::Connection,
@@ -141,7 +151,9 @@ use std::collections::HashMap;
 use sqlx::{Connection, Database};
 
 use crate::error::Error;
+use crate::import::{ImportSource, ImportSourceRow};
 use crate::migration::{AppliedMigrationSqlRow, Migration};
+use crate::snapshot::{Drift, Snapshot};
 
 /// Any database module which support mysql, sqlite and postgres by default
 #[cfg(all(
@@ -162,6 +174,10 @@ mod sqlite;
 #[cfg(feature = "postgres")]
 mod postgres;
 
+/// Module for tracking and upgrading layout version of migrator table
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+mod table_version;
+
 /// Module for testing
 #[cfg(all(test, feature = "sqlite"))]
 mod tests;
@@ -181,11 +197,13 @@ enum PlanType {
 /// A [`Plan`] can specify whether to apply or revert migrations, and may target
 /// all migrations, specific migrations, or a limited number of migrations.
 #[derive(Debug)]
+#[expect(clippy::struct_field_names)]
 pub struct Plan {
     plan_type: PlanType,
     app_migration: Option<(String, Option<String>)>,
     count: Option<usize>,
     fake: bool,
+    allow_irreversible: bool,
 }
 
 impl Plan {
@@ -199,6 +217,7 @@ impl Plan {
             app_migration,
             count,
             fake: false,
+            allow_irreversible: false,
         }
     }
 
@@ -219,6 +238,21 @@ impl Plan {
         plan
     }
 
+    /// Sets whether revert plan can contain irreversible migrations.
+    ///
+    /// By default, generating a revert plan fails before any migration is
+    /// reverted if plan contains migration with an operation whose
+    /// [`Operation::is_reversible`](crate::operation::Operation::is_reversible)
+    /// returns `false`. Allowing irreversible migrations is useful for only
+    /// listing plan, running such plan fails when irreversible operation is
+    /// reverted. Fake plans are never checked since operations are not run.
+    #[must_use]
+    pub fn allow_irreversible(self, allow_irreversible: bool) -> Self {
+        let mut plan = self;
+        plan.allow_irreversible = allow_irreversible;
+        plan
+    }
+
     /// Creates a new plan to apply all migrations.
     #[must_use]
     pub fn apply_all() -> Self {
@@ -262,6 +296,150 @@ impl Plan {
     }
 }
 
+/// Reason why migration is present in plan generated using
+/// [`Migrate::explain_migration_plan`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PlanReason {
+    /// Migration is directly targeted by plan. All migrations of plan which
+    /// doesn't target app are targeted
+    Targeted,
+    /// Migration is parent of other migration of apply plan
+    ParentOf {
+        /// App of child migration
+        app: String,
+        /// Name of child migration
+        name: String,
+    },
+    /// Migration needs to run before other migration of apply plan
+    RunsBefore {
+        /// App of migration which runs after
+        app: String,
+        /// Name of migration which runs after
+        name: String,
+    },
+    /// Migration is child of other migration of revert plan
+    ChildOf {
+        /// App of parent migration
+        app: String,
+        /// Name of parent migration
+        name: String,
+    },
+    /// Other migration of revert plan needs to run before migration
+    RunsAfter {
+        /// App of migration which runs before
+        app: String,
+        /// Name of migration which runs before
+        name: String,
+    },
+    /// Migration replaces parent of other migration of apply plan
+    Replacement {
+        /// App of replaced migration
+        app: String,
+        /// Name of replaced migration
+        name: String,
+    },
+    /// Migration is applied instead of migrations it replaces since none of
+    /// them are applied
+    ReplacesUnapplied {
+        /// Replaced migrations in `app:name` format
+        migrations: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for PlanReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanReason::Targeted => write!(f, "targeted directly"),
+            PlanReason::ParentOf { app, name } => write!(f, "required as parent of {app}:{name}"),
+            PlanReason::RunsBefore { app, name } => {
+                write!(f, "required to run before {app}:{name}")
+            }
+            PlanReason::ChildOf { app, name } => write!(f, "child of reverted {app}:{name}"),
+            PlanReason::RunsAfter { app, name } => {
+                write!(f, "required by run before of {app}:{name}")
+            }
+            PlanReason::Replacement { app, name } => write!(f, "replacement of {app}:{name}"),
+            PlanReason::ReplacesUnapplied { migrations } => {
+                write!(
+                    f,
+                    "replaced children {} are unapplied",
+                    migrations.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// Return reasons why each migration of generated plan is present in plan
+fn explain_plan<DB>(
+    plan: &Plan,
+    migration_list: &MigrationVec<DB>,
+    applied_migrations: &[AppliedMigrationSqlRow],
+) -> Vec<Vec<PlanReason>> {
+    migration_list
+        .iter()
+        .map(|&migration| {
+            let mut reasons = vec![];
+            let targeted = plan.app_migration.as_ref().is_none_or(|(app, name)| {
+                migration.app() == app && name.as_ref().is_none_or(|name| migration.name() == name)
+            });
+            if targeted {
+                reasons.push(PlanReason::Targeted);
+            }
+            let replaces = migration.replaces();
+            for &other in migration_list {
+                if targeted || other == migration {
+                    continue;
+                }
+                let app = other.app().to_string();
+                let name = other.name().to_string();
+                match plan.plan_type {
+                    PlanType::Apply => {
+                        let other_parents = other.parents();
+                        if other_parents.contains(migration) {
+                            reasons.push(PlanReason::ParentOf { app, name });
+                        } else if migration.run_before().contains(other) {
+                            reasons.push(PlanReason::RunsBefore { app, name });
+                        } else if let Some(replaced) = replaces
+                            .iter()
+                            .find(|replaced| other_parents.contains(replaced))
+                        {
+                            reasons.push(PlanReason::Replacement {
+                                app: replaced.app().to_string(),
+                                name: replaced.name().to_string(),
+                            });
+                        }
+                    }
+                    PlanType::Revert => {
+                        if migration.parents().contains(other) {
+                            reasons.push(PlanReason::ChildOf { app, name });
+                        } else if other.run_before().contains(migration) {
+                            reasons.push(PlanReason::RunsAfter { app, name });
+                        }
+                    }
+                }
+            }
+            // replacing migration is only applied when none of replaced migrations are
+            // applied
+            if matches!(plan.plan_type, PlanType::Apply)
+                && !replaces.is_empty()
+                && !replaces
+                    .iter()
+                    .any(|replaced| applied_migrations.iter().any(|applied| applied == replaced))
+            {
+                reasons.push(PlanReason::ReplacesUnapplied {
+                    migrations: replaces
+                        .iter()
+                        .map(|replaced| format!("{}:{}", replaced.app(), replaced.name()))
+                        .collect(),
+                });
+            }
+            reasons
+        })
+        .collect()
+}
+
 /// The [`Info`] trait provides database-agnostic methods for managing
 /// migrations and interacting with migration states.
 pub trait Info<DB> {
@@ -342,6 +520,12 @@ where
         connection: &mut <DB as Database>::Connection,
     ) -> Result<(), Error>;
 
+    /// Check whether migration table exists without creating it
+    async fn migration_table_exists(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<bool, Error>;
+
     /// Drop migration table if migration table exists
     async fn drop_migration_table_if_exists(
         &self,
@@ -355,6 +539,23 @@ where
         migration: &BoxMigration<DB>,
     ) -> Result<(), Error>;
 
+    /// Adds a migration record to the migration table in the database using
+    /// provided applied time instead of current time.
+    ///
+    /// By default adding migration with applied time is not supported and
+    /// returns [`Error::UnsupportedDatabase`]
+    async fn add_migration_to_db_table_with_applied_time(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migration: &BoxMigration<DB>,
+        applied_time: &str,
+    ) -> Result<(), Error> {
+        let _connection = connection;
+        let _migration = migration;
+        let _applied_time = applied_time;
+        return Err(Error::UnsupportedDatabase);
+    }
+
     /// Removes a migration record from the migration table in the database.
     async fn delete_migration_from_db_table(
         &self,
@@ -369,11 +570,95 @@ where
         connection: &mut <DB as Database>::Connection,
     ) -> Result<Vec<AppliedMigrationSqlRow>, Error>;
 
+    /// Fetches version and applied time of migrations applied by other
+    /// migration tool from its migration table.
+    ///
+    /// By default fetching import source rows is not supported and returns
+    /// [`Error::UnsupportedDatabase`]
+    async fn fetch_import_source_rows(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        source: ImportSource,
+    ) -> Result<Vec<ImportSourceRow>, Error> {
+        let _connection = connection;
+        let _source = source;
+        return Err(Error::UnsupportedDatabase);
+    }
+
+    /// Fetches normalized snapshot of database schema. Tables used by migrator
+    /// are not included in snapshot.
+    ///
+    /// By default fetching schema snapshot is not supported and returns
+    /// [`Error::UnsupportedDatabase`]
+    async fn fetch_schema_snapshot(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<Snapshot, Error> {
+        let _connection = connection;
+        return Err(Error::UnsupportedDatabase);
+    }
+
     /// Lock database while doing migrations so no two migrations run together
     async fn lock(&self, connection: &mut <DB as Database>::Connection) -> Result<(), Error>;
 
     /// Unlock locked database
     async fn unlock(&self, connection: &mut <DB as Database>::Connection) -> Result<(), Error>;
+
+    /// Switch connection to provided schema so migration table, lock and
+    /// operations are resolved inside that schema.
+    ///
+    /// By default switching schema is not supported and returns
+    /// [`Error::UnsupportedDatabase`]
+    async fn switch_schema(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        schema: &str,
+    ) -> Result<(), Error> {
+        let _connection = connection;
+        let _schema = schema;
+        return Err(Error::UnsupportedDatabase);
+    }
+
+    /// Fetch list of schema name using provided query. Query must return
+    /// single text column.
+    ///
+    /// By default fetching schema is not supported and returns
+    /// [`Error::UnsupportedDatabase`]
+    async fn fetch_schemas(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        query: &str,
+    ) -> Result<Vec<String>, Error> {
+        let _connection = connection;
+        let _query = query;
+        return Err(Error::UnsupportedDatabase);
+    }
+
+    /// Fetch current schema setting of connection which can be restored using
+    /// [`DatabaseOperation::restore_schema`] after switching schema.
+    ///
+    /// By default no schema setting is fetched and returns `None`
+    async fn fetch_current_schema(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<Option<String>, Error> {
+        let _connection = connection;
+        return Ok(None);
+    }
+
+    /// Restore schema setting of connection fetched using
+    /// [`DatabaseOperation::fetch_current_schema`].
+    ///
+    /// By default nothing is restored
+    async fn restore_schema(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        schema: Option<&str>,
+    ) -> Result<(), Error> {
+        let _connection = connection;
+        let _schema = schema;
+        return Ok(());
+    }
 }
 
 fn populate_replace_recursive<'populate, DB>(
@@ -592,6 +877,161 @@ fn get_recursive<'get, DB>(
     recursive_vec
 }
 
+/// Find migration using app and name
+fn find_migration<'migration, DB>(
+    migrations: &'migration [BoxMigration<DB>],
+    app: &str,
+    name: &str,
+) -> Result<&'migration BoxMigration<DB>, Error> {
+    migrations
+        .iter()
+        .find(|migration| migration.app() == app && migration.name() == name)
+        .ok_or(Error::MigrationNotFound {
+            app: app.to_string(),
+            name: name.to_string(),
+        })
+}
+
+/// Return messages for applied migrations whose parent or run before migration
+/// is not applied where either of them is provided migration. Migration is
+/// considered applied when migration replacing it is applied
+fn ordering_violations<DB>(
+    migrations: &[BoxMigration<DB>],
+    applied_migrations: &[AppliedMigrationSqlRow],
+    migration: &BoxMigration<DB>,
+) -> Vec<String> {
+    let is_applied = |search: &BoxMigration<DB>| {
+        applied_migrations.iter().any(|applied| applied == search)
+            || migrations.iter().any(|replacing| {
+                replacing.replaces().contains(search)
+                    && applied_migrations
+                        .iter()
+                        .any(|applied| applied == replacing)
+            })
+    };
+    let mut violations = vec![];
+    for child in migrations.iter().filter(|&child| is_applied(child)) {
+        // dependency along with whether it is parent or run before migration of child
+        let mut dependencies = child
+            .parents()
+            .into_iter()
+            .map(|parent| (parent, true))
+            .collect::<Vec<_>>();
+        for run_before in migrations {
+            if run_before.run_before().contains(child) {
+                dependencies.push((
+                    Box::new((run_before.app().to_string(), run_before.name().to_string())),
+                    false,
+                ));
+            }
+        }
+        for (dependency, is_parent) in dependencies {
+            if (child == migration || &dependency == migration) && !is_applied(&dependency) {
+                let child = format!("{}:{}", child.app(), child.name());
+                let dependency = format!("{}:{}", dependency.app(), dependency.name());
+                violations.push(
+                    if is_parent {
+                        format!(
+                            "migration {child} is applied but its parent migration {dependency} \
+                             is not applied"
+                        )
+                    } else {
+                        format!(
+                            "migration {child} is applied but migration {dependency} which runs \
+                             before it is not applied"
+                        )
+                    },
+                );
+            }
+        }
+    }
+    violations
+}
+
+/// Return whether all operations of migration are reversible
+pub(crate) fn is_reversible<DB>(migration: &BoxMigration<DB>) -> bool
+where
+    DB: Database,
+{
+    migration
+        .operations()
+        .iter()
+        .all(|operation| operation.is_reversible())
+}
+
+/// Apply or revert migrations generated for plan in order. Connection is
+/// expected to hold lock of migrator while migrations were generated and run
+pub(crate) async fn run_migrations<DB, M>(
+    migrator: &M,
+    connection: &mut <DB as Database>::Connection,
+    plan: &Plan,
+    migrations: &[&BoxMigration<DB>],
+) -> Result<(), Error>
+where
+    DB: Database,
+    M: Migrate<DB> + ?Sized,
+{
+    for migration in migrations {
+        match plan.plan_type {
+            PlanType::Apply => {
+                tracing::debug!("applying {} : {}", migration.app(), migration.name());
+                let operations = migration.operations();
+                if migration.is_atomic() {
+                    let mut transaction = connection.begin().await?;
+                    if !plan.fake {
+                        for operation in operations {
+                            operation.up(&mut transaction).await?;
+                        }
+                    }
+                    migrator
+                        .add_migration_to_db_table(&mut transaction, migration)
+                        .await?;
+                    transaction.commit().await?;
+                } else {
+                    if !plan.fake {
+                        for operation in operations {
+                            operation.up(connection).await?;
+                        }
+                    }
+                    migrator
+                        .add_migration_to_db_table(connection, migration)
+                        .await?;
+                }
+            }
+            PlanType::Revert => {
+                tracing::debug!("reverting {} : {}", migration.app(), migration.name());
+
+                // Reverse operation since last applied operation need to be reverted first
+                let mut operations = migration.operations();
+                operations.reverse();
+
+                if migration.is_atomic() {
+                    let mut transaction = connection.begin().await?;
+                    if !plan.fake {
+                        for operation in operations {
+                            operation.down(&mut transaction).await?;
+                        }
+                    }
+                    migrator
+                        .delete_migration_from_db_table(&mut transaction, migration)
+                        .await?;
+                    transaction.commit().await?;
+                } else {
+                    if !plan.fake {
+                        for operation in operations {
+                            operation.down(connection).await?;
+                        }
+                    }
+                    migrator
+                        .delete_migration_from_db_table(connection, migration)
+                        .await?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 /// The [`Migrate`] trait defines methods to manage and apply database
 /// migrations according to a given plan.
 ///
@@ -643,7 +1083,7 @@ where
                     .is_some()
                 {
                     return Err(Error::PlanError {
-                        message: format!("migration {child_name} replaced multiple times",),
+                        message: format!("migration {child_name} replaced multiple times"),
                     });
                 }
             }
@@ -850,11 +1290,49 @@ where
                 some_plan,
                 self.migrations(),
             )?;
+
+            // Check reversibility before reverting any migration so revert doesn't fail
+            // midway after partially reverting plan
+            if matches!(some_plan.plan_type, PlanType::Revert)
+                && !some_plan.fake
+                && !some_plan.allow_irreversible
+            {
+                let irreversible_migrations = migration_list
+                    .iter()
+                    .filter(|migration| !is_reversible(migration))
+                    .map(|migration| format!("{}:{}", migration.app(), migration.name()))
+                    .collect::<Vec<_>>();
+                if !irreversible_migrations.is_empty() {
+                    return Err(Error::PlanError {
+                        message: format!(
+                            "revert plan contains irreversible migrations {}",
+                            irreversible_migrations.join(", ")
+                        ),
+                    });
+                }
+            }
         }
 
         Ok(migration_list)
     }
 
+    /// Generate migration plan according to plan along with reasons why each
+    /// migration is present in plan. Useful to find out why migrations of
+    /// other apps are present in plan which targets app
+    ///
+    /// # Errors
+    /// If failed to generate migration plan
+    async fn explain_migration_plan(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        plan: &Plan,
+    ) -> Result<Vec<(&BoxMigration<DB>, Vec<PlanReason>)>, Error> {
+        let migration_list = self.generate_migration_plan(connection, Some(plan)).await?;
+        let applied_migrations = self.fetch_applied_migration_from_db(connection).await?;
+        let reasons = explain_plan(plan, &migration_list, &applied_migrations);
+        Ok(migration_list.into_iter().zip(reasons).collect())
+    }
+
     /// Run provided plan migrations
     ///
     /// # Errors
@@ -866,66 +1344,161 @@ where
     ) -> Result<(), Error> {
         tracing::debug!("running plan {:?}", plan);
         self.lock(connection).await?;
-        for migration in self.generate_migration_plan(connection, Some(plan)).await? {
-            match plan.plan_type {
-                PlanType::Apply => {
-                    tracing::debug!("applying {} : {}", migration.app(), migration.name());
-                    let operations = migration.operations();
-                    if migration.is_atomic() {
-                        let mut transaction = connection.begin().await?;
-                        if !plan.fake {
-                            for operation in operations {
-                                operation.up(&mut transaction).await?;
-                            }
-                        }
-                        self.add_migration_to_db_table(&mut transaction, migration)
-                            .await?;
-                        transaction.commit().await?;
-                    } else {
-                        if !plan.fake {
-                            for operation in operations {
-                                operation.up(connection).await?;
-                            }
-                        }
-                        self.add_migration_to_db_table(connection, migration)
-                            .await?;
-                    }
-                }
-                PlanType::Revert => {
-                    tracing::debug!("reverting {} : {}", migration.app(), migration.name());
-
-                    // Reverse operation since last applied operation need to be reverted first
-                    let mut operations = migration.operations();
-                    operations.reverse();
-
-                    if migration.is_atomic() {
-                        let mut transaction = connection.begin().await?;
-                        if !plan.fake {
-                            for operation in operations {
-                                operation.down(&mut transaction).await?;
-                            }
-                        }
-                        self.delete_migration_from_db_table(&mut transaction, migration)
-                            .await?;
-                        transaction.commit().await?;
-                    } else {
-                        if !plan.fake {
-                            for operation in operations {
-                                operation.down(connection).await?;
-                            }
-                        }
-                        self.delete_migration_from_db_table(connection, migration)
-                            .await?;
-                    }
-                }
+        let migrations = self.generate_migration_plan(connection, Some(plan)).await?;
+        run_migrations(self, connection, plan, &migrations).await?;
+        self.unlock(connection).await?;
+        Ok(())
+    }
+
+    /// Take snapshot of database schema along with list of currently applied
+    /// migrations. Migration table is not created when it doesn't exist,
+    /// instead no migrations are considered applied
+    ///
+    /// # Errors
+    /// If failed to fetch applied migrations or schema of database
+    async fn take_snapshot(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<Snapshot, Error> {
+        let applied_migrations = if self.migration_table_exists(connection).await? {
+            self.fetch_applied_migration_from_db(connection).await?
+        } else {
+            vec![]
+        };
+        let snapshot = self.fetch_schema_snapshot(connection).await?;
+        Ok(snapshot.with_migrations(
+            applied_migrations
+                .iter()
+                .map(|migration| format!("{}:{}", migration.app(), migration.name())),
+        ))
+    }
+
+    /// Compare database schema against snapshot and return list of drift.
+    /// Empty list is returned when database schema matches snapshot
+    ///
+    /// # Errors
+    /// If currently applied migrations differs from migrations applied when
+    /// snapshot was taken or if failed to take snapshot of database
+    async fn detect_drift(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        snapshot: &Snapshot,
+    ) -> Result<Vec<Drift>, Error> {
+        let live_snapshot = self.take_snapshot(connection).await?;
+        if live_snapshot.migrations() != snapshot.migrations() {
+            let not_in_snapshot = live_snapshot
+                .migrations()
+                .iter()
+                .filter(|migration| !snapshot.migrations().contains(migration))
+                .count();
+            let not_applied = snapshot
+                .migrations()
+                .iter()
+                .filter(|migration| !live_snapshot.migrations().contains(migration))
+                .count();
+            return Err(Error::SnapshotError {
+                message: format!(
+                    "snapshot was taken for different applied migrations, {not_in_snapshot} \
+                     applied migrations are not present in snapshot and {not_applied} migrations \
+                     of snapshot are not applied"
+                ),
+            });
+        }
+        Ok(snapshot.diff(&live_snapshot))
+    }
+
+    /// Mark migration as applied without running its operations and without
+    /// generating plan, so migration present in middle of chain can be marked.
+    /// Row is inserted while database is locked. Returns list of warnings when
+    /// parent or run before migration of migration is not applied
+    ///
+    /// # Errors
+    /// If migration doesn't exists, is already applied or failed to insert row
+    async fn mark_applied(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        app: &str,
+        name: &str,
+    ) -> Result<Vec<String>, Error> {
+        let migration = find_migration(self.migrations(), app, name)?;
+        self.ensure_migration_table_exists(connection).await?;
+        self.lock(connection).await?;
+        let result = async {
+            let mut applied_migrations = self.fetch_applied_migration_from_db(connection).await?;
+            if applied_migrations
+                .iter()
+                .any(|applied| applied == migration)
+            {
+                return Err(Error::PlanError {
+                    message: format!("migration {app}:{name} is already applied"),
+                });
             }
+            self.add_migration_to_db_table(connection, migration)
+                .await?;
+            applied_migrations = self.fetch_applied_migration_from_db(connection).await?;
+            Ok(ordering_violations(
+                self.migrations(),
+                &applied_migrations,
+                migration,
+            ))
         }
+        .await;
         self.unlock(connection).await?;
-        Ok(())
+        result
+    }
+
+    /// Remove applied row of migration without running its operations and
+    /// without generating plan. Row is deleted while database is locked.
+    /// Returns list of warnings when migration is parent or run before
+    /// migration of applied migration
+    ///
+    /// # Errors
+    /// If migration doesn't exists, is not applied or failed to delete row
+    async fn unmark_applied(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        app: &str,
+        name: &str,
+    ) -> Result<Vec<String>, Error> {
+        let migration = find_migration(self.migrations(), app, name)?;
+        self.ensure_migration_table_exists(connection).await?;
+        self.lock(connection).await?;
+        let result = async {
+            let mut applied_migrations = self.fetch_applied_migration_from_db(connection).await?;
+            if !applied_migrations
+                .iter()
+                .any(|applied| applied == migration)
+            {
+                return Err(Error::PlanError {
+                    message: format!("migration {app}:{name} is not applied"),
+                });
+            }
+            self.delete_migration_from_db_table(connection, migration)
+                .await?;
+            applied_migrations = self.fetch_applied_migration_from_db(connection).await?;
+            Ok(ordering_violations(
+                self.migrations(),
+                &applied_migrations,
+                migration,
+            ))
+        }
+        .await;
+        self.unlock(connection).await?;
+        result
     }
 }
 
-const DEFAULT_TABLE_NAME: &str = "_sqlx_migrator_migrations";
+pub(crate) const DEFAULT_TABLE_NAME: &str = "_sqlx_migrator_migrations";
+
+/// Name of tables used by migrator for migrator table name
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+pub(crate) fn migrator_table_names(table_name: &str) -> [String; 3] {
+    [
+        table_name.to_string(),
+        table_version::version_table_name(table_name),
+        crate::backfill::checkpoint_table_name(table_name),
+    ]
+}
 
 /// Migrator struct which store migrations graph and information related to
 /// different library supported migrations
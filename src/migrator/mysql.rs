@@ -1,8 +1,14 @@
-use sqlx::{Database, MySql};
+use sqlx::{Database, Executor, MySql};
 
-use super::{DatabaseOperation, Migrator};
+use super::table_version::{
+    ensure_latest_migrator_table, migrator_table_exists, version_table_name, TableVersionQueries,
+};
+use super::{migrator_table_names, DatabaseOperation, Migrator};
+use crate::backfill::checkpoint_table_name;
 use crate::error::Error;
+use crate::import::{rfc3339_to_utc, ImportSource, ImportSourceRow};
 use crate::migration::{AppliedMigrationSqlRow, Migration};
+use crate::snapshot::{fetch_snapshot, Snapshot, SnapshotQueries};
 
 /// create migrator table query
 #[must_use]
@@ -38,6 +44,39 @@ pub(crate) fn add_migration_query(table_name: &str) -> String {
     format!("INSERT INTO {table_name}(app, name) VALUES (?, ?)")
 }
 
+/// add migration with applied time query along with applied time bound to
+/// query. RFC3339 applied time such as time stored by refinery is converted to
+/// UTC and then to session time zone since TIMESTAMP column doesn't accept
+/// RFC3339 text
+#[must_use]
+pub(crate) fn add_migration_with_applied_time_query(
+    table_name: &str,
+    applied_time: &str,
+) -> (String, String) {
+    match rfc3339_to_utc(applied_time) {
+        Some(utc_time) => {
+            (
+                format!(
+                    "INSERT INTO {table_name}(app, name, applied_time) VALUES (?, ?, \
+                     CONVERT_TZ(?, '+00:00', @@session.time_zone))"
+                ),
+                utc_time,
+            )
+        }
+        None => {
+            (
+                format!("INSERT INTO {table_name}(app, name, applied_time) VALUES (?, ?, ?)"),
+                applied_time.to_string(),
+            )
+        }
+    }
+}
+
+/// import source rows query
+pub(crate) fn import_source_rows_query(source: ImportSource) -> String {
+    source.fetch_rows_query("CHAR")
+}
+
 /// delete migration query
 #[must_use]
 pub(crate) fn delete_migration_query(table_name: &str) -> String {
@@ -49,6 +88,11 @@ pub(crate) fn current_database_query() -> &'static str {
     "SELECT DATABASE()"
 }
 
+/// switch database query
+pub(crate) fn switch_schema_query(schema: &str) -> String {
+    format!("USE `{}`", schema.replace('`', "``"))
+}
+
 /// get lock database query
 /// # Errors
 /// Failed to lock database
@@ -69,16 +113,71 @@ pub(crate) fn get_lock_id(database_name: &str, table_name: &str) -> String {
     crc32fast::hash(buf.as_bytes()).to_string()
 }
 
+/// Table version queries
+pub(crate) fn table_version_queries(table_name: &str) -> TableVersionQueries {
+    TableVersionQueries::new(
+        table_name,
+        "SELECT 1 FROM information_schema.tables WHERE table_schema = DATABASE() AND table_name = \
+         ?"
+        .to_string(),
+        table_name.to_string(),
+        create_migrator_table_query(table_name),
+        vec![],
+    )
+}
+
+/// Schema snapshot queries
+pub(crate) fn snapshot_queries() -> SnapshotQueries {
+    SnapshotQueries {
+        columns: "SELECT CAST(c.TABLE_NAME AS CHAR) AS table_name, CAST(c.COLUMN_NAME AS CHAR) AS \
+                  column_name, CAST(c.COLUMN_TYPE AS CHAR) AS data_type, CAST(c.IS_NULLABLE AS \
+                  CHAR) AS is_nullable, CAST(c.COLUMN_DEFAULT AS CHAR) AS column_default FROM \
+                  information_schema.COLUMNS c JOIN information_schema.TABLES t ON t.TABLE_SCHEMA \
+                  = c.TABLE_SCHEMA AND t.TABLE_NAME = c.TABLE_NAME WHERE c.TABLE_SCHEMA = \
+                  DATABASE() AND t.TABLE_TYPE = 'BASE TABLE' ORDER BY c.TABLE_NAME, \
+                  c.ORDINAL_POSITION",
+        indexes: "SELECT CAST(TABLE_NAME AS CHAR) AS table_name, CAST(INDEX_NAME AS CHAR) AS \
+                  index_name, CASE WHEN NON_UNIQUE = 0 THEN 'YES' ELSE 'NO' END AS is_unique, \
+                  CAST(COALESCE(COLUMN_NAME, 'expression') AS CHAR) AS column_name FROM \
+                  information_schema.STATISTICS WHERE TABLE_SCHEMA = DATABASE() AND INDEX_NAME <> \
+                  'PRIMARY' ORDER BY TABLE_NAME, INDEX_NAME, SEQ_IN_INDEX",
+        constraints: "SELECT CAST(tc.TABLE_NAME AS CHAR) AS table_name, CAST(tc.CONSTRAINT_NAME \
+                      AS CHAR) AS constraint_name, CAST(tc.CONSTRAINT_TYPE AS CHAR) AS \
+                      constraint_type, CAST(CONCAT(tc.CONSTRAINT_TYPE, ' (', \
+                      GROUP_CONCAT(k.COLUMN_NAME ORDER BY k.ORDINAL_POSITION SEPARATOR ', '), \
+                      ')', IF(tc.CONSTRAINT_TYPE = 'FOREIGN KEY', CONCAT(' REFERENCES ', \
+                      MAX(k.REFERENCED_TABLE_NAME), ' (', GROUP_CONCAT(k.REFERENCED_COLUMN_NAME \
+                      ORDER BY k.ORDINAL_POSITION SEPARATOR ', '), ')'), '')) AS CHAR) AS \
+                      definition FROM information_schema.TABLE_CONSTRAINTS tc JOIN \
+                      information_schema.KEY_COLUMN_USAGE k ON k.CONSTRAINT_SCHEMA = \
+                      tc.CONSTRAINT_SCHEMA AND k.TABLE_NAME = tc.TABLE_NAME AND k.CONSTRAINT_NAME \
+                      = tc.CONSTRAINT_NAME WHERE tc.TABLE_SCHEMA = DATABASE() AND \
+                      tc.CONSTRAINT_TYPE IN ('PRIMARY KEY', 'FOREIGN KEY') GROUP BY \
+                      tc.TABLE_NAME, tc.CONSTRAINT_NAME, tc.CONSTRAINT_TYPE UNION ALL SELECT \
+                      CAST(tc.TABLE_NAME AS CHAR), CAST(tc.CONSTRAINT_NAME AS CHAR), 'CHECK', \
+                      CAST(CONCAT('CHECK ', cc.CHECK_CLAUSE) AS CHAR) FROM \
+                      information_schema.TABLE_CONSTRAINTS tc JOIN \
+                      information_schema.CHECK_CONSTRAINTS cc ON cc.CONSTRAINT_SCHEMA = \
+                      tc.CONSTRAINT_SCHEMA AND cc.CONSTRAINT_NAME = tc.CONSTRAINT_NAME WHERE \
+                      tc.TABLE_SCHEMA = DATABASE() AND tc.CONSTRAINT_TYPE = 'CHECK'",
+    }
+}
+
 #[async_trait::async_trait]
 impl DatabaseOperation<MySql> for Migrator<MySql> {
     async fn ensure_migration_table_exists(
         &self,
         connection: &mut <MySql as Database>::Connection,
     ) -> Result<(), Error> {
-        sqlx::query(&create_migrator_table_query(self.table_name()))
-            .execute(connection)
-            .await?;
-        Ok(())
+        ensure_latest_migrator_table(self, connection, &table_version_queries(self.table_name()))
+            .await
+    }
+
+    async fn migration_table_exists(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+    ) -> Result<bool, Error> {
+        migrator_table_exists::<MySql>(connection, &table_version_queries(self.table_name())).await
     }
 
     async fn drop_migration_table_if_exists(
@@ -86,6 +185,12 @@ impl DatabaseOperation<MySql> for Migrator<MySql> {
         connection: &mut <MySql as Database>::Connection,
     ) -> Result<(), Error> {
         sqlx::query(&drop_table_query(self.table_name()))
+            .execute(&mut *connection)
+            .await?;
+        sqlx::query(&drop_table_query(&version_table_name(self.table_name())))
+            .execute(&mut *connection)
+            .await?;
+        sqlx::query(&drop_table_query(&checkpoint_table_name(self.table_name())))
             .execute(connection)
             .await?;
         Ok(())
@@ -104,6 +209,23 @@ impl DatabaseOperation<MySql> for Migrator<MySql> {
         Ok(())
     }
 
+    async fn add_migration_to_db_table_with_applied_time(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        migration: &Box<dyn Migration<MySql>>,
+        applied_time: &str,
+    ) -> Result<(), Error> {
+        let (sql_query, applied_time) =
+            add_migration_with_applied_time_query(self.table_name(), applied_time);
+        sqlx::query(&sql_query)
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(applied_time)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
     async fn delete_migration_from_db_table(
         &self,
         connection: &mut <MySql as Database>::Connection,
@@ -128,6 +250,30 @@ impl DatabaseOperation<MySql> for Migrator<MySql> {
         )
     }
 
+    async fn fetch_import_source_rows(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        source: ImportSource,
+    ) -> Result<Vec<ImportSourceRow>, Error> {
+        Ok(
+            sqlx::query_as::<_, ImportSourceRow>(&import_source_rows_query(source))
+                .fetch_all(connection)
+                .await?,
+        )
+    }
+
+    async fn fetch_schema_snapshot(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+    ) -> Result<Snapshot, Error> {
+        fetch_snapshot::<MySql>(
+            connection,
+            &snapshot_queries(),
+            &migrator_table_names(self.table_name()),
+        )
+        .await
+    }
+
     async fn lock(&self, connection: &mut <MySql as Database>::Connection) -> Result<(), Error> {
         let (database_name,): (String,) = sqlx::query_as(current_database_query())
             .fetch_one(&mut *connection)
@@ -151,4 +297,52 @@ impl DatabaseOperation<MySql> for Migrator<MySql> {
             .await?;
         Ok(())
     }
+
+    async fn switch_schema(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        schema: &str,
+    ) -> Result<(), Error> {
+        // use query without arguments so it runs with simple text protocol
+        connection
+            .execute(switch_schema_query(schema).as_str())
+            .await?;
+        Ok(())
+    }
+
+    async fn fetch_schemas(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        query: &str,
+    ) -> Result<Vec<String>, Error> {
+        let rows = sqlx::query_as::<_, (String,)>(query)
+            .fetch_all(connection)
+            .await?;
+        Ok(rows.into_iter().map(|(schema,)| schema).collect())
+    }
+
+    async fn fetch_current_schema(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+    ) -> Result<Option<String>, Error> {
+        let (database_name,): (Option<String>,) = sqlx::query_as(current_database_query())
+            .fetch_one(connection)
+            .await?;
+        Ok(database_name)
+    }
+
+    async fn restore_schema(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        schema: Option<&str>,
+    ) -> Result<(), Error> {
+        // mysql cannot unselect database so connection without database is left
+        // as it is
+        if let Some(database_name) = schema {
+            connection
+                .execute(switch_schema_query(database_name).as_str())
+                .await?;
+        }
+        Ok(())
+    }
 }
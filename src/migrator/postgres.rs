@@ -1,8 +1,14 @@
-use sqlx::{Database, Postgres};
+use sqlx::{Database, Executor, Postgres};
 
-use super::{DatabaseOperation, Migrator};
+use super::table_version::{
+    ensure_latest_migrator_table, migrator_table_exists, version_table_name, TableVersionQueries,
+};
+use super::{migrator_table_names, DatabaseOperation, Migrator};
+use crate::backfill::checkpoint_table_name;
 use crate::error::Error;
+use crate::import::{ImportSource, ImportSourceRow};
 use crate::migration::{AppliedMigrationSqlRow, Migration};
+use crate::snapshot::{fetch_snapshot, Snapshot, SnapshotQueries};
 
 /// Create migrator table query
 #[must_use]
@@ -35,6 +41,20 @@ pub(crate) fn add_migration_query(table_name: &str) -> String {
     format!("INSERT INTO {table_name}(app, name) VALUES ($1, $2)")
 }
 
+/// add migration with applied time query
+#[must_use]
+pub(crate) fn add_migration_with_applied_time_query(table_name: &str) -> String {
+    format!(
+        "INSERT INTO {table_name}(app, name, applied_time) VALUES ($1, $2, CAST($3 AS \
+         TIMESTAMPTZ))"
+    )
+}
+
+/// import source rows query
+pub(crate) fn import_source_rows_query(source: ImportSource) -> String {
+    source.fetch_rows_query("TEXT")
+}
+
 /// Delete migration query
 #[must_use]
 pub(crate) fn delete_migration_query(table_name: &str) -> String {
@@ -46,6 +66,33 @@ pub(crate) fn current_database_query() -> &'static str {
     "SELECT CURRENT_DATABASE()"
 }
 
+/// get tenant schema set by switch schema query. Returns NULL when connection
+/// was not switched to tenant schema
+pub(crate) fn tenant_schema_query() -> &'static str {
+    "SELECT NULLIF(CURRENT_SETTING('sqlx_migrator.tenant_schema', true), '')"
+}
+
+/// switch schema query. Along with search path, schema is stored as tenant
+/// schema of session so lock id can differentiate tenants
+pub(crate) fn switch_schema_query(schema: &str) -> String {
+    format!(
+        "SET search_path TO \"{}\"; SET sqlx_migrator.tenant_schema TO '{}'",
+        schema.replace('"', "\"\""),
+        schema.replace('\'', "''")
+    )
+}
+
+/// get current search path query
+pub(crate) fn current_schema_query() -> &'static str {
+    "SELECT CURRENT_SETTING('search_path')"
+}
+
+/// restore search path query. Tenant schema of session is also cleared
+pub(crate) fn restore_schema_query() -> &'static str {
+    "SELECT set_config('search_path', $1, false), set_config('sqlx_migrator.tenant_schema', '', \
+     false)"
+}
+
 /// get lock database query
 pub(crate) fn lock_database_query() -> &'static str {
     "SELECT pg_advisory_lock($1)"
@@ -56,22 +103,79 @@ pub(crate) fn unlock_database_query() -> &'static str {
     "SELECT pg_advisory_unlock($1)"
 }
 
-/// generate lock id
-pub(crate) fn get_lock_id(database_name: &str, table_name: &str) -> i64 {
-    let buf = format!("{database_name}/{table_name}");
+/// generate lock id. Tenant schema is only included when connection was
+/// switched to tenant schema other than `public`, so lock id of connection
+/// using default search path remains same as older version
+pub(crate) fn get_lock_id(
+    database_name: &str,
+    tenant_schema: Option<&str>,
+    table_name: &str,
+) -> i64 {
+    let buf = match tenant_schema {
+        Some(schema) if schema != "public" => format!("{database_name}/{schema}.{table_name}"),
+        _ => format!("{database_name}/{table_name}"),
+    };
     i64::from(crc32fast::hash(buf.as_bytes()))
 }
 
+/// Table version queries
+pub(crate) fn table_version_queries(table_name: &str) -> TableVersionQueries {
+    TableVersionQueries::new(
+        table_name,
+        "SELECT 1 FROM information_schema.tables WHERE table_schema = CURRENT_SCHEMA() AND \
+         table_name = $1"
+            .to_string(),
+        table_name.to_lowercase(),
+        create_migrator_table_query(table_name),
+        vec![],
+    )
+}
+
+/// Schema snapshot queries
+pub(crate) fn snapshot_queries() -> SnapshotQueries {
+    SnapshotQueries {
+        columns: "SELECT c.relname::TEXT AS table_name, a.attname::TEXT AS column_name, \
+                  format_type(a.atttypid, a.atttypmod) AS data_type, CASE WHEN a.attnotnull THEN \
+                  'NO' ELSE 'YES' END AS is_nullable, pg_get_expr(d.adbin, d.adrelid) AS \
+                  column_default FROM pg_catalog.pg_attribute a JOIN pg_catalog.pg_class c ON \
+                  c.oid = a.attrelid JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+                  LEFT JOIN pg_catalog.pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = \
+                  a.attnum WHERE n.nspname = CURRENT_SCHEMA() AND c.relkind IN ('r', 'p') AND \
+                  a.attnum > 0 AND NOT a.attisdropped ORDER BY c.relname, a.attnum",
+        indexes: "SELECT t.relname::TEXT AS table_name, i.relname::TEXT AS index_name, CASE WHEN \
+                  ix.indisunique THEN 'YES' ELSE 'NO' END AS is_unique, \
+                  pg_get_indexdef(ix.indexrelid, k.position, true) AS column_name FROM \
+                  pg_catalog.pg_index ix JOIN pg_catalog.pg_class i ON i.oid = ix.indexrelid JOIN \
+                  pg_catalog.pg_class t ON t.oid = ix.indrelid JOIN pg_catalog.pg_namespace n ON \
+                  n.oid = t.relnamespace CROSS JOIN LATERAL generate_series(1, ix.indnkeyatts) AS \
+                  k(position) WHERE n.nspname = CURRENT_SCHEMA() AND NOT ix.indisprimary ORDER BY \
+                  t.relname, i.relname, k.position",
+        constraints: "SELECT t.relname::TEXT AS table_name, con.conname::TEXT AS constraint_name, \
+                      CASE con.contype WHEN 'p' THEN 'PRIMARY KEY' WHEN 'f' THEN 'FOREIGN KEY' \
+                      ELSE 'CHECK' END AS constraint_type, pg_get_constraintdef(con.oid, true) AS \
+                      definition FROM pg_catalog.pg_constraint con JOIN pg_catalog.pg_class t ON \
+                      t.oid = con.conrelid JOIN pg_catalog.pg_namespace n ON n.oid = \
+                      t.relnamespace WHERE n.nspname = CURRENT_SCHEMA() AND con.contype IN ('p', \
+                      'f', 'c')",
+    }
+}
+
 #[async_trait::async_trait]
 impl DatabaseOperation<Postgres> for Migrator<Postgres> {
     async fn ensure_migration_table_exists(
         &self,
         connection: &mut <Postgres as Database>::Connection,
     ) -> Result<(), Error> {
-        sqlx::query(&create_migrator_table_query(self.table_name()))
-            .execute(connection)
-            .await?;
-        Ok(())
+        ensure_latest_migrator_table(self, connection, &table_version_queries(self.table_name()))
+            .await
+    }
+
+    async fn migration_table_exists(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+    ) -> Result<bool, Error> {
+        migrator_table_exists::<Postgres>(connection, &table_version_queries(self.table_name()))
+            .await
     }
 
     async fn drop_migration_table_if_exists(
@@ -79,6 +183,12 @@ impl DatabaseOperation<Postgres> for Migrator<Postgres> {
         connection: &mut <Postgres as Database>::Connection,
     ) -> Result<(), Error> {
         sqlx::query(&drop_table_query(self.table_name()))
+            .execute(&mut *connection)
+            .await?;
+        sqlx::query(&drop_table_query(&version_table_name(self.table_name())))
+            .execute(&mut *connection)
+            .await?;
+        sqlx::query(&drop_table_query(&checkpoint_table_name(self.table_name())))
             .execute(connection)
             .await?;
         Ok(())
@@ -97,6 +207,21 @@ impl DatabaseOperation<Postgres> for Migrator<Postgres> {
         Ok(())
     }
 
+    async fn add_migration_to_db_table_with_applied_time(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        migration: &Box<dyn Migration<Postgres>>,
+        applied_time: &str,
+    ) -> Result<(), Error> {
+        sqlx::query(&add_migration_with_applied_time_query(self.table_name()))
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(applied_time)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
     async fn delete_migration_from_db_table(
         &self,
         connection: &mut <Postgres as Database>::Connection,
@@ -121,11 +246,38 @@ impl DatabaseOperation<Postgres> for Migrator<Postgres> {
         )
     }
 
+    async fn fetch_import_source_rows(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        source: ImportSource,
+    ) -> Result<Vec<ImportSourceRow>, Error> {
+        Ok(
+            sqlx::query_as::<_, ImportSourceRow>(&import_source_rows_query(source))
+                .fetch_all(connection)
+                .await?,
+        )
+    }
+
+    async fn fetch_schema_snapshot(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+    ) -> Result<Snapshot, Error> {
+        fetch_snapshot::<Postgres>(
+            connection,
+            &snapshot_queries(),
+            &migrator_table_names(&self.table_name().to_lowercase()),
+        )
+        .await
+    }
+
     async fn lock(&self, connection: &mut <Postgres as Database>::Connection) -> Result<(), Error> {
         let (database_name,): (String,) = sqlx::query_as(current_database_query())
             .fetch_one(&mut *connection)
             .await?;
-        let lock_id = get_lock_id(&database_name, self.table_name());
+        let (tenant_schema,): (Option<String>,) = sqlx::query_as(tenant_schema_query())
+            .fetch_one(&mut *connection)
+            .await?;
+        let lock_id = get_lock_id(&database_name, tenant_schema.as_deref(), self.table_name());
         sqlx::query(lock_database_query())
             .bind(lock_id)
             .execute(connection)
@@ -140,11 +292,107 @@ impl DatabaseOperation<Postgres> for Migrator<Postgres> {
         let (database_name,): (String,) = sqlx::query_as(current_database_query())
             .fetch_one(&mut *connection)
             .await?;
-        let lock_id = get_lock_id(&database_name, self.table_name());
+        let (tenant_schema,): (Option<String>,) = sqlx::query_as(tenant_schema_query())
+            .fetch_one(&mut *connection)
+            .await?;
+        let lock_id = get_lock_id(&database_name, tenant_schema.as_deref(), self.table_name());
         sqlx::query(unlock_database_query())
             .bind(lock_id)
             .execute(connection)
             .await?;
         Ok(())
     }
+
+    async fn switch_schema(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        schema: &str,
+    ) -> Result<(), Error> {
+        // use query without arguments so it runs with simple text protocol
+        connection
+            .execute(switch_schema_query(schema).as_str())
+            .await?;
+        Ok(())
+    }
+
+    async fn fetch_schemas(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        query: &str,
+    ) -> Result<Vec<String>, Error> {
+        let rows = sqlx::query_as::<_, (String,)>(query)
+            .fetch_all(connection)
+            .await?;
+        Ok(rows.into_iter().map(|(schema,)| schema).collect())
+    }
+
+    async fn fetch_current_schema(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+    ) -> Result<Option<String>, Error> {
+        let (search_path,): (String,) = sqlx::query_as(current_schema_query())
+            .fetch_one(connection)
+            .await?;
+        Ok(Some(search_path))
+    }
+
+    async fn restore_schema(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        schema: Option<&str>,
+    ) -> Result<(), Error> {
+        if let Some(search_path) = schema {
+            sqlx::query(restore_schema_query())
+                .bind(search_path)
+                .execute(connection)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_lock_id, switch_schema_query};
+
+    #[test]
+    fn postgres_lock_id_of_default_search_path() {
+        let default_lock_id = i64::from(crc32fast::hash(b"db/_sqlx_migrator_migrations"));
+        assert_eq!(
+            get_lock_id("db", None, "_sqlx_migrator_migrations"),
+            default_lock_id
+        );
+        assert_eq!(
+            get_lock_id("db", Some("public"), "_sqlx_migrator_migrations"),
+            default_lock_id
+        );
+        assert_ne!(
+            get_lock_id("db", Some("tenant"), "_sqlx_migrator_migrations"),
+            default_lock_id
+        );
+    }
+
+    #[test]
+    fn postgres_lock_id_of_tenant_schema() {
+        assert_eq!(
+            get_lock_id("db", Some("tenant"), "_sqlx_migrator_migrations"),
+            i64::from(crc32fast::hash(b"db/tenant._sqlx_migrator_migrations"))
+        );
+        assert_ne!(
+            get_lock_id("db", Some("tenant"), "_sqlx_migrator_migrations"),
+            get_lock_id("db", Some("other"), "_sqlx_migrator_migrations")
+        );
+    }
+
+    #[test]
+    fn postgres_switch_schema_query() {
+        assert_eq!(
+            switch_schema_query("tenant"),
+            "SET search_path TO \"tenant\"; SET sqlx_migrator.tenant_schema TO 'tenant'"
+        );
+        assert_eq!(
+            switch_schema_query("a\"b'c"),
+            "SET search_path TO \"a\"\"b'c\"; SET sqlx_migrator.tenant_schema TO 'a\"b''c'"
+        );
+    }
 }
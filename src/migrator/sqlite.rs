@@ -1,8 +1,14 @@
 use sqlx::{Database, Sqlite};
 
-use super::{DatabaseOperation, Migrator};
+use super::table_version::{
+    ensure_latest_migrator_table, migrator_table_exists, version_table_name, TableVersionQueries,
+};
+use super::{migrator_table_names, DatabaseOperation, Migrator};
+use crate::backfill::checkpoint_table_name;
 use crate::error::Error;
+use crate::import::{ImportSource, ImportSourceRow};
 use crate::migration::{AppliedMigrationSqlRow, Migration};
+use crate::snapshot::{fetch_snapshot, Snapshot, SnapshotQueries};
 
 /// create migrator table
 #[must_use]
@@ -35,22 +41,77 @@ pub(crate) fn add_migration_query(table_name: &str) -> String {
     format!("INSERT INTO {table_name}(app, name) VALUES ($1, $2)")
 }
 
+/// add migration with applied time query
+#[must_use]
+pub(crate) fn add_migration_with_applied_time_query(table_name: &str) -> String {
+    format!("INSERT INTO {table_name}(app, name, applied_time) VALUES ($1, $2, $3)")
+}
+
+/// import source rows query
+pub(crate) fn import_source_rows_query(source: ImportSource) -> String {
+    source.fetch_rows_query("TEXT")
+}
+
 /// delete migration query
 #[must_use]
 pub(crate) fn delete_migration_query(table_name: &str) -> String {
     format!("DELETE FROM {table_name} WHERE app = $1 AND name = $2")
 }
 
+/// Table version queries
+pub(crate) fn table_version_queries(table_name: &str) -> TableVersionQueries {
+    TableVersionQueries::new(
+        table_name,
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = $1".to_string(),
+        table_name.to_string(),
+        create_migrator_table_query(table_name),
+        vec![],
+    )
+}
+
+/// Schema snapshot queries. Check constraints are not included since sqlite
+/// only stores them as part of create table statement
+pub(crate) fn snapshot_queries() -> SnapshotQueries {
+    SnapshotQueries {
+        columns: "SELECT m.name AS table_name, p.name AS column_name, p.type AS data_type, CASE \
+                  WHEN p.\"notnull\" = 0 THEN 'YES' ELSE 'NO' END AS is_nullable, p.dflt_value AS \
+                  column_default FROM sqlite_master m JOIN pragma_table_info(m.name) p WHERE \
+                  m.type = 'table' AND m.name NOT LIKE 'sqlite_%' ORDER BY m.name, p.cid",
+        indexes: "SELECT m.name AS table_name, l.name AS index_name, CASE WHEN l.\"unique\" = 1 \
+                  THEN 'YES' ELSE 'NO' END AS is_unique, COALESCE(i.name, 'expression') AS \
+                  column_name FROM sqlite_master m JOIN pragma_index_list(m.name) l JOIN \
+                  pragma_index_info(l.name) i WHERE m.type = 'table' AND m.name NOT LIKE \
+                  'sqlite_%' AND l.origin <> 'pk' ORDER BY m.name, l.name, i.seqno",
+        constraints: "SELECT table_name, table_name || '_pkey' AS constraint_name, 'PRIMARY KEY' \
+                      AS constraint_type, 'PRIMARY KEY (' || group_concat(name, ', ') || ')' AS \
+                      definition FROM (SELECT m.name AS table_name, p.name FROM sqlite_master m \
+                      JOIN pragma_table_info(m.name) p WHERE m.type = 'table' AND m.name NOT LIKE \
+                      'sqlite_%' AND p.pk > 0 ORDER BY m.name, p.pk) GROUP BY table_name UNION \
+                      ALL SELECT table_name, table_name || '_fkey_' || id, 'FOREIGN KEY', \
+                      'FOREIGN KEY (' || group_concat(\"from\", ', ') || ') REFERENCES ' || \
+                      \"table\" || ' (' || group_concat(COALESCE(\"to\", ''), ', ') || ')' FROM \
+                      (SELECT m.name AS table_name, f.* FROM sqlite_master m JOIN \
+                      pragma_foreign_key_list(m.name) f WHERE m.type = 'table' AND m.name NOT \
+                      LIKE 'sqlite_%' ORDER BY m.name, f.id, f.seq) GROUP BY table_name, id, \
+                      \"table\"",
+    }
+}
+
 #[async_trait::async_trait]
 impl DatabaseOperation<Sqlite> for Migrator<Sqlite> {
     async fn ensure_migration_table_exists(
         &self,
         connection: &mut <Sqlite as Database>::Connection,
     ) -> Result<(), Error> {
-        sqlx::query(&create_migrator_table_query(self.table_name()))
-            .execute(connection)
-            .await?;
-        Ok(())
+        ensure_latest_migrator_table(self, connection, &table_version_queries(self.table_name()))
+            .await
+    }
+
+    async fn migration_table_exists(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+    ) -> Result<bool, Error> {
+        migrator_table_exists::<Sqlite>(connection, &table_version_queries(self.table_name())).await
     }
 
     async fn drop_migration_table_if_exists(
@@ -58,6 +119,12 @@ impl DatabaseOperation<Sqlite> for Migrator<Sqlite> {
         connection: &mut <Sqlite as Database>::Connection,
     ) -> Result<(), Error> {
         sqlx::query(&drop_table_query(self.table_name()))
+            .execute(&mut *connection)
+            .await?;
+        sqlx::query(&drop_table_query(&version_table_name(self.table_name())))
+            .execute(&mut *connection)
+            .await?;
+        sqlx::query(&drop_table_query(&checkpoint_table_name(self.table_name())))
             .execute(connection)
             .await?;
         Ok(())
@@ -76,6 +143,21 @@ impl DatabaseOperation<Sqlite> for Migrator<Sqlite> {
         Ok(())
     }
 
+    async fn add_migration_to_db_table_with_applied_time(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+        migration: &Box<dyn Migration<Sqlite>>,
+        applied_time: &str,
+    ) -> Result<(), Error> {
+        sqlx::query(&add_migration_with_applied_time_query(self.table_name()))
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(applied_time)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
     async fn delete_migration_from_db_table(
         &self,
         connection: &mut <Sqlite as Database>::Connection,
@@ -100,6 +182,30 @@ impl DatabaseOperation<Sqlite> for Migrator<Sqlite> {
         )
     }
 
+    async fn fetch_import_source_rows(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+        source: ImportSource,
+    ) -> Result<Vec<ImportSourceRow>, Error> {
+        Ok(
+            sqlx::query_as::<_, ImportSourceRow>(&import_source_rows_query(source))
+                .fetch_all(connection)
+                .await?,
+        )
+    }
+
+    async fn fetch_schema_snapshot(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+    ) -> Result<Snapshot, Error> {
+        fetch_snapshot::<Sqlite>(
+            connection,
+            &snapshot_queries(),
+            &migrator_table_names(self.table_name()),
+        )
+        .await
+    }
+
     async fn lock(&self, _connection: &mut <Sqlite as Database>::Connection) -> Result<(), Error> {
         Ok(())
     }
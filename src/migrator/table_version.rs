@@ -0,0 +1,337 @@
+use sqlx::{ColumnIndex, Connection, Database, Decode, Encode, Executor, IntoArguments, Type};
+
+use super::DatabaseOperation;
+use crate::error::Error;
+
+/// Version of layout of migrator table created by this version of crate.
+///
+/// Increase this value whenever layout of migrator table changes and add
+/// queries to upgrade previous layout to new layout in
+/// [`TableVersionQueries::upgrades`] of each database
+pub(crate) const MIGRATOR_TABLE_VERSION: i32 = 1;
+
+/// Queries used for tracking and upgrading layout version of migrator table
+pub(crate) struct TableVersionQueries {
+    /// Name of table which stores layout version
+    pub(crate) version_table: String,
+    /// Query to create version table if it doesn't exists
+    pub(crate) create_version_table: String,
+    /// Query to fetch version. Must return single integer column
+    pub(crate) fetch_version: String,
+    /// Query to delete stored version
+    pub(crate) delete_version: String,
+    /// Query which returns a row if migrator table exists. Name of migrator
+    /// table is bound as first parameter
+    pub(crate) migrator_table_exists: String,
+    /// Name of migrator table bound to migrator table exists query
+    pub(crate) migrator_table_name: String,
+    /// Query to create migrator table with latest layout
+    pub(crate) create_migrator_table: String,
+    /// List of upgrade queries where list at index `n` upgrades layout from
+    /// version `n + 1` to version `n + 2`
+    pub(crate) upgrades: Vec<Vec<String>>,
+    /// Latest layout version which is stored after upgrade
+    pub(crate) version: i32,
+}
+
+impl TableVersionQueries {
+    /// Create queries for migrator table. Version table related queries are
+    /// same for all database
+    pub(crate) fn new(
+        table_name: &str,
+        migrator_table_exists: String,
+        migrator_table_name: String,
+        create_migrator_table: String,
+        upgrades: Vec<Vec<String>>,
+    ) -> Self {
+        let version_table = version_table_name(table_name);
+        Self {
+            version_table: version_table.clone(),
+            create_version_table: format!(
+                "CREATE TABLE IF NOT EXISTS {version_table} (version INTEGER NOT NULL)"
+            ),
+            fetch_version: format!("SELECT version FROM {version_table}"),
+            delete_version: format!("DELETE FROM {version_table}"),
+            migrator_table_exists,
+            migrator_table_name,
+            create_migrator_table,
+            upgrades,
+            version: MIGRATOR_TABLE_VERSION,
+        }
+    }
+
+    /// Replace upgrade queries and bump latest version to match them so
+    /// upgrade path can be tested before layout of migrator table changes
+    #[cfg(all(test, feature = "sqlite"))]
+    pub(crate) fn with_upgrades(mut self, upgrades: Vec<Vec<String>>) -> Self {
+        self.version = i32::try_from(upgrades.len()).unwrap_or(i32::MAX - 1) + 1;
+        self.upgrades = upgrades;
+        self
+    }
+
+    /// Query to insert latest version
+    fn insert_version(&self) -> String {
+        format!(
+            "INSERT INTO {}(version) VALUES ({})",
+            self.version_table, self.version
+        )
+    }
+}
+
+/// Name of table which stores layout version of migrator table
+pub(crate) fn version_table_name(table_name: &str) -> String {
+    format!("{table_name}_version")
+}
+
+async fn fetch_version<DB>(
+    connection: &mut <DB as Database>::Connection,
+    queries: &TableVersionQueries,
+) -> Result<Option<i32>, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'r> i32: Decode<'r, DB> + Type<DB>,
+    usize: ColumnIndex<<DB as Database>::Row>,
+{
+    Ok(sqlx::query_scalar::<_, i32>(&queries.fetch_version)
+        .fetch_optional(connection)
+        .await?)
+}
+
+/// Check whether migrator table exists without creating it
+pub(crate) async fn migrator_table_exists<DB>(
+    connection: &mut <DB as Database>::Connection,
+    queries: &TableVersionQueries,
+) -> Result<bool, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+{
+    Ok(sqlx::query(&queries.migrator_table_exists)
+        .bind(queries.migrator_table_name.clone())
+        .fetch_optional(connection)
+        .await?
+        .is_some())
+}
+
+async fn upgrade<DB>(
+    connection: &mut <DB as Database>::Connection,
+    queries: &TableVersionQueries,
+) -> Result<(), Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'r> i32: Decode<'r, DB> + Type<DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    usize: ColumnIndex<<DB as Database>::Row>,
+{
+    // upgrade and version change are done inside transaction so failed upgrade
+    // doesn't leave version table empty. Database which commits DDL
+    // implicitly such as mysql still commits upgrade queries one by one
+    let mut transaction = connection.begin().await?;
+    let from_version = match fetch_version(&mut transaction, queries).await? {
+        Some(version) => version,
+        None => {
+            // migrator table created before version table was introduced uses first
+            // layout version, else no migrator table exists so create with latest
+            // layout
+            if migrator_table_exists(&mut transaction, queries).await? {
+                1
+            } else {
+                sqlx::query(&queries.create_migrator_table)
+                    .execute(&mut *transaction)
+                    .await?;
+                queries.version
+            }
+        }
+    };
+    if from_version > queries.version {
+        return Err(Error::NewerMigratorTableVersion {
+            found: from_version,
+            supported: queries.version,
+        });
+    }
+    let skip_upgrades = usize::try_from(from_version - 1).unwrap_or_default();
+    for (version, upgrade_queries) in
+        (from_version..).zip(queries.upgrades.iter().skip(skip_upgrades))
+    {
+        tracing::debug!(
+            "upgrading migrator table from version {version} to {}",
+            version + 1
+        );
+        for query in upgrade_queries {
+            sqlx::query(query).execute(&mut *transaction).await?;
+        }
+    }
+    sqlx::query(&queries.delete_version)
+        .execute(&mut *transaction)
+        .await?;
+    sqlx::query(&queries.insert_version())
+        .execute(&mut *transaction)
+        .await?;
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Ensure migrator table exists and its layout is of latest version.
+///
+/// If layout is older than latest version than layout is upgraded in place
+/// while holding migrator lock. Raises error if layout was created by newer
+/// version of crate
+pub(crate) async fn ensure_latest_migrator_table<DB, M>(
+    migrator: &M,
+    connection: &mut <DB as Database>::Connection,
+    queries: &TableVersionQueries,
+) -> Result<(), Error>
+where
+    DB: Database,
+    M: DatabaseOperation<DB> + ?Sized,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'r> i32: Decode<'r, DB> + Type<DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    usize: ColumnIndex<<DB as Database>::Row>,
+{
+    sqlx::query(&queries.create_version_table)
+        .execute(&mut *connection)
+        .await?;
+    match fetch_version(connection, queries).await? {
+        Some(version) if version == queries.version => return Ok(()),
+        Some(version) if version > queries.version => {
+            return Err(Error::NewerMigratorTableVersion {
+                found: version,
+                supported: queries.version,
+            });
+        }
+        _ => {}
+    }
+    // lock is re-entrant for all supported database so it can be used even when
+    // lock is already held by running plan
+    migrator.lock(connection).await?;
+    let upgrade_result = upgrade(connection, queries).await;
+    migrator.unlock(connection).await?;
+    upgrade_result
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use sqlx::Sqlite;
+
+    use super::{ensure_latest_migrator_table, MIGRATOR_TABLE_VERSION};
+    use crate::error::Error;
+    use crate::migrator::sqlite::{create_migrator_table_query, table_version_queries};
+    use crate::migrator::{DatabaseOperation, Info, Migrate, Migrator, Plan};
+    use crate::test_utils::{migration, sqlite_pool};
+    use crate::vec_box;
+
+    #[tokio::test]
+    async fn migrator_table_version() {
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box!(A));
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        // table created by crate version which doesn't track layout version
+        sqlx::query(&create_migrator_table_query(migrator.table_name()))
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO _sqlx_migrator_migrations(app, name) VALUES ('test', 'a')")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        migrator
+            .ensure_migration_table_exists(&mut conn)
+            .await
+            .unwrap();
+        let version: i32 =
+            sqlx::query_scalar("SELECT version FROM _sqlx_migrator_migrations_version")
+                .fetch_one(&mut *conn)
+                .await
+                .unwrap();
+        assert_eq!(version, MIGRATOR_TABLE_VERSION);
+        assert_eq!(
+            migrator
+                .fetch_applied_migration_from_db(&mut conn)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        sqlx::query("UPDATE _sqlx_migrator_migrations_version SET version = version + 1")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        let result = migrator.run(&mut conn, &Plan::apply_all()).await;
+        assert!(matches!(
+            result,
+            Err(Error::NewerMigratorTableVersion { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn migrator_table_upgrade() {
+        let migrator = Migrator::<Sqlite>::default();
+        let table_name = migrator.table_name().to_string();
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        let fetch_version = format!("SELECT version FROM {table_name}_version");
+        // migrator table created before layout version was tracked
+        sqlx::query(&create_migrator_table_query(&table_name))
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+
+        let failing_queries = table_version_queries(&table_name).with_upgrades(vec![vec![
+            format!("ALTER TABLE {table_name} ADD COLUMN checksum TEXT"),
+            "INVALID UPGRADE QUERY".to_string(),
+        ]]);
+        assert!(
+            ensure_latest_migrator_table(&migrator, &mut conn, &failing_queries)
+                .await
+                .is_err()
+        );
+        let version = sqlx::query_scalar::<_, i32>(&fetch_version)
+            .fetch_optional(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(version, None);
+        assert!(sqlx::query(&format!("SELECT checksum FROM {table_name}"))
+            .fetch_all(&mut *conn)
+            .await
+            .is_err());
+
+        let queries = table_version_queries(&table_name).with_upgrades(vec![vec![format!(
+            "ALTER TABLE {table_name} ADD COLUMN checksum TEXT"
+        )]]);
+        ensure_latest_migrator_table(&migrator, &mut conn, &queries)
+            .await
+            .unwrap();
+        let version = sqlx::query_scalar::<_, i32>(&fetch_version)
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(version, 2);
+        sqlx::query(&format!("SELECT checksum FROM {table_name}"))
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap();
+        // upgrade is not run again once latest version is stored
+        ensure_latest_migrator_table(&migrator, &mut conn, &queries)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            migrator.ensure_migration_table_exists(&mut conn).await,
+            Err(Error::NewerMigratorTableVersion {
+                found: 2,
+                supported: 1
+            })
+        ));
+    }
+}
@@ -1,9 +1,10 @@
-use sqlx::{Database, Sqlite, SqlitePool};
+use sqlx::{Database, Sqlite};
 
-use super::{DatabaseOperation, Info, Migrate};
+use super::{DatabaseOperation, Info, Migrate, Migrator, PlanReason};
 use crate::error::Error;
 use crate::migration::{AppliedMigrationSqlRow, Migration};
 use crate::migrator::Plan;
+use crate::test_utils::{migration, sqlite_pool};
 use crate::vec_box;
 
 #[derive(Default)]
@@ -49,6 +50,13 @@ impl DatabaseOperation<Sqlite> for CustomMigrator {
         Ok(())
     }
 
+    async fn migration_table_exists(
+        &self,
+        _connection: &mut <Sqlite as Database>::Connection,
+    ) -> Result<bool, Error> {
+        Ok(true)
+    }
+
     async fn drop_migration_table_if_exists(
         &self,
         _connection: &mut <Sqlite as Database>::Connection,
@@ -93,43 +101,12 @@ impl DatabaseOperation<Sqlite> for CustomMigrator {
 
 impl Migrate<Sqlite> for CustomMigrator {}
 
-macro_rules! migration {
-    ($op:ty, $name:literal, $parents:expr, $replaces:expr, $run_before:expr) => {
-        #[async_trait::async_trait]
-        impl crate::migration::Migration<sqlx::Sqlite> for $op {
-            fn app(&self) -> &str {
-                "test"
-            }
-
-            fn name(&self) -> &str {
-                $name
-            }
-
-            fn parents(&self) -> Vec<Box<dyn crate::migration::Migration<sqlx::Sqlite>>> {
-                $parents
-            }
-
-            fn operations(&self) -> Vec<Box<dyn crate::operation::Operation<sqlx::Sqlite>>> {
-                vec![]
-            }
-
-            fn replaces(&self) -> Vec<Box<dyn crate::migration::Migration<sqlx::Sqlite>>> {
-                $replaces
-            }
-
-            fn run_before(&self) -> Vec<Box<dyn crate::migration::Migration<sqlx::Sqlite>>> {
-                $run_before
-            }
-        }
-    };
-}
-
 async fn generate_apply_all_plan(
     migrator: &mut CustomMigrator,
     migration_list: Vec<Box<dyn Migration<Sqlite>>>,
 ) -> Result<Vec<&Box<dyn Migration<Sqlite>>>, Error> {
     migrator.add_migrations(migration_list);
-    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let sqlite = sqlite_pool().await;
     let mut conn = sqlite.acquire().await.unwrap();
     migrator
         .generate_migration_plan(&mut conn, Some(&Plan::apply_all()))
@@ -641,7 +618,7 @@ async fn apply_virtual_plan_size() {
     );
     let mut migrator = CustomMigrator::default();
     migrator.add_migrations(vec_box!(A, B, C, D, E, F, G));
-    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let sqlite = sqlite_pool().await;
     let mut conn = sqlite.acquire().await.unwrap();
     let full_plan = migrator
         .generate_migration_plan(&mut conn, Some(&Plan::apply_all()))
@@ -740,7 +717,7 @@ async fn revert_virtual_plan_size() {
     let mut migrator = CustomMigrator::default();
     migrator.add_migrations(vec_box!(A, B, C, D, E, F, G));
     migrator.add_applied_migrations(vec_box!(A, B, C, D, E, F, G));
-    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let sqlite = sqlite_pool().await;
     let mut conn = sqlite.acquire().await.unwrap();
     let revert_plan = migrator
         .generate_migration_plan(&mut conn, Some(&Plan::revert_all()))
@@ -787,3 +764,324 @@ async fn revert_virtual_plan_size() {
     assert!(plan_till_b_iter.next() == Some(&&(Box::new(B) as Box<dyn Migration<Sqlite>>)));
     assert!(plan_till_b_iter.next().is_none());
 }
+
+#[cfg(feature = "macros")]
+#[tokio::test]
+async fn derive_migration() {
+    #[derive(crate::Migration)]
+    #[migration(db = Sqlite, app = "test", name = "a")]
+    struct A;
+    #[derive(crate::Migration)]
+    #[migration(db = Sqlite, app = "test", name = "b", parents(A), atomic = false)]
+    struct B;
+    #[derive(crate::Migration)]
+    #[migration(
+        db = Sqlite,
+        app = "test",
+        name = "c",
+        parents(A),
+        replaces(("test", "b")),
+        run_before(("test", "d"))
+    )]
+    struct C;
+    #[derive(crate::Migration)]
+    #[migration(db = Sqlite, app = "test", name = "d", parents(("test", "b")))]
+    struct D;
+    #[derive(crate::Migration)]
+    #[migration(db = Sqlite, app = "test", name = "d", virtual)]
+    struct VirtualD;
+
+    assert!(!Migration::<Sqlite>::is_atomic(&B));
+    assert!(Migration::<Sqlite>::is_virtual(&VirtualD));
+    let mut migrator = CustomMigrator::default();
+    let plan = generate_apply_all_plan(&mut migrator, vec_box![A, B, C, D, VirtualD])
+        .await
+        .unwrap();
+    let mut plan_iter = plan.iter();
+    assert!(plan_iter.next() == Some(&&(Box::new(A) as Box<dyn Migration<Sqlite>>)));
+    assert!(plan_iter.next() == Some(&&(Box::new(C) as Box<dyn Migration<Sqlite>>)));
+    assert!(plan_iter.next() == Some(&&(Box::new(D) as Box<dyn Migration<Sqlite>>)));
+    assert!(plan_iter.next().is_none());
+}
+
+#[test]
+fn migration_macro_infer_name_and_app() {
+    use crate::migration::{app_from_module_path, name_from_file};
+
+    struct Inferred;
+    crate::sqlite_migration!(Inferred, parents = vec_box![], operations = vec_box![]);
+    struct Keyed;
+    crate::sqlite_migration!(
+        Keyed,
+        app = "main",
+        name = "m0002_keyed",
+        parents = vec_box![Inferred],
+        operations = vec_box![],
+        replaces = vec_box![("main", "m0001_old")],
+        run_before = vec_box![("other", "m0001")],
+        atomic = false,
+    );
+
+    assert_eq!(Migration::<Sqlite>::app(&Inferred), "migrator");
+    assert_eq!(Migration::<Sqlite>::name(&Inferred), "tests");
+    assert_eq!(Migration::<Sqlite>::app(&Keyed), "main");
+    assert_eq!(Migration::<Sqlite>::name(&Keyed), "m0002_keyed");
+    assert_eq!(Migration::<Sqlite>::parents(&Keyed).len(), 1);
+    assert_eq!(Migration::<Sqlite>::replaces(&Keyed).len(), 1);
+    assert_eq!(Migration::<Sqlite>::run_before(&Keyed).len(), 1);
+    assert!(!Migration::<Sqlite>::is_atomic(&Keyed));
+
+    assert_eq!(
+        name_from_file("migrations/m0003_use_macros.rs"),
+        "m0003_use_macros"
+    );
+    assert_eq!(name_from_file("src\\m0001.rs"), "m0001");
+    assert_eq!(
+        name_from_file("migrations/m0004_nested/mod.rs"),
+        "m0004_nested"
+    );
+    assert_eq!(name_from_file("m0005"), "m0005");
+    assert_eq!(app_from_module_path("example::main::m0001"), "main");
+    assert_eq!(app_from_module_path("example::m0001"), "example");
+    assert_eq!(app_from_module_path("example"), "example");
+}
+
+#[tokio::test]
+async fn revert_irreversible_check() {
+    use crate::operation::Operation;
+    use crate::sql_migration::SqlMigration;
+
+    struct IrreversibleOperation;
+    #[async_trait::async_trait]
+    impl Operation<Sqlite> for IrreversibleOperation {
+        async fn up(&self, _connection: &mut sqlx::SqliteConnection) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    struct A;
+    crate::sqlite_migration!(
+        A,
+        app = "test",
+        name = "a",
+        parents = vec_box![],
+        operations = vec_box![("CREATE TABLE a (id INTEGER)", "DROP TABLE a")]
+    );
+    struct B;
+    crate::sqlite_migration!(
+        B,
+        app = "test",
+        name = "b",
+        parents = vec_box![A],
+        operations = vec_box![IrreversibleOperation]
+    );
+    let c = SqlMigration::new("test", "c", "CREATE TABLE c (id INTEGER)").parent("test", "b");
+    let sqlite = sqlite_pool().await;
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box![A, B, c]);
+    migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+
+    let error = migrator
+        .run(&mut conn, &Plan::revert_all())
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, Error::PlanError { message } if message.ends_with("test:c, test:b")),
+        "revert plan should list irreversible migrations"
+    );
+    // nothing is reverted since plan fails before running
+    assert_eq!(
+        migrator
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap()
+            .len(),
+        3
+    );
+    let plan = migrator
+        .generate_migration_plan(
+            &mut conn,
+            Some(&Plan::revert_all().allow_irreversible(true)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(plan.len(), 3);
+    migrator
+        .run(&mut conn, &Plan::revert_count(1).fake(true))
+        .await
+        .unwrap();
+    migrator
+        .run(&mut conn, &Plan::revert_count(1))
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test]
+async fn explain_plan() {
+    type Explained<'a> = Vec<(&'a Box<dyn Migration<Sqlite>>, Vec<PlanReason>)>;
+    struct A;
+    migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+    struct B;
+    migration!(B, "b", vec_box!(A), vec_box!(), vec_box!());
+    struct C;
+    migration!(C, "c", vec_box!(B), vec_box!(), vec_box!());
+    struct D;
+    migration!(D, "d", vec_box!(), vec_box!(), vec_box!(C));
+    struct E;
+    migration!(E, "e", vec_box!(), vec_box!(A, D), vec_box!());
+    let mut migrator = CustomMigrator::default();
+    migrator.add_migrations(vec_box!(A, B, C, D));
+    let sqlite = sqlite_pool().await;
+    let mut conn = sqlite.acquire().await.unwrap();
+
+    let explain = |explained: Explained| {
+        explained
+            .iter()
+            .map(|(migration, reasons)| {
+                let reasons = reasons.iter().map(ToString::to_string).collect::<Vec<_>>();
+                format!("{}: {}", migration.name(), reasons.join(", "))
+            })
+            .collect::<Vec<_>>()
+    };
+    let explained = migrator
+        .explain_migration_plan(&mut conn, &Plan::apply_name("test", &Some("c".to_string())))
+        .await
+        .unwrap();
+    assert_eq!(
+        explain(explained),
+        vec![
+            "a: required as parent of test:b",
+            "b: required as parent of test:c",
+            "d: required to run before test:c",
+            "c: targeted directly",
+        ]
+    );
+
+    migrator.add_applied_migrations(vec_box!(A, B, C, D));
+    let explained = migrator
+        .explain_migration_plan(
+            &mut conn,
+            &Plan::revert_name("test", &Some("a".to_string())),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        explain(explained),
+        vec![
+            "c: child of reverted test:b",
+            "b: child of reverted test:a",
+            "a: targeted directly",
+        ]
+    );
+
+    let mut migrator = CustomMigrator::default();
+    migrator.add_migrations(vec_box!(A, D, E));
+    let explained = migrator
+        .explain_migration_plan(&mut conn, &Plan::apply_all())
+        .await
+        .unwrap();
+    assert_eq!(
+        explain(explained),
+        vec![
+            "e: targeted directly, replaced children test:a, test:d are unapplied",
+            "b: targeted directly",
+            "c: targeted directly",
+        ]
+    );
+    let migrations: Vec<Box<dyn Migration<Sqlite>>> = vec_box!(E);
+    let migration_list = migrations.iter().collect::<Vec<_>>();
+    let reasons = super::explain_plan(
+        &Plan::apply_all(),
+        &migration_list,
+        &[AppliedMigrationSqlRow::new(1, "test", "a")],
+    );
+    assert_eq!(reasons, vec![vec![PlanReason::Targeted]]);
+}
+
+#[tokio::test]
+async fn mark_and_unmark_applied() {
+    struct A;
+    migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+    struct B;
+    migration!(B, "b", vec_box!(A), vec_box!(), vec_box!());
+    struct C;
+    migration!(C, "c", vec_box!(B), vec_box!(), vec_box!());
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A, B, C));
+    let sqlite = sqlite_pool().await;
+    let mut conn = sqlite.acquire().await.unwrap();
+
+    let warnings = migrator.mark_applied(&mut conn, "test", "b").await.unwrap();
+    assert_eq!(
+        warnings,
+        vec!["migration test:b is applied but its parent migration test:a is not applied"]
+    );
+    assert!(matches!(
+        migrator.mark_applied(&mut conn, "test", "b").await,
+        Err(Error::PlanError { .. })
+    ));
+    assert!(matches!(
+        migrator.mark_applied(&mut conn, "test", "d").await,
+        Err(Error::MigrationNotFound { .. })
+    ));
+    assert!(migrator
+        .mark_applied(&mut conn, "test", "a")
+        .await
+        .unwrap()
+        .is_empty());
+    let applied = migrator
+        .fetch_applied_migration_from_db(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(applied.len(), 2);
+
+    migrator.mark_applied(&mut conn, "test", "c").await.unwrap();
+    let warnings = migrator
+        .unmark_applied(&mut conn, "test", "b")
+        .await
+        .unwrap();
+    assert_eq!(
+        warnings,
+        vec!["migration test:c is applied but its parent migration test:b is not applied"]
+    );
+    assert!(matches!(
+        migrator.unmark_applied(&mut conn, "test", "b").await,
+        Err(Error::PlanError { .. })
+    ));
+}
+
+#[tokio::test]
+async fn mark_and_unmark_applied_run_before() {
+    struct A;
+    migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+    struct B;
+    migration!(B, "b", vec_box!(), vec_box!(), vec_box!(A));
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A, B));
+    let sqlite = sqlite_pool().await;
+    let mut conn = sqlite.acquire().await.unwrap();
+
+    let warnings = migrator.mark_applied(&mut conn, "test", "a").await.unwrap();
+    assert_eq!(
+        warnings,
+        vec![
+            "migration test:a is applied but migration test:b which runs before it is not applied"
+        ]
+    );
+    assert!(migrator
+        .mark_applied(&mut conn, "test", "b")
+        .await
+        .unwrap()
+        .is_empty());
+    let warnings = migrator
+        .unmark_applied(&mut conn, "test", "b")
+        .await
+        .unwrap();
+    assert_eq!(
+        warnings,
+        vec![
+            "migration test:a is applied but migration test:b which runs before it is not applied"
+        ]
+    );
+}
@@ -0,0 +1,371 @@
+//! Module for applying same migration plan to multiple databases
+//!
+//! [`MultiDatabaseRunner`] runs provided [`Plan`] against multiple databases
+//! of same type (for example regional shards) and returns
+//! [`MultiDatabaseReport`] which contains matrix of migrations applied on each
+//! database so databases which diverge from each other can be detected.
+#![cfg_attr(
+    feature = "sqlite",
+    doc = r#"
+# Example
+```rust,no_run
+use sqlx::Sqlite;
+use sqlx_migrator::migrator::{Migrator, Plan};
+use sqlx_migrator::multi_database::MultiDatabaseRunner;
+
+# async fn run() -> Result<(), sqlx_migrator::Error> {
+let migrator = Migrator::<Sqlite>::default();
+let report = MultiDatabaseRunner::new(&migrator)
+    .add_url("shard_1", "sqlite://shard_1.db?mode=rwc")
+    .add_url("shard_2", "sqlite://shard_2.db?mode=rwc")
+    .concurrency(2)
+    .run(&Plan::apply_all())
+    .await;
+println!("{report}");
+if report.is_diverged() {
+    println!("databases have diverged");
+}
+# Ok(())
+# }
+```
+"#
+)]
+
+use std::fmt::Display;
+
+use futures_util::StreamExt;
+use sqlx::{Database, Pool};
+
+use crate::error::Error;
+use crate::migration::AppliedMigrationSqlRow;
+use crate::migrator::{Migrate, Plan};
+
+enum Target<DB>
+where
+    DB: Database,
+{
+    Url(String),
+    Pool(Pool<DB>),
+}
+
+/// Report of running plan for a single database
+#[derive(Debug)]
+pub struct DatabaseReport {
+    name: String,
+    error: Option<Error>,
+    applied_migrations: Option<Vec<AppliedMigrationSqlRow>>,
+}
+
+impl DatabaseReport {
+    /// Return name of database
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return error raised while running plan for database
+    #[must_use]
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+
+    /// Return migrations applied on database after plan was run. Returns
+    /// `None` if applied migrations could not be fetched from database
+    #[must_use]
+    pub fn applied_migrations(&self) -> Option<&[AppliedMigrationSqlRow]> {
+        self.applied_migrations.as_deref()
+    }
+
+    /// Return whether migration with provided app and name is applied on
+    /// database. Returns `None` if applied migrations could not be fetched
+    #[must_use]
+    pub fn is_applied(&self, app: &str, name: &str) -> Option<bool> {
+        self.applied_migrations.as_ref().map(|applied_migrations| {
+            applied_migrations
+                .iter()
+                .any(|applied| applied.app() == app && applied.name() == name)
+        })
+    }
+}
+
+/// Consolidated report of running plan for multiple databases
+#[derive(Debug)]
+pub struct MultiDatabaseReport {
+    migrations: Vec<(String, String)>,
+    databases: Vec<DatabaseReport>,
+}
+
+impl MultiDatabaseReport {
+    /// Return list of app and name of all migrations in order of apply
+    #[must_use]
+    pub fn migrations(&self) -> &[(String, String)] {
+        &self.migrations
+    }
+
+    /// Return report of each database in same order as databases were added
+    #[must_use]
+    pub fn databases(&self) -> &[DatabaseReport] {
+        &self.databases
+    }
+
+    /// Return true if running plan failed for any database
+    #[must_use]
+    pub fn has_failure(&self) -> bool {
+        self.databases
+            .iter()
+            .any(|database| database.error.is_some())
+    }
+
+    /// Return list of migrations which are applied on some databases but not
+    /// on others. Databases whose applied migrations could not be fetched are
+    /// ignored
+    #[must_use]
+    pub fn diverged_migrations(&self) -> Vec<&(String, String)> {
+        self.migrations
+            .iter()
+            .filter(|(app, name)| {
+                let statuses = self
+                    .databases
+                    .iter()
+                    .filter_map(|database| database.is_applied(app, name))
+                    .collect::<Vec<_>>();
+                statuses.contains(&true) && statuses.contains(&false)
+            })
+            .collect()
+    }
+
+    /// Return true if any migration is applied on some databases but not on
+    /// others
+    #[must_use]
+    pub fn is_diverged(&self) -> bool {
+        !self.diverged_migrations().is_empty()
+    }
+}
+
+impl Display for MultiDatabaseReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let app_width = self
+            .migrations
+            .iter()
+            .map(|(app, _)| app.len())
+            .chain([3])
+            .max()
+            .unwrap_or_default();
+        let name_width = self
+            .migrations
+            .iter()
+            .map(|(_, name)| name.len())
+            .chain([4])
+            .max()
+            .unwrap_or_default();
+        write!(f, "{:^app_width$} | {:^name_width$}", "App", "Name")?;
+        for database in &self.databases {
+            write!(f, " | {}", database.name)?;
+        }
+        writeln!(f)?;
+        for (app, name) in &self.migrations {
+            write!(f, "{app:^app_width$} | {name:^name_width$}")?;
+            for database in &self.databases {
+                let width = database.name.len();
+                let status = match database.is_applied(app, name) {
+                    Some(true) => "\u{2713}",
+                    Some(false) => "\u{2717}",
+                    None => "?",
+                };
+                write!(f, " | {status:^width$}")?;
+            }
+            writeln!(f)?;
+        }
+        for database in &self.databases {
+            if let Some(error) = &database.error {
+                writeln!(f, "{}: {error}", database.name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runner which runs same plan for multiple databases of same type
+pub struct MultiDatabaseRunner<'migrator, DB>
+where
+    DB: Database,
+{
+    migrator: &'migrator dyn Migrate<DB>,
+    targets: Vec<(String, Target<DB>)>,
+    concurrency: usize,
+}
+
+impl<'migrator, DB> MultiDatabaseRunner<'migrator, DB>
+where
+    DB: Database,
+{
+    /// Create new multi database runner for migrator. By default databases
+    /// are run one at a time
+    #[must_use]
+    pub fn new(migrator: &'migrator dyn Migrate<DB>) -> Self {
+        Self {
+            migrator,
+            targets: vec![],
+            concurrency: 1,
+        }
+    }
+
+    /// Add database using connection url. Connection is only created when
+    /// plan is run
+    #[must_use]
+    pub fn add_url(self, name: impl Into<String>, url: impl Into<String>) -> Self {
+        let mut runner = self;
+        runner.targets.push((name.into(), Target::Url(url.into())));
+        runner
+    }
+
+    /// Add database using already created pool
+    #[must_use]
+    pub fn add_pool(self, name: impl Into<String>, pool: Pool<DB>) -> Self {
+        let mut runner = self;
+        runner.targets.push((name.into(), Target::Pool(pool)));
+        runner
+    }
+
+    /// Set maximum number of databases for which plan is run concurrently.
+    /// Value of 0 is treated as 1
+    #[must_use]
+    pub fn concurrency(self, concurrency: usize) -> Self {
+        let mut runner = self;
+        runner.concurrency = concurrency.max(1);
+        runner
+    }
+
+    /// Run plan for all databases. Failure of one database doesn't stop
+    /// running plan for other databases, check
+    /// [`MultiDatabaseReport::has_failure`] for failure
+    pub async fn run(&self, plan: &Plan) -> MultiDatabaseReport {
+        let results = futures_util::stream::iter(&self.targets)
+            .map(|(name, target)| self.run_database(name, target, plan))
+            .buffered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+        let mut migrations = None;
+        let mut databases = Vec::with_capacity(results.len());
+        for (database, database_migrations) in results {
+            if migrations.is_none() {
+                migrations = database_migrations;
+            }
+            databases.push(database);
+        }
+        // fallback to order in which migrations were added if plan could not be
+        // generated for any database
+        let migrations = migrations.unwrap_or_else(|| {
+            self.migrator
+                .migrations()
+                .iter()
+                .map(|migration| (migration.app().to_string(), migration.name().to_string()))
+                .collect()
+        });
+        MultiDatabaseReport {
+            migrations,
+            databases,
+        }
+    }
+
+    async fn run_database(
+        &self,
+        name: &str,
+        target: &Target<DB>,
+        plan: &Plan,
+    ) -> (DatabaseReport, Option<Vec<(String, String)>>) {
+        tracing::debug!("running plan for database {name}");
+        let mut report = DatabaseReport {
+            name: name.to_string(),
+            error: None,
+            applied_migrations: None,
+        };
+        let pool = match target {
+            Target::Url(url) => {
+                match Pool::<DB>::connect(url).await {
+                    Ok(pool) => pool,
+                    Err(error) => {
+                        report.error = Some(error.into());
+                        return (report, None);
+                    }
+                }
+            }
+            Target::Pool(pool) => pool.clone(),
+        };
+        let mut connection = match pool.acquire().await {
+            Ok(connection) => connection,
+            Err(error) => {
+                report.error = Some(error.into());
+                return (report, None);
+            }
+        };
+        if let Err(error) = self.migrator.run(&mut connection, plan).await {
+            tracing::debug!("running plan for database {name} failed: {error}");
+            report.error = Some(error);
+        }
+        // fetch applied migrations even if plan fails so partially applied plan is
+        // also present in report
+        match self
+            .migrator
+            .fetch_applied_migration_from_db(&mut connection)
+            .await
+        {
+            Ok(applied_migrations) => report.applied_migrations = Some(applied_migrations),
+            Err(error) => {
+                if report.error.is_none() {
+                    report.error = Some(error);
+                }
+            }
+        }
+        let migrations = self
+            .migrator
+            .generate_migration_plan(&mut connection, None)
+            .await
+            .ok()
+            .map(|migrations| {
+                migrations
+                    .iter()
+                    .map(|migration| (migration.app().to_string(), migration.name().to_string()))
+                    .collect()
+            });
+        (report, migrations)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use sqlx::Sqlite;
+
+    use super::MultiDatabaseRunner;
+    use crate::migrator::{Info, Migrate, Migrator, Plan};
+    use crate::test_utils::{migration, sqlite_pool};
+    use crate::vec_box;
+
+    #[tokio::test]
+    async fn multi_database_diverged() {
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        struct B;
+        migration!(B, "b", vec_box!(A), vec_box!(), vec_box!());
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box!(A, B));
+        let first = sqlite_pool().await;
+        let second = sqlite_pool().await;
+        let runner = MultiDatabaseRunner::new(&migrator)
+            .add_pool("first", first)
+            .add_pool("second", second.clone())
+            .concurrency(2);
+        let report = runner.run(&Plan::apply_count(1)).await;
+        assert!(!report.has_failure());
+        assert!(!report.is_diverged());
+        let mut conn = second.acquire().await.unwrap();
+        migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+        drop(conn);
+        let report = runner.run(&Plan::apply_count(0)).await;
+        assert!(!report.has_failure());
+        assert_eq!(
+            report.diverged_migrations(),
+            vec![&("test".to_string(), "b".to_string())]
+        );
+    }
+}
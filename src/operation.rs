@@ -27,7 +27,8 @@ impl Operation<Sqlite> for ExampleOperation {
     }
 
     // By default operation is irreversible and cannot be reversed if you want to support
-    // reverse of migration than add down function as well
+    // reverse of migration than add down function as well and mark operation as
+    // reversible
     async fn down(
         &self,
         connection: &mut sqlx::SqliteConnection,
@@ -35,6 +36,10 @@ impl Operation<Sqlite> for ExampleOperation {
         // Do some operations
         Ok(())
     }
+
+    fn is_reversible(&self) -> bool {
+        true
+    }
 }
 ```
 "
@@ -43,6 +48,7 @@ impl Operation<Sqlite> for ExampleOperation {
 use sqlx::Database;
 
 use crate::error::Error;
+use crate::script::execute_script;
 
 /// Trait for defining a database operation.
 ///
@@ -70,12 +76,33 @@ where
     ///
     /// This method is called when the migration is being rolled back. Implement
     /// this method if you want to make the operation reversible. If not
-    /// implemented, the operation is considered irreversible.
+    /// implemented, the operation is considered irreversible. When implementing
+    /// this method also override [`Operation::is_reversible`] to return `true`
     async fn down(&self, connection: &mut <DB as Database>::Connection) -> Result<(), Error> {
         let _connection = connection;
         return Err(Error::IrreversibleOperation);
     }
 
+    /// Indicates whether the operation can be reverted using `down`.
+    ///
+    /// Revert plan is checked before reverting any migration, and generating
+    /// revert plan fails if any operation of plan is not reversible. By
+    /// default, operations are considered irreversible. Override this method
+    /// to return `true` along with `down` method.
+    fn is_reversible(&self) -> bool {
+        false
+    }
+
+    /// Returns up SQL and optional down SQL when operation only runs plain
+    /// SQL.
+    ///
+    /// SQL is used by [`squash`](crate::squash) to combine operations of
+    /// multiple migrations into single migration. By default, operations are
+    /// considered to run rust code and cannot be squashed.
+    fn sql(&self) -> Option<(String, Option<String>)> {
+        None
+    }
+
     /// Indicates whether the `up` operation is destructible.
     ///
     /// If the operation is destructible, the user will be prompted for
@@ -88,28 +115,33 @@ where
     }
 }
 
+/// Operation for `(up, down)` SQL tuple. SQL can contain multiple statements
+/// which are run using [`execute_script`]
 #[async_trait::async_trait]
 impl<DB, U, D> Operation<DB> for (U, D)
 where
     DB: Database,
     U: AsRef<str> + Send + Sync,
     D: AsRef<str> + Send + Sync,
+    <DB as Database>::Connection: 'static,
     for<'c> &'c mut <DB as Database>::Connection: sqlx::Executor<'c, Database = DB>,
-    for<'q> <DB as Database>::Arguments<'q>: sqlx::IntoArguments<'q, DB>,
 {
     async fn up(&self, connection: &mut <DB as Database>::Connection) -> Result<(), Error> {
-        sqlx::query(self.0.as_ref())
-            .execute(connection)
-            .await
-            .map_err(Error::from)?;
-        Ok(())
+        execute_script::<DB>(connection, self.0.as_ref()).await
     }
 
     async fn down(&self, connection: &mut <DB as Database>::Connection) -> Result<(), Error> {
-        sqlx::query(self.1.as_ref())
-            .execute(connection)
-            .await
-            .map_err(Error::from)?;
-        Ok(())
+        execute_script::<DB>(connection, self.1.as_ref()).await
+    }
+
+    fn is_reversible(&self) -> bool {
+        true
+    }
+
+    fn sql(&self) -> Option<(String, Option<String>)> {
+        Some((
+            self.0.as_ref().to_string(),
+            Some(self.1.as_ref().to_string()),
+        ))
     }
 }
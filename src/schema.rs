@@ -0,0 +1,1304 @@
+//! Module for defining typed schema operations
+//!
+//! Schema operations such as [`CreateTable`], [`AddColumn`] or [`CreateIndex`]
+//! implement [`Operation`] for all supported database and render SQL for
+//! dialect of database used. For `Any` database dialect is selected using
+//! backend name of connection. Down SQL of every schema operation is derived
+//! automatically from its definition, so operations which drop object such as
+//! [`DropTable`] or [`DropColumn`] expect definition of dropped object.
+//! Operations which can lose data are marked as destructible.
+#![cfg_attr(
+    feature = "postgres",
+    doc = r#"
+# Example
+```rust
+use sqlx::Postgres;
+use sqlx_migrator::operation::Operation;
+use sqlx_migrator::schema::{AddColumn, Column, CreateIndex, CreateTable};
+use sqlx_migrator::vec_box;
+
+let operations: Vec<Box<dyn Operation<Postgres>>> = vec_box![
+    CreateTable::new("users")
+        .column(Column::new("id", "INTEGER").primary_key())
+        .column(Column::new("name", "TEXT").not_null()),
+    AddColumn::new("users", Column::new("age", "INTEGER").default("0")),
+    CreateIndex::new("users_name_index", "users", ["name"]).unique(),
+];
+```
+"#
+)]
+
+use std::fmt::Display;
+
+use crate::error::Error;
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+use crate::operation::Operation;
+
+/// SQL dialect used for rendering schema operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Dialect {
+    /// Postgres dialect
+    Postgres,
+    /// `MySQL` dialect
+    MySql,
+    /// `SQLite` dialect
+    Sqlite,
+}
+
+impl Dialect {
+    /// Return dialect from backend name of database. Returns `None` if backend
+    /// name is not supported
+    #[must_use]
+    pub fn from_backend_name(backend_name: &str) -> Option<Self> {
+        match backend_name {
+            "PostgreSQL" => Some(Self::Postgres),
+            "MySQL" => Some(Self::MySql),
+            "SQLite" => Some(Self::Sqlite),
+            _ => None,
+        }
+    }
+
+    /// Quote identifier for dialect
+    #[must_use]
+    pub fn quote(self, identifier: &str) -> String {
+        match self {
+            Self::Postgres | Self::Sqlite => format!("\"{}\"", identifier.replace('"', "\"\"")),
+            Self::MySql => format!("`{}`", identifier.replace('`', "``")),
+        }
+    }
+
+    /// Return bind parameter placeholder of dialect for one based index
+    #[must_use]
+    pub fn placeholder(self, index: usize) -> String {
+        match self {
+            Self::Postgres => format!("${index}"),
+            Self::MySql | Self::Sqlite => "?".to_string(),
+        }
+    }
+
+    fn quote_list(self, identifiers: &[String]) -> String {
+        identifiers
+            .iter()
+            .map(|identifier| self.quote(identifier))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn unsupported(self, operation: &str) -> Error {
+        Error::UnsupportedSchemaOperation {
+            operation: operation.to_string(),
+            database: self.to_string(),
+        }
+    }
+}
+
+impl Display for Dialect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Postgres => "PostgreSQL",
+            Self::MySql => "MySQL",
+            Self::Sqlite => "SQLite",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Definition of column used by schema operations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    name: String,
+    data_type: String,
+    nullable: bool,
+    default: Option<String>,
+    primary_key: bool,
+    unique: bool,
+}
+
+impl Column {
+    /// Create new nullable column with name and SQL data type
+    #[must_use]
+    pub fn new(name: impl Into<String>, data_type: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            data_type: data_type.into(),
+            nullable: true,
+            default: None,
+            primary_key: false,
+            unique: false,
+        }
+    }
+
+    /// Mark column as not null
+    #[must_use]
+    pub fn not_null(self) -> Self {
+        let mut column = self;
+        column.nullable = false;
+        column
+    }
+
+    /// Set default value of column. Value is SQL expression which is used as
+    /// it is
+    #[must_use]
+    pub fn default(self, default: impl Into<String>) -> Self {
+        let mut column = self;
+        column.default = Some(default.into());
+        column
+    }
+
+    /// Mark column as primary key
+    #[must_use]
+    pub fn primary_key(self) -> Self {
+        let mut column = self;
+        column.primary_key = true;
+        column.nullable = false;
+        column
+    }
+
+    /// Mark column as unique
+    #[must_use]
+    pub fn unique(self) -> Self {
+        let mut column = self;
+        column.unique = true;
+        column
+    }
+
+    /// Return name of column
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return data type of column
+    #[must_use]
+    pub fn data_type(&self) -> &str {
+        &self.data_type
+    }
+
+    /// Return whether column is nullable
+    #[must_use]
+    pub fn is_nullable(&self) -> bool {
+        self.nullable
+    }
+
+    /// Return default value of column
+    #[must_use]
+    pub fn default_value(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    /// Render column definition for dialect
+    #[must_use]
+    pub fn definition(&self, dialect: Dialect) -> String {
+        let mut definition = format!("{} {}", dialect.quote(&self.name), self.data_type);
+        if self.primary_key {
+            definition.push_str(" PRIMARY KEY");
+        } else if !self.nullable {
+            definition.push_str(" NOT NULL");
+        }
+        if self.unique {
+            definition.push_str(" UNIQUE");
+        }
+        if let Some(default) = &self.default {
+            definition.push_str(" DEFAULT ");
+            definition.push_str(default);
+        }
+        definition
+    }
+}
+
+/// Trait implemented by all schema operations to render SQL statements for
+/// dialect
+pub trait SchemaOperation: Send + Sync {
+    /// Return statements run when operation is applied
+    ///
+    /// # Errors
+    /// If operation is not supported by dialect
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error>;
+
+    /// Return statements run when operation is reverted
+    ///
+    /// # Errors
+    /// If operation is not supported by dialect
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error>;
+
+    /// Return whether applying operation can lose data
+    fn is_destructible(&self) -> bool {
+        false
+    }
+}
+
+/// Operation to create table
+#[derive(Debug, Clone)]
+pub struct CreateTable {
+    name: String,
+    columns: Vec<Column>,
+}
+
+impl CreateTable {
+    /// Create new create table operation without any column
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            columns: vec![],
+        }
+    }
+
+    /// Add column to table
+    #[must_use]
+    pub fn column(self, column: Column) -> Self {
+        let mut operation = self;
+        operation.columns.push(column);
+        operation
+    }
+
+    fn create_statement(&self, dialect: Dialect) -> String {
+        let columns = self
+            .columns
+            .iter()
+            .map(|column| column.definition(dialect))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("CREATE TABLE {} ({columns})", dialect.quote(&self.name))
+    }
+
+    fn drop_statement(&self, dialect: Dialect) -> String {
+        format!("DROP TABLE {}", dialect.quote(&self.name))
+    }
+}
+
+impl SchemaOperation for CreateTable {
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.create_statement(dialect)])
+    }
+
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.drop_statement(dialect)])
+    }
+}
+
+/// Operation to drop table. Definition of table is used for recreating table
+/// when operation is reverted, so drop table without any column is
+/// irreversible. Only columns are recreated, indexes and foreign keys of
+/// dropped table are not recreated and need their own operation such as
+/// [`DropIndex`] or [`DropForeignKey`] placed before drop table
+#[derive(Debug, Clone)]
+pub struct DropTable {
+    table: CreateTable,
+}
+
+impl DropTable {
+    /// Create new drop table operation
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            table: CreateTable::new(name),
+        }
+    }
+
+    /// Add column of dropped table
+    #[must_use]
+    pub fn column(self, column: Column) -> Self {
+        Self {
+            table: self.table.column(column),
+        }
+    }
+}
+
+impl SchemaOperation for DropTable {
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.table.drop_statement(dialect)])
+    }
+
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        if self.table.columns.is_empty() {
+            return Err(Error::IrreversibleOperation);
+        }
+        Ok(vec![self.table.create_statement(dialect)])
+    }
+
+    fn is_destructible(&self) -> bool {
+        true
+    }
+}
+
+/// Operation to rename table
+#[derive(Debug, Clone)]
+pub struct RenameTable {
+    from: String,
+    to: String,
+}
+
+impl RenameTable {
+    /// Create new rename table operation
+    #[must_use]
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+}
+
+fn rename_table_statement(dialect: Dialect, from: &str, to: &str) -> String {
+    match dialect {
+        Dialect::MySql => {
+            format!(
+                "RENAME TABLE {} TO {}",
+                dialect.quote(from),
+                dialect.quote(to)
+            )
+        }
+        Dialect::Postgres | Dialect::Sqlite => {
+            format!(
+                "ALTER TABLE {} RENAME TO {}",
+                dialect.quote(from),
+                dialect.quote(to)
+            )
+        }
+    }
+}
+
+impl SchemaOperation for RenameTable {
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![rename_table_statement(dialect, &self.from, &self.to)])
+    }
+
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![rename_table_statement(dialect, &self.to, &self.from)])
+    }
+}
+
+/// Operation to add column to table
+#[derive(Debug, Clone)]
+pub struct AddColumn {
+    table: String,
+    column: Column,
+}
+
+impl AddColumn {
+    /// Create new add column operation
+    #[must_use]
+    pub fn new(table: impl Into<String>, column: Column) -> Self {
+        Self {
+            table: table.into(),
+            column,
+        }
+    }
+
+    fn add_statement(&self, dialect: Dialect) -> String {
+        format!(
+            "ALTER TABLE {} ADD COLUMN {}",
+            dialect.quote(&self.table),
+            self.column.definition(dialect)
+        )
+    }
+
+    fn drop_statement(&self, dialect: Dialect) -> String {
+        format!(
+            "ALTER TABLE {} DROP COLUMN {}",
+            dialect.quote(&self.table),
+            dialect.quote(&self.column.name)
+        )
+    }
+}
+
+impl SchemaOperation for AddColumn {
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.add_statement(dialect)])
+    }
+
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.drop_statement(dialect)])
+    }
+}
+
+/// Operation to drop column from table. Definition of column is used for
+/// adding column back when operation is reverted
+#[derive(Debug, Clone)]
+pub struct DropColumn {
+    column: AddColumn,
+}
+
+impl DropColumn {
+    /// Create new drop column operation
+    #[must_use]
+    pub fn new(table: impl Into<String>, column: Column) -> Self {
+        Self {
+            column: AddColumn::new(table, column),
+        }
+    }
+}
+
+impl SchemaOperation for DropColumn {
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.column.drop_statement(dialect)])
+    }
+
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.column.add_statement(dialect)])
+    }
+
+    fn is_destructible(&self) -> bool {
+        true
+    }
+}
+
+/// Operation to alter type, nullability and default value of column. Primary
+/// key and unique of column cannot be altered. `SQLite` doesn't support
+/// altering column.
+///
+/// `MySQL` alters column using `MODIFY COLUMN` which replaces whole definition
+/// of column with definition of `to` column. Attributes which cannot be
+/// described by [`Column`] such as `AUTO_INCREMENT`, `COMMENT`, character set
+/// and collation are removed from column, so write raw SQL operation for
+/// column using them
+#[derive(Debug, Clone)]
+pub struct AlterColumn {
+    table: String,
+    from: Column,
+    to: Column,
+}
+
+impl AlterColumn {
+    /// Create new alter column operation which changes column from one
+    /// definition to another definition. Use [`RenameColumn`] for renaming
+    /// column
+    #[must_use]
+    pub fn new(table: impl Into<String>, from: Column, to: Column) -> Self {
+        Self {
+            table: table.into(),
+            from,
+            to,
+        }
+    }
+}
+
+fn alter_column_statements(
+    dialect: Dialect,
+    table: &str,
+    from: &Column,
+    to: &Column,
+) -> Result<Vec<String>, Error> {
+    if from.name != to.name {
+        return Err(dialect.unsupported("altering column name using alter column"));
+    }
+    if from.primary_key != to.primary_key || from.unique != to.unique {
+        return Err(dialect.unsupported("altering primary key or unique using alter column"));
+    }
+    if from.data_type == to.data_type && from.nullable == to.nullable && from.default == to.default
+    {
+        return Ok(vec![]);
+    }
+    let table = dialect.quote(table);
+    let name = dialect.quote(&to.name);
+    match dialect {
+        Dialect::Postgres => {
+            let mut changes = vec![];
+            if from.data_type != to.data_type {
+                changes.push(format!(
+                    "ALTER COLUMN {name} TYPE {data_type}",
+                    data_type = to.data_type
+                ));
+            }
+            if from.nullable != to.nullable {
+                let action = if to.nullable { "DROP" } else { "SET" };
+                changes.push(format!("ALTER COLUMN {name} {action} NOT NULL"));
+            }
+            if from.default != to.default {
+                changes.push(match &to.default {
+                    Some(default) => format!("ALTER COLUMN {name} SET DEFAULT {default}"),
+                    None => format!("ALTER COLUMN {name} DROP DEFAULT"),
+                });
+            }
+            Ok(vec![format!("ALTER TABLE {table} {}", changes.join(", "))])
+        }
+        Dialect::MySql => {
+            // primary key and unique are constraint which are not changed by modify column
+            let mut column = Column::new(&to.name, &to.data_type);
+            column.nullable = to.nullable;
+            column.default.clone_from(&to.default);
+            Ok(vec![format!(
+                "ALTER TABLE {table} MODIFY COLUMN {}",
+                column.definition(dialect)
+            )])
+        }
+        Dialect::Sqlite => Err(dialect.unsupported("altering column")),
+    }
+}
+
+impl SchemaOperation for AlterColumn {
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        alter_column_statements(dialect, &self.table, &self.from, &self.to)
+    }
+
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        alter_column_statements(dialect, &self.table, &self.to, &self.from)
+    }
+
+    fn is_destructible(&self) -> bool {
+        self.from.data_type != self.to.data_type
+    }
+}
+
+/// Operation to rename column
+#[derive(Debug, Clone)]
+pub struct RenameColumn {
+    table: String,
+    from: String,
+    to: String,
+}
+
+impl RenameColumn {
+    /// Create new rename column operation
+    #[must_use]
+    pub fn new(table: impl Into<String>, from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+}
+
+fn rename_column_statement(dialect: Dialect, table: &str, from: &str, to: &str) -> String {
+    format!(
+        "ALTER TABLE {} RENAME COLUMN {} TO {}",
+        dialect.quote(table),
+        dialect.quote(from),
+        dialect.quote(to)
+    )
+}
+
+impl SchemaOperation for RenameColumn {
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![rename_column_statement(
+            dialect,
+            &self.table,
+            &self.from,
+            &self.to,
+        )])
+    }
+
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![rename_column_statement(
+            dialect,
+            &self.table,
+            &self.to,
+            &self.from,
+        )])
+    }
+}
+
+/// Operation to create index
+#[derive(Debug, Clone)]
+pub struct CreateIndex {
+    name: String,
+    table: String,
+    columns: Vec<String>,
+    unique: bool,
+}
+
+impl CreateIndex {
+    /// Create new create index operation for columns of table
+    #[must_use]
+    pub fn new<I, C>(name: impl Into<String>, table: impl Into<String>, columns: I) -> Self
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            table: table.into(),
+            columns: columns.into_iter().map(Into::into).collect(),
+            unique: false,
+        }
+    }
+
+    /// Mark index as unique
+    #[must_use]
+    pub fn unique(self) -> Self {
+        let mut operation = self;
+        operation.unique = true;
+        operation
+    }
+
+    fn create_statement(&self, dialect: Dialect) -> String {
+        let unique = if self.unique { "UNIQUE " } else { "" };
+        format!(
+            "CREATE {unique}INDEX {} ON {} ({})",
+            dialect.quote(&self.name),
+            dialect.quote(&self.table),
+            dialect.quote_list(&self.columns)
+        )
+    }
+
+    fn drop_statement(&self, dialect: Dialect) -> String {
+        match dialect {
+            Dialect::MySql => {
+                format!(
+                    "DROP INDEX {} ON {}",
+                    dialect.quote(&self.name),
+                    dialect.quote(&self.table)
+                )
+            }
+            Dialect::Postgres | Dialect::Sqlite => {
+                format!("DROP INDEX {}", dialect.quote(&self.name))
+            }
+        }
+    }
+}
+
+impl SchemaOperation for CreateIndex {
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.create_statement(dialect)])
+    }
+
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.drop_statement(dialect)])
+    }
+}
+
+/// Operation to drop index. Definition of index is used for recreating index
+/// when operation is reverted
+#[derive(Debug, Clone)]
+pub struct DropIndex {
+    index: CreateIndex,
+}
+
+impl DropIndex {
+    /// Create new drop index operation for index of columns of table
+    #[must_use]
+    pub fn new<I, C>(name: impl Into<String>, table: impl Into<String>, columns: I) -> Self
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<String>,
+    {
+        Self {
+            index: CreateIndex::new(name, table, columns),
+        }
+    }
+
+    /// Mark dropped index as unique
+    #[must_use]
+    pub fn unique(self) -> Self {
+        Self {
+            index: self.index.unique(),
+        }
+    }
+}
+
+impl SchemaOperation for DropIndex {
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.index.drop_statement(dialect)])
+    }
+
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.index.create_statement(dialect)])
+    }
+}
+
+/// Operation to add foreign key constraint. `SQLite` doesn't support adding
+/// foreign key to existing table
+#[derive(Debug, Clone)]
+pub struct AddForeignKey {
+    name: String,
+    table: String,
+    columns: Vec<String>,
+    referenced_table: String,
+    referenced_columns: Vec<String>,
+    on_delete: Option<String>,
+    on_update: Option<String>,
+}
+
+impl AddForeignKey {
+    /// Create new add foreign key operation where columns of table references
+    /// columns of referenced table
+    #[must_use]
+    pub fn new<I, C, RI, RC>(
+        name: impl Into<String>,
+        table: impl Into<String>,
+        columns: I,
+        referenced_table: impl Into<String>,
+        referenced_columns: RI,
+    ) -> Self
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<String>,
+        RI: IntoIterator<Item = RC>,
+        RC: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            table: table.into(),
+            columns: columns.into_iter().map(Into::into).collect(),
+            referenced_table: referenced_table.into(),
+            referenced_columns: referenced_columns.into_iter().map(Into::into).collect(),
+            on_delete: None,
+            on_update: None,
+        }
+    }
+
+    /// Set on delete action such as `CASCADE` or `SET NULL`
+    #[must_use]
+    pub fn on_delete(self, action: impl Into<String>) -> Self {
+        let mut operation = self;
+        operation.on_delete = Some(action.into());
+        operation
+    }
+
+    /// Set on update action such as `CASCADE` or `SET NULL`
+    #[must_use]
+    pub fn on_update(self, action: impl Into<String>) -> Self {
+        let mut operation = self;
+        operation.on_update = Some(action.into());
+        operation
+    }
+
+    fn add_statement(&self, dialect: Dialect) -> Result<String, Error> {
+        if dialect == Dialect::Sqlite {
+            return Err(dialect.unsupported("adding foreign key"));
+        }
+        let mut statement = format!(
+            "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({})",
+            dialect.quote(&self.table),
+            dialect.quote(&self.name),
+            dialect.quote_list(&self.columns),
+            dialect.quote(&self.referenced_table),
+            dialect.quote_list(&self.referenced_columns)
+        );
+        if let Some(on_delete) = &self.on_delete {
+            statement.push_str(" ON DELETE ");
+            statement.push_str(on_delete);
+        }
+        if let Some(on_update) = &self.on_update {
+            statement.push_str(" ON UPDATE ");
+            statement.push_str(on_update);
+        }
+        Ok(statement)
+    }
+
+    fn drop_statement(&self, dialect: Dialect) -> Result<String, Error> {
+        let table = dialect.quote(&self.table);
+        let name = dialect.quote(&self.name);
+        match dialect {
+            Dialect::Postgres => Ok(format!("ALTER TABLE {table} DROP CONSTRAINT {name}")),
+            Dialect::MySql => Ok(format!("ALTER TABLE {table} DROP FOREIGN KEY {name}")),
+            Dialect::Sqlite => Err(dialect.unsupported("dropping foreign key")),
+        }
+    }
+}
+
+impl SchemaOperation for AddForeignKey {
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.add_statement(dialect)?])
+    }
+
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.drop_statement(dialect)?])
+    }
+}
+
+/// Operation to drop foreign key constraint. Definition of foreign key is used
+/// for adding foreign key back when operation is reverted
+#[derive(Debug, Clone)]
+pub struct DropForeignKey {
+    foreign_key: AddForeignKey,
+}
+
+impl DropForeignKey {
+    /// Create new drop foreign key operation from definition of foreign key
+    #[must_use]
+    pub fn new(foreign_key: AddForeignKey) -> Self {
+        Self { foreign_key }
+    }
+}
+
+impl SchemaOperation for DropForeignKey {
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.foreign_key.drop_statement(dialect)?])
+    }
+
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.foreign_key.add_statement(dialect)?])
+    }
+}
+
+/// Operation to add check constraint. `SQLite` doesn't support adding check
+/// constraint to existing table
+#[derive(Debug, Clone)]
+pub struct AddCheckConstraint {
+    name: String,
+    table: String,
+    expression: String,
+}
+
+impl AddCheckConstraint {
+    /// Create new add check constraint operation. Expression is SQL
+    /// expression which is used as it is
+    #[must_use]
+    pub fn new(
+        name: impl Into<String>,
+        table: impl Into<String>,
+        expression: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            table: table.into(),
+            expression: expression.into(),
+        }
+    }
+
+    fn add_statement(&self, dialect: Dialect) -> Result<String, Error> {
+        if dialect == Dialect::Sqlite {
+            return Err(dialect.unsupported("adding check constraint"));
+        }
+        Ok(format!(
+            "ALTER TABLE {} ADD CONSTRAINT {} CHECK ({})",
+            dialect.quote(&self.table),
+            dialect.quote(&self.name),
+            self.expression
+        ))
+    }
+
+    fn drop_statement(&self, dialect: Dialect) -> Result<String, Error> {
+        let table = dialect.quote(&self.table);
+        let name = dialect.quote(&self.name);
+        match dialect {
+            Dialect::Postgres => Ok(format!("ALTER TABLE {table} DROP CONSTRAINT {name}")),
+            Dialect::MySql => Ok(format!("ALTER TABLE {table} DROP CHECK {name}")),
+            Dialect::Sqlite => Err(dialect.unsupported("dropping check constraint")),
+        }
+    }
+}
+
+impl SchemaOperation for AddCheckConstraint {
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.add_statement(dialect)?])
+    }
+
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.drop_statement(dialect)?])
+    }
+}
+
+/// Operation to drop check constraint. Definition of check constraint is used
+/// for adding check constraint back when operation is reverted
+#[derive(Debug, Clone)]
+pub struct DropCheckConstraint {
+    check: AddCheckConstraint,
+}
+
+impl DropCheckConstraint {
+    /// Create new drop check constraint operation from definition of check
+    /// constraint
+    #[must_use]
+    pub fn new(check: AddCheckConstraint) -> Self {
+        Self { check }
+    }
+}
+
+impl SchemaOperation for DropCheckConstraint {
+    fn up_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.check.drop_statement(dialect)?])
+    }
+
+    fn down_statements(&self, dialect: Dialect) -> Result<Vec<String>, Error> {
+        Ok(vec![self.check.add_statement(dialect)?])
+    }
+}
+
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+async fn execute_statements<DB>(
+    connection: &mut <DB as sqlx::Database>::Connection,
+    statements: Vec<String>,
+) -> Result<(), Error>
+where
+    DB: sqlx::Database,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: sqlx::Executor<'c, Database = DB>,
+    for<'q> <DB as sqlx::Database>::Arguments<'q>: sqlx::IntoArguments<'q, DB>,
+{
+    for statement in statements {
+        tracing::debug!("running schema statement {statement}");
+        sqlx::query(&statement).execute(&mut *connection).await?;
+    }
+    Ok(())
+}
+
+#[cfg(all(
+    any(feature = "postgres", feature = "mysql", feature = "sqlite"),
+    feature = "any"
+))]
+pub(crate) fn any_dialect(connection: &sqlx::AnyConnection) -> Result<Dialect, Error> {
+    Dialect::from_backend_name(connection.backend_name()).ok_or(Error::UnsupportedDatabase)
+}
+
+/// Return dialects of all enabled database which can be used by `Any`
+#[cfg(all(
+    any(feature = "postgres", feature = "mysql", feature = "sqlite"),
+    feature = "any"
+))]
+fn any_dialects() -> Vec<Dialect> {
+    vec![
+        #[cfg(feature = "postgres")]
+        Dialect::Postgres,
+        #[cfg(feature = "mysql")]
+        Dialect::MySql,
+        #[cfg(feature = "sqlite")]
+        Dialect::Sqlite,
+    ]
+}
+
+/// Return up and down statements of operation for dialect joined as SQL
+/// script. Down is `None` if operation cannot be reverted for dialect
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+fn statements_sql<O>(operation: &O, dialect: Dialect) -> Option<(String, Option<String>)>
+where
+    O: SchemaOperation + ?Sized,
+{
+    let join = |statements: Vec<String>| {
+        statements
+            .iter()
+            .map(|statement| format!("{statement};"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let up = operation.up_statements(dialect).ok()?;
+    let down = operation.down_statements(dialect).ok().map(join);
+    Some((join(up), down))
+}
+
+/// Return SQL of operation when it is same for all enabled database which can
+/// be used by `Any`, since database of `Any` is only known after connecting
+#[cfg(all(
+    any(feature = "postgres", feature = "mysql", feature = "sqlite"),
+    feature = "any"
+))]
+fn any_statements_sql<O>(operation: &O) -> Option<(String, Option<String>)>
+where
+    O: SchemaOperation + ?Sized,
+{
+    let mut sqls = any_dialects()
+        .into_iter()
+        .map(|dialect| statements_sql(operation, dialect));
+    let first = sqls.next()??;
+    sqls.all(|sql| sql.as_ref() == Some(&first))
+        .then_some(first)
+}
+
+/// Implement [`Operation`] for schema operations for all enabled database
+macro_rules! impl_schema_operation {
+    ($($operation:ty),* $(,)?) => {
+        $(
+            #[cfg(feature = "postgres")]
+            #[async_trait::async_trait]
+            impl Operation<sqlx::Postgres> for $operation {
+                async fn up(&self, connection: &mut sqlx::PgConnection) -> Result<(), Error> {
+                    let statements = self.up_statements(Dialect::Postgres)?;
+                    execute_statements::<sqlx::Postgres>(connection, statements).await
+                }
+
+                async fn down(&self, connection: &mut sqlx::PgConnection) -> Result<(), Error> {
+                    let statements = self.down_statements(Dialect::Postgres)?;
+                    execute_statements::<sqlx::Postgres>(connection, statements).await
+                }
+
+                fn is_reversible(&self) -> bool {
+                    self.down_statements(Dialect::Postgres).is_ok()
+                }
+
+                fn sql(&self) -> Option<(String, Option<String>)> {
+                    statements_sql(self, Dialect::Postgres)
+                }
+
+                fn is_destructible(&self) -> bool {
+                    SchemaOperation::is_destructible(self)
+                }
+            }
+
+            #[cfg(feature = "mysql")]
+            #[async_trait::async_trait]
+            impl Operation<sqlx::MySql> for $operation {
+                async fn up(&self, connection: &mut sqlx::MySqlConnection) -> Result<(), Error> {
+                    let statements = self.up_statements(Dialect::MySql)?;
+                    execute_statements::<sqlx::MySql>(connection, statements).await
+                }
+
+                async fn down(&self, connection: &mut sqlx::MySqlConnection) -> Result<(), Error> {
+                    let statements = self.down_statements(Dialect::MySql)?;
+                    execute_statements::<sqlx::MySql>(connection, statements).await
+                }
+
+                fn is_reversible(&self) -> bool {
+                    self.down_statements(Dialect::MySql).is_ok()
+                }
+
+                fn sql(&self) -> Option<(String, Option<String>)> {
+                    statements_sql(self, Dialect::MySql)
+                }
+
+                fn is_destructible(&self) -> bool {
+                    SchemaOperation::is_destructible(self)
+                }
+            }
+
+            #[cfg(feature = "sqlite")]
+            #[async_trait::async_trait]
+            impl Operation<sqlx::Sqlite> for $operation {
+                async fn up(&self, connection: &mut sqlx::SqliteConnection) -> Result<(), Error> {
+                    let statements = self.up_statements(Dialect::Sqlite)?;
+                    execute_statements::<sqlx::Sqlite>(connection, statements).await
+                }
+
+                async fn down(&self, connection: &mut sqlx::SqliteConnection) -> Result<(), Error> {
+                    let statements = self.down_statements(Dialect::Sqlite)?;
+                    execute_statements::<sqlx::Sqlite>(connection, statements).await
+                }
+
+                fn is_reversible(&self) -> bool {
+                    self.down_statements(Dialect::Sqlite).is_ok()
+                }
+
+                fn sql(&self) -> Option<(String, Option<String>)> {
+                    statements_sql(self, Dialect::Sqlite)
+                }
+
+                fn is_destructible(&self) -> bool {
+                    SchemaOperation::is_destructible(self)
+                }
+            }
+
+            #[cfg(all(
+                any(feature = "postgres", feature = "mysql", feature = "sqlite"),
+                feature = "any"
+            ))]
+            #[async_trait::async_trait]
+            impl Operation<sqlx::Any> for $operation {
+                async fn up(&self, connection: &mut sqlx::AnyConnection) -> Result<(), Error> {
+                    let statements = self.up_statements(any_dialect(connection)?)?;
+                    execute_statements::<sqlx::Any>(connection, statements).await
+                }
+
+                async fn down(&self, connection: &mut sqlx::AnyConnection) -> Result<(), Error> {
+                    let statements = self.down_statements(any_dialect(connection)?)?;
+                    execute_statements::<sqlx::Any>(connection, statements).await
+                }
+
+                // dialect is only known after connecting so operation is reversible only
+                // when it can be reverted for every enabled database
+                fn is_reversible(&self) -> bool {
+                    any_dialects()
+                        .into_iter()
+                        .all(|dialect| self.down_statements(dialect).is_ok())
+                }
+
+                // squashed SQL is only provided when operation renders same SQL for
+                // every enabled database
+                fn sql(&self) -> Option<(String, Option<String>)> {
+                    any_statements_sql(self)
+                }
+
+                fn is_destructible(&self) -> bool {
+                    SchemaOperation::is_destructible(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_schema_operation!(
+    CreateTable,
+    DropTable,
+    RenameTable,
+    AddColumn,
+    DropColumn,
+    AlterColumn,
+    RenameColumn,
+    CreateIndex,
+    DropIndex,
+    AddForeignKey,
+    DropForeignKey,
+    AddCheckConstraint,
+    DropCheckConstraint,
+);
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use sqlx::Sqlite;
+
+    use super::{
+        AddCheckConstraint, AddColumn, AlterColumn, Column, CreateIndex, CreateTable, Dialect,
+        DropColumn, DropTable, RenameColumn, RenameTable, SchemaOperation,
+    };
+    use crate::error::Error;
+    use crate::migrator::{Info, Migrate, Migrator, Plan};
+    use crate::operation::Operation;
+    use crate::test_utils::sqlite_pool;
+    use crate::vec_box;
+
+    #[tokio::test]
+    async fn schema_operations() {
+        struct A;
+        crate::sqlite_migration!(
+            A,
+            app = "test",
+            name = "a",
+            parents = vec_box![],
+            operations = vec_box![
+                CreateTable::new("users")
+                    .column(Column::new("id", "INTEGER").primary_key())
+                    .column(Column::new("name", "TEXT").not_null()),
+                AddColumn::new("users", Column::new("age", "INTEGER").default("0")),
+                CreateIndex::new("users_name", "users", ["name"]).unique(),
+                RenameColumn::new("users", "age", "years"),
+                RenameTable::new("users", "people"),
+            ]
+        );
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box![A]);
+        migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+        let objects = sqlx::query_scalar::<_, String>(
+            "SELECT name FROM sqlite_master WHERE name IN ('people', 'users', 'users_name') ORDER \
+             BY name",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap();
+        assert_eq!(objects, vec!["people", "users_name"]);
+        sqlx::query("INSERT INTO people (id, name, years) VALUES (1, 'a', 2)")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        migrator.run(&mut conn, &Plan::revert_all()).await.unwrap();
+        let objects = sqlx::query_scalar::<_, String>(
+            "SELECT name FROM sqlite_master WHERE name IN ('people', 'users', 'users_name')",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap();
+        assert!(objects.is_empty());
+
+        let drop_column = DropColumn::new("users", Column::new("age", "INTEGER").not_null());
+        assert!(SchemaOperation::is_destructible(&drop_column));
+        assert_eq!(
+            drop_column.down_statements(Dialect::MySql).unwrap(),
+            vec!["ALTER TABLE `users` ADD COLUMN `age` INTEGER NOT NULL"]
+        );
+        let alter_column = AlterColumn::new(
+            "users",
+            Column::new("age", "INTEGER"),
+            Column::new("age", "BIGINT").not_null().default("0"),
+        );
+        assert_eq!(
+            alter_column.up_statements(Dialect::Postgres).unwrap(),
+            vec![
+                "ALTER TABLE \"users\" ALTER COLUMN \"age\" TYPE BIGINT, ALTER COLUMN \"age\" SET \
+                 NOT NULL, ALTER COLUMN \"age\" SET DEFAULT 0"
+            ]
+        );
+        assert_eq!(
+            alter_column.down_statements(Dialect::MySql).unwrap(),
+            vec!["ALTER TABLE `users` MODIFY COLUMN `age` INTEGER"]
+        );
+        assert!(matches!(
+            alter_column.up_statements(Dialect::Sqlite),
+            Err(Error::UnsupportedSchemaOperation { .. })
+        ));
+        let check = AddCheckConstraint::new("age_check", "users", "age > 0");
+        assert_eq!(
+            check.down_statements(Dialect::MySql).unwrap(),
+            vec!["ALTER TABLE `users` DROP CHECK `age_check`"]
+        );
+    }
+
+    #[test]
+    fn alter_column_without_change() {
+        let default_only = AlterColumn::new(
+            "users",
+            Column::new("age", "INTEGER").default("0"),
+            Column::new("age", "INTEGER").default("0"),
+        );
+        for dialect in [Dialect::Postgres, Dialect::MySql, Dialect::Sqlite] {
+            assert!(default_only.up_statements(dialect).unwrap().is_empty());
+            assert!(default_only.down_statements(dialect).unwrap().is_empty());
+        }
+        // primary key and unique only differ so no valid change list exists
+        for to in [
+            Column::new("id", "INTEGER").not_null().unique(),
+            Column::new("id", "INTEGER").primary_key(),
+        ] {
+            let alter_column =
+                AlterColumn::new("users", Column::new("id", "INTEGER").not_null(), to);
+            assert!(matches!(
+                alter_column.up_statements(Dialect::Postgres),
+                Err(Error::UnsupportedSchemaOperation { .. })
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn schema_operation_reversibility() {
+        struct A;
+        crate::sqlite_migration!(
+            A,
+            app = "test",
+            name = "a",
+            parents = vec_box![],
+            operations = vec_box![
+                CreateTable::new("users").column(Column::new("id", "INTEGER")),
+                DropTable::new("users")
+            ]
+        );
+        let drop_without_columns = DropTable::new("users");
+        assert!(matches!(
+            drop_without_columns.down_statements(Dialect::Postgres),
+            Err(Error::IrreversibleOperation)
+        ));
+        assert!(!Operation::<Sqlite>::is_reversible(&drop_without_columns));
+        assert_eq!(
+            Operation::<Sqlite>::sql(&drop_without_columns),
+            Some(("DROP TABLE \"users\";".to_string(), None))
+        );
+        let drop_with_columns = DropTable::new("users").column(Column::new("id", "INTEGER"));
+        assert!(Operation::<Sqlite>::is_reversible(&drop_with_columns));
+        let alter_column = AlterColumn::new(
+            "users",
+            Column::new("age", "INTEGER"),
+            Column::new("age", "BIGINT"),
+        );
+        assert!(!Operation::<Sqlite>::is_reversible(&alter_column));
+
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box![A]);
+        migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+        // irreversible drop table is detected before revert plan runs
+        assert!(matches!(
+            migrator.run(&mut conn, &Plan::revert_all()).await,
+            Err(Error::PlanError { message }) if message.ends_with("test:a")
+        ));
+    }
+
+    #[cfg(feature = "any")]
+    #[test]
+    fn any_schema_operation_sql() {
+        let rename_table = RenameTable::new("users", "people");
+        let sql = Operation::<sqlx::Any>::sql(&rename_table);
+        // mysql quotes identifier and renames table differently than other database
+        if cfg!(feature = "mysql") {
+            assert_eq!(sql, None);
+        } else {
+            assert_eq!(
+                sql,
+                Some((
+                    "ALTER TABLE \"users\" RENAME TO \"people\";".to_string(),
+                    Some("ALTER TABLE \"people\" RENAME TO \"users\";".to_string())
+                ))
+            );
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! Module for running SQL scripts containing multiple statements
+//!
+//! Prepared statement used by `sqlx::query` only supports single statement
+//! for postgres and mysql. [`execute_script`] runs script without arguments
+//! so simple query protocol of database is used which supports multiple
+//! statements. Mysql scripts which uses client `DELIMITER` directive are split
+//! into statements using [`split_statements`] and each statement is run one
+//! after another.
+//!
+//! Operation implemented for `(up, down)` tuple and SQL migrations uses
+//! [`execute_script`] for running SQL.
+
+use sqlx::{Database, Executor};
+
+use crate::error::Error;
+use crate::schema::Dialect;
+
+/// Return whether line is mysql client `DELIMITER` directive
+fn is_delimiter_directive(line: &str) -> bool {
+    let line = line.trim_start();
+    line.get(..9)
+        .is_some_and(|keyword| keyword.eq_ignore_ascii_case("DELIMITER"))
+        && line[9..].starts_with(char::is_whitespace)
+}
+
+/// Return index after end of quoted value which starts at index
+fn skip_quoted(sql: &str, index: usize, quote: u8, backslash_escape: bool) -> usize {
+    let bytes = sql.as_bytes();
+    let mut index = index + 1;
+    while index < bytes.len() {
+        if backslash_escape && bytes[index] == b'\\' {
+            index += 2;
+            continue;
+        }
+        if bytes[index] == quote {
+            // doubled quote is escaped quote
+            if bytes.get(index + 1) == Some(&quote) {
+                index += 2;
+                continue;
+            }
+            return index + 1;
+        }
+        index += 1;
+    }
+    bytes.len()
+}
+
+/// Return tag of postgres dollar quoted string such as `$$` or `$body$` which
+/// starts at index
+fn dollar_quote_tag(sql: &str, index: usize) -> Option<&str> {
+    let rest = &sql[index + 1..];
+    let tag_length = rest.find('$')?;
+    let tag = &rest[..tag_length];
+    let is_valid_tag = tag.chars().enumerate().all(|(position, c)| {
+        c == '_' || c.is_ascii_alphabetic() || (position > 0 && c.is_ascii_digit())
+    });
+    is_valid_tag.then(|| &sql[index..index + tag_length + 2])
+}
+
+/// Return whether statement is sqlite trigger whose body can contain
+/// semicolon. Such statement only ends with `END` followed by delimiter
+fn is_sqlite_trigger(statement: &str) -> bool {
+    let mut words = statement
+        .split_whitespace()
+        .map(str::to_ascii_uppercase)
+        .take(3);
+    if words.next().as_deref() != Some("CREATE") {
+        return false;
+    }
+    match words.next().as_deref() {
+        Some("TRIGGER") => true,
+        Some("TEMP" | "TEMPORARY") => words.next().as_deref() == Some("TRIGGER"),
+        _ => false,
+    }
+}
+
+/// Return whether statement ends with `END` keyword
+fn ends_with_end_keyword(statement: &str) -> bool {
+    let statement = statement.trim_end().as_bytes();
+    let Some(keyword_start) = statement.len().checked_sub(3) else {
+        return false;
+    };
+    statement[keyword_start..].eq_ignore_ascii_case(b"END")
+        && !statement[..keyword_start]
+            .last()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || *c == b'_')
+}
+
+/// Split SQL script into statements for dialect.
+///
+/// Statements are split at `;` which are not present inside quoted value,
+/// comment, postgres dollar quoted string or sqlite trigger body. For mysql
+/// `DELIMITER` directive changes delimiter used for splitting statements and
+/// directive itself is not included in statements. Returned statements are
+/// trimmed and empty statements are skipped
+#[must_use]
+pub fn split_statements(sql: &str, dialect: Dialect) -> Vec<String> {
+    split(sql, dialect).0
+}
+
+/// Split SQL script into statements and return whether any `DELIMITER`
+/// directive was found outside of quoted value and comment
+fn split(sql: &str, dialect: Dialect) -> (Vec<String>, bool) {
+    let bytes = sql.as_bytes();
+    let mut statements = vec![];
+    let mut delimiter = ";".to_string();
+    let mut has_directive = false;
+    let mut start = 0;
+    let mut index = 0;
+    let mut push_statement = |statement: &str| {
+        let statement = statement.trim();
+        if !statement.is_empty() {
+            statements.push(statement.to_string());
+        }
+    };
+    while index < bytes.len() {
+        let rest = &sql[index..];
+        if dialect == Dialect::MySql
+            && matches!(bytes[index], b'D' | b'd')
+            && sql[..index]
+                .rsplit('\n')
+                .next()
+                .is_some_and(|line| line.trim().is_empty())
+            && is_delimiter_directive(rest.lines().next().unwrap_or_default())
+        {
+            has_directive = true;
+            push_statement(&sql[start..index]);
+            let line_end = rest.find('\n').map_or(bytes.len(), |end| index + end);
+            delimiter = sql[index + 9..line_end].trim().to_string();
+            index = line_end;
+            start = index;
+            continue;
+        }
+        match bytes[index] {
+            b'\'' | b'"' => {
+                index = skip_quoted(sql, index, bytes[index], dialect == Dialect::MySql);
+            }
+            b'`' if dialect == Dialect::MySql => {
+                index = skip_quoted(sql, index, b'`', false);
+            }
+            b'-' if rest.starts_with("--") => {
+                index = rest.find('\n').map_or(bytes.len(), |end| index + end);
+            }
+            b'#' if dialect == Dialect::MySql => {
+                index = rest.find('\n').map_or(bytes.len(), |end| index + end);
+            }
+            b'/' if rest.starts_with("/*") => {
+                index = rest[2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| index + end + 4);
+            }
+            b'$' if dialect == Dialect::Postgres && dollar_quote_tag(sql, index).is_some() => {
+                let tag = dollar_quote_tag(sql, index).unwrap_or_default();
+                let body_start = index + tag.len();
+                index = sql[body_start..]
+                    .find(tag)
+                    .map_or(bytes.len(), |end| body_start + end + tag.len());
+            }
+            _ if !delimiter.is_empty() && rest.starts_with(delimiter.as_str()) => {
+                let statement = &sql[start..index];
+                if dialect == Dialect::Sqlite
+                    && is_sqlite_trigger(statement)
+                    && !ends_with_end_keyword(statement)
+                {
+                    index += delimiter.len();
+                    continue;
+                }
+                push_statement(statement);
+                index += delimiter.len();
+                start = index;
+            }
+            _ => {
+                index += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+    push_statement(&sql[start..]);
+    (statements, has_directive)
+}
+
+/// Return whether connection is mysql connection. Backend of `Any` connection
+/// is resolved at runtime
+fn is_mysql<DB>(
+    #[cfg_attr(
+        not(all(feature = "any", feature = "mysql")),
+        expect(unused_variables)
+    )]
+    connection: &<DB as Database>::Connection,
+) -> bool
+where
+    DB: Database,
+    <DB as Database>::Connection: 'static,
+{
+    #[cfg(all(feature = "any", feature = "mysql"))]
+    if let Some(connection) =
+        (connection as &dyn std::any::Any).downcast_ref::<sqlx::AnyConnection>()
+    {
+        return crate::schema::any_dialect(connection).ok() == Some(Dialect::MySql);
+    }
+    Dialect::from_backend_name(DB::NAME) == Some(Dialect::MySql)
+}
+
+/// Execute SQL script which can contain multiple statements
+///
+/// # Errors
+/// If any statement of script fails
+pub async fn execute_script<DB>(
+    connection: &mut <DB as Database>::Connection,
+    sql: &str,
+) -> Result<(), Error>
+where
+    DB: Database,
+    <DB as Database>::Connection: 'static,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+{
+    // DELIMITER is only understood by mysql client so statements are run one by
+    // one without delimiter
+    if is_mysql::<DB>(connection) {
+        let (statements, has_directive) = split(sql, Dialect::MySql);
+        if has_directive {
+            for statement in statements {
+                connection.execute(statement.as_str()).await?;
+            }
+            return Ok(());
+        }
+    }
+    connection.execute(sql).await?;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use sqlx::Sqlite;
+
+    use super::{execute_script, split, split_statements};
+    use crate::migrator::{Info, Migrate, Migrator, Plan};
+    use crate::schema::Dialect;
+    use crate::test_utils::sqlite_pool;
+    use crate::vec_box;
+
+    #[tokio::test]
+    async fn multi_statement_script() {
+        struct A;
+        crate::sqlite_migration!(
+            A,
+            app = "test",
+            name = "a",
+            parents = vec_box![],
+            operations = vec_box![(
+                "CREATE TABLE a (id INTEGER PRIMARY KEY, note TEXT); INSERT INTO a (note) VALUES \
+                 ('first; still first'); CREATE TRIGGER a_trigger AFTER INSERT ON a BEGIN UPDATE \
+                 a SET note = 'triggered' WHERE id = NEW.id; END;",
+                "DROP TRIGGER a_trigger; DROP TABLE a;"
+            )]
+        );
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box![A]);
+        migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+        sqlx::query("INSERT INTO a (note) VALUES ('second')")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        let notes = sqlx::query_scalar::<_, String>("SELECT note FROM a ORDER BY id")
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(notes, vec!["first; still first", "triggered"]);
+        migrator.run(&mut conn, &Plan::revert_all()).await.unwrap();
+
+        assert_eq!(
+            split_statements(
+                "CREATE FUNCTION f() RETURNS INT AS $body$ SELECT 1; $body$ LANGUAGE SQL;\n-- a; \
+                 comment\nSELECT 'a;b', \"c;d\" /* e; */;",
+                Dialect::Postgres
+            ),
+            vec![
+                "CREATE FUNCTION f() RETURNS INT AS $body$ SELECT 1; $body$ LANGUAGE SQL",
+                "-- a; comment\nSELECT 'a;b', \"c;d\" /* e; */"
+            ]
+        );
+        assert_eq!(
+            split_statements(
+                "DROP PROCEDURE IF EXISTS p;\nDELIMITER //\nCREATE PROCEDURE p() BEGIN SELECT \
+                 'it\\'s'; SELECT 2; END//\nDELIMITER ;\nCALL p();",
+                Dialect::MySql
+            ),
+            vec![
+                "DROP PROCEDURE IF EXISTS p",
+                "CREATE PROCEDURE p() BEGIN SELECT 'it\\'s'; SELECT 2; END",
+                "CALL p()"
+            ]
+        );
+        assert_eq!(
+            split_statements(
+                "CREATE TEMP TRIGGER t AFTER INSERT ON a BEGIN SELECT 1; SELECT 2; END; SELECT 3",
+                Dialect::Sqlite
+            ),
+            vec![
+                "CREATE TEMP TRIGGER t AFTER INSERT ON a BEGIN SELECT 1; SELECT 2; END",
+                "SELECT 3"
+            ]
+        );
+    }
+
+    #[test]
+    fn split_non_ascii_trigger() {
+        assert_eq!(
+            split_statements(
+                "CREATE TRIGGER t AFTER INSERT ON tableéab BEGIN DELETE FROM tableéab; END; \
+                 SELECT 1",
+                Dialect::Sqlite
+            ),
+            vec![
+                "CREATE TRIGGER t AFTER INSERT ON tableéab BEGIN DELETE FROM tableéab; END",
+                "SELECT 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn delimiter_directive_outside_quote_and_comment() {
+        let (statements, has_directive) = split(
+            "SELECT '\nDELIMITER //\n';\n/*\nDELIMITER //\n*/\nSELECT 1;",
+            Dialect::MySql,
+        );
+        assert!(!has_directive);
+        assert_eq!(statements.len(), 2);
+        let (_, has_directive) = split("SELECT 1;\nDELIMITER //\nSELECT 2//", Dialect::MySql);
+        assert!(has_directive);
+        let (_, has_directive) = split("SELECT 1;\nDELIMITER //\nSELECT 2//", Dialect::Sqlite);
+        assert!(!has_directive);
+    }
+
+    #[tokio::test]
+    async fn delimiter_directive_ignored_for_non_mysql() {
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        execute_script::<Sqlite>(
+            &mut conn,
+            "CREATE TABLE a (note TEXT); INSERT INTO a (note) VALUES ('\nDELIMITER //\n'), \
+             ('it\\');",
+        )
+        .await
+        .unwrap();
+        let notes = sqlx::query_scalar::<_, String>("SELECT note FROM a")
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(notes, vec!["\nDELIMITER //\n", "it\\"]);
+    }
+
+    #[cfg(feature = "any")]
+    #[tokio::test]
+    async fn delimiter_directive_ignored_for_non_mysql_any() {
+        use sqlx::Connection;
+
+        use super::is_mysql;
+
+        sqlx::any::install_default_drivers();
+        let mut conn = sqlx::AnyConnection::connect("sqlite::memory:")
+            .await
+            .unwrap();
+        assert!(!is_mysql::<sqlx::Any>(&conn));
+        execute_script::<sqlx::Any>(
+            &mut conn,
+            "CREATE TABLE a (note TEXT); INSERT INTO a (note) VALUES ('\nDELIMITER //\n');",
+        )
+        .await
+        .unwrap();
+        let notes = sqlx::query_scalar::<_, String>("SELECT note FROM a")
+            .fetch_all(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(notes, vec!["\nDELIMITER //\n"]);
+    }
+}
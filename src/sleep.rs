@@ -0,0 +1,51 @@
+//! Module containing runtime agnostic sleep
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+/// Runtime agnostic sleep which wakes task from separate thread since crate
+/// doesn't depend upon any async runtime. Each call spawns one OS thread which
+/// lives for given duration
+pub(crate) fn sleep(duration: Duration) -> impl Future<Output = ()> {
+    struct Sleep {
+        state: Arc<Mutex<(bool, Option<Waker>)>>,
+        duration: Option<Duration>,
+    }
+
+    impl Future for Sleep {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            if let Some(duration) = self.duration.take() {
+                let state = Arc::clone(&self.state);
+                std::thread::spawn(move || {
+                    std::thread::sleep(duration);
+                    let mut state = state
+                        .lock()
+                        .unwrap_or_else(std::sync::PoisonError::into_inner);
+                    state.0 = true;
+                    if let Some(waker) = state.1.take() {
+                        waker.wake();
+                    }
+                });
+            }
+            let mut state = self
+                .state
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            if state.0 {
+                return Poll::Ready(());
+            }
+            state.1 = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    Sleep {
+        state: Arc::new(Mutex::new((false, None))),
+        duration: Some(duration),
+    }
+}
@@ -0,0 +1,628 @@
+//! Module for taking snapshot of database schema and detecting drift
+//!
+//! [`Snapshot`] is a normalized view of tables, columns, indexes and
+//! constraints present in database along with list of migrations which were
+//! applied when snapshot was taken. Snapshot is taken using
+//! [`Migrate::take_snapshot`](crate::migrator::Migrate::take_snapshot) which
+//! reads schema from `pg_catalog` for postgres, `information_schema` for mysql
+//! and `sqlite_master` for sqlite. Tables of migrator are not included in
+//! snapshot.
+//!
+//! Snapshot can be serialized to JSON and committed alongside migrations.
+//! [`Migrate::detect_drift`](crate::migrator::Migrate::detect_drift) compares
+//! live database against committed snapshot to find changes which were applied
+//! directly to database without migration.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// Column of table present in snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotColumn {
+    name: String,
+    data_type: String,
+    nullable: bool,
+    default: Option<String>,
+}
+
+impl SnapshotColumn {
+    /// Return name of column
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return data type of column as reported by database
+    #[must_use]
+    pub fn data_type(&self) -> &str {
+        &self.data_type
+    }
+
+    /// Return whether column is nullable
+    #[must_use]
+    pub fn nullable(&self) -> bool {
+        self.nullable
+    }
+
+    /// Return default value expression of column
+    #[must_use]
+    pub fn default(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    fn definition(&self) -> String {
+        let not_null = if self.nullable { "" } else { " NOT NULL" };
+        match &self.default {
+            Some(default) => format!("{}{not_null} DEFAULT {default}", self.data_type),
+            None => format!("{}{not_null}", self.data_type),
+        }
+    }
+}
+
+/// Index of table present in snapshot. Primary key index is not included
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotIndex {
+    name: String,
+    columns: Vec<String>,
+    unique: bool,
+}
+
+impl SnapshotIndex {
+    /// Return name of index
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return columns or expressions of index in order
+    #[must_use]
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Return whether index is unique
+    #[must_use]
+    pub fn unique(&self) -> bool {
+        self.unique
+    }
+
+    fn definition(&self) -> String {
+        let unique = if self.unique { "UNIQUE " } else { "" };
+        format!("{unique}({})", self.columns.join(", "))
+    }
+}
+
+/// Primary key, foreign key or check constraint of table present in snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotConstraint {
+    name: String,
+    kind: String,
+    definition: String,
+}
+
+impl SnapshotConstraint {
+    /// Return name of constraint
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return kind of constraint which is one of `PRIMARY KEY`, `FOREIGN KEY`
+    /// or `CHECK`
+    #[must_use]
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// Return definition of constraint such as `PRIMARY KEY (id)`
+    #[must_use]
+    pub fn definition(&self) -> &str {
+        &self.definition
+    }
+}
+
+/// Table present in snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotTable {
+    name: String,
+    columns: Vec<SnapshotColumn>,
+    indexes: Vec<SnapshotIndex>,
+    constraints: Vec<SnapshotConstraint>,
+}
+
+impl SnapshotTable {
+    /// Return name of table
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return columns of table in order of their position
+    #[must_use]
+    pub fn columns(&self) -> &[SnapshotColumn] {
+        &self.columns
+    }
+
+    /// Return indexes of table sorted by name
+    #[must_use]
+    pub fn indexes(&self) -> &[SnapshotIndex] {
+        &self.indexes
+    }
+
+    /// Return constraints of table sorted by name
+    #[must_use]
+    pub fn constraints(&self) -> &[SnapshotConstraint] {
+        &self.constraints
+    }
+
+    fn diff(&self, actual: &Self, drifts: &mut Vec<Drift>) {
+        let table = &self.name;
+        diff_items(
+            &self.columns,
+            &actual.columns,
+            |column| &column.name,
+            |column| format!("column {table}.{}", column.name),
+            SnapshotColumn::definition,
+            drifts,
+        );
+        diff_items(
+            &self.indexes,
+            &actual.indexes,
+            |index| &index.name,
+            |index| format!("index {table}.{}", index.name),
+            SnapshotIndex::definition,
+            drifts,
+        );
+        diff_items(
+            &self.constraints,
+            &actual.constraints,
+            |constraint| &constraint.name,
+            |constraint| format!("constraint {table}.{}", constraint.name),
+            |constraint| constraint.definition.clone(),
+            drifts,
+        );
+    }
+}
+
+/// Normalized snapshot of database schema
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    migrations: Vec<String>,
+    tables: Vec<SnapshotTable>,
+}
+
+impl Snapshot {
+    /// Return applied migrations when snapshot was taken in `app:name` format
+    /// sorted alphabetically
+    #[must_use]
+    pub fn migrations(&self) -> &[String] {
+        &self.migrations
+    }
+
+    /// Return tables sorted by name
+    #[must_use]
+    pub fn tables(&self) -> &[SnapshotTable] {
+        &self.tables
+    }
+
+    /// Return table with provided name
+    #[must_use]
+    pub fn table(&self, name: &str) -> Option<&SnapshotTable> {
+        self.tables.iter().find(|table| table.name == name)
+    }
+
+    /// Set applied migrations of snapshot
+    #[must_use]
+    pub(crate) fn with_migrations(self, migrations: impl IntoIterator<Item = String>) -> Self {
+        let mut snapshot = self;
+        snapshot.migrations = migrations.into_iter().collect();
+        snapshot.migrations.sort();
+        snapshot
+    }
+
+    /// Serialize snapshot to pretty printed JSON
+    ///
+    /// # Errors
+    /// If snapshot cannot be serialized
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|error| {
+            Error::SnapshotError {
+                message: error.to_string(),
+            }
+        })
+    }
+
+    /// Deserialize snapshot from JSON
+    ///
+    /// # Errors
+    /// If JSON is not valid snapshot
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|error| {
+            Error::SnapshotError {
+                message: format!("invalid snapshot: {error}"),
+            }
+        })
+    }
+
+    /// Compare snapshot against actual snapshot and return list of
+    /// differences. Applied migrations are not compared
+    #[must_use]
+    pub fn diff(&self, actual: &Self) -> Vec<Drift> {
+        let mut drifts = vec![];
+        for table in &self.tables {
+            match actual.table(&table.name) {
+                Some(actual_table) => table.diff(actual_table, &mut drifts),
+                None => {
+                    drifts.push(Drift::Missing {
+                        object: format!("table {}", table.name),
+                    });
+                }
+            }
+        }
+        for table in &actual.tables {
+            if self.table(&table.name).is_none() {
+                drifts.push(Drift::Unexpected {
+                    object: format!("table {}", table.name),
+                });
+            }
+        }
+        drifts
+    }
+}
+
+/// Difference between snapshot and database schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Drift {
+    /// Object present in snapshot is missing from database
+    Missing {
+        /// Type and name of object such as `column users.name`
+        object: String,
+    },
+    /// Object present in database is not present in snapshot
+    Unexpected {
+        /// Type and name of object such as `column users.name`
+        object: String,
+    },
+    /// Definition of object in database differs from snapshot
+    Changed {
+        /// Type and name of object such as `column users.name`
+        object: String,
+        /// Definition present in snapshot
+        expected: String,
+        /// Definition present in database
+        actual: String,
+    },
+}
+
+impl std::fmt::Display for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing { object } => write!(f, "missing {object}"),
+            Self::Unexpected { object } => write!(f, "unexpected {object}"),
+            Self::Changed {
+                object,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "changed {object}: expected `{expected}`, found `{actual}`"
+                )
+            }
+        }
+    }
+}
+
+fn diff_items<T>(
+    expected: &[T],
+    actual: &[T],
+    name: impl Fn(&T) -> &String,
+    object: impl Fn(&T) -> String,
+    definition: impl Fn(&T) -> String,
+    drifts: &mut Vec<Drift>,
+) {
+    for item in expected {
+        match actual
+            .iter()
+            .find(|actual_item| name(actual_item) == name(item))
+        {
+            Some(actual_item) => {
+                let expected_definition = definition(item);
+                let actual_definition = definition(actual_item);
+                if expected_definition != actual_definition {
+                    drifts.push(Drift::Changed {
+                        object: object(item),
+                        expected: expected_definition,
+                        actual: actual_definition,
+                    });
+                }
+            }
+            None => {
+                drifts.push(Drift::Missing {
+                    object: object(item),
+                });
+            }
+        }
+    }
+    for item in actual {
+        if !expected
+            .iter()
+            .any(|expected_item| name(expected_item) == name(item))
+        {
+            drifts.push(Drift::Unexpected {
+                object: object(item),
+            });
+        }
+    }
+}
+
+/// Queries used for taking snapshot of schema. Each query must return rows in
+/// order of table name followed by position of column
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+pub(crate) struct SnapshotQueries {
+    /// Query returning `table_name`, `column_name`, `data_type`,
+    /// `is_nullable` (`YES` or `NO`) and `column_default` columns
+    pub(crate) columns: &'static str,
+    /// Query returning `table_name`, `index_name`, `is_unique` (`YES` or
+    /// `NO`) and `column_name` columns
+    pub(crate) indexes: &'static str,
+    /// Query returning `table_name`, `constraint_name`, `constraint_type` and
+    /// `definition` columns where definition starts with constraint type
+    pub(crate) constraints: &'static str,
+}
+
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+#[derive(sqlx::FromRow)]
+pub(crate) struct ColumnRow {
+    table_name: String,
+    column_name: String,
+    data_type: String,
+    is_nullable: String,
+    column_default: Option<String>,
+}
+
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+#[derive(sqlx::FromRow)]
+pub(crate) struct IndexRow {
+    table_name: String,
+    index_name: String,
+    is_unique: String,
+    column_name: String,
+}
+
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+#[derive(sqlx::FromRow)]
+pub(crate) struct ConstraintRow {
+    table_name: String,
+    constraint_name: String,
+    constraint_type: String,
+    definition: String,
+}
+
+/// Take snapshot of schema using queries. Tables present in excluded tables
+/// list are skipped
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+pub(crate) async fn fetch_snapshot<DB>(
+    connection: &mut <DB as sqlx::Database>::Connection,
+    queries: &SnapshotQueries,
+    excluded_tables: &[String],
+) -> Result<Snapshot, Error>
+where
+    DB: sqlx::Database,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: sqlx::Executor<'c, Database = DB>,
+    for<'q> <DB as sqlx::Database>::Arguments<'q>: sqlx::IntoArguments<'q, DB>,
+    for<'r> ColumnRow: sqlx::FromRow<'r, <DB as sqlx::Database>::Row>,
+    for<'r> IndexRow: sqlx::FromRow<'r, <DB as sqlx::Database>::Row>,
+    for<'r> ConstraintRow: sqlx::FromRow<'r, <DB as sqlx::Database>::Row>,
+{
+    let column_rows = sqlx::query_as::<_, ColumnRow>(queries.columns)
+        .fetch_all(&mut *connection)
+        .await?;
+    let index_rows = sqlx::query_as::<_, IndexRow>(queries.indexes)
+        .fetch_all(&mut *connection)
+        .await?;
+    let constraint_rows = sqlx::query_as::<_, ConstraintRow>(queries.constraints)
+        .fetch_all(&mut *connection)
+        .await?;
+
+    let mut tables: Vec<SnapshotTable> = vec![];
+    for row in column_rows {
+        if excluded_tables.contains(&row.table_name) {
+            continue;
+        }
+        if tables
+            .last()
+            .is_none_or(|table| table.name != row.table_name)
+        {
+            tables.push(SnapshotTable {
+                name: row.table_name.clone(),
+                columns: vec![],
+                indexes: vec![],
+                constraints: vec![],
+            });
+        }
+        if let Some(table) = tables.last_mut() {
+            table.columns.push(SnapshotColumn {
+                name: row.column_name,
+                data_type: row.data_type,
+                nullable: row.is_nullable.eq_ignore_ascii_case("YES"),
+                default: row.column_default,
+            });
+        }
+    }
+    for row in index_rows {
+        let Some(table) = tables.iter_mut().find(|table| table.name == row.table_name) else {
+            continue;
+        };
+        match table
+            .indexes
+            .iter_mut()
+            .find(|index| index.name == row.index_name)
+        {
+            Some(index) => index.columns.push(row.column_name),
+            None => {
+                table.indexes.push(SnapshotIndex {
+                    name: row.index_name,
+                    columns: vec![row.column_name],
+                    unique: row.is_unique.eq_ignore_ascii_case("YES"),
+                });
+            }
+        }
+    }
+    for row in constraint_rows {
+        let Some(table) = tables.iter_mut().find(|table| table.name == row.table_name) else {
+            continue;
+        };
+        table.constraints.push(SnapshotConstraint {
+            name: row.constraint_name,
+            kind: row.constraint_type,
+            definition: row.definition,
+        });
+    }
+    tables.sort_by(|a, b| a.name.cmp(&b.name));
+    for table in &mut tables {
+        table.indexes.sort_by(|a, b| a.name.cmp(&b.name));
+        table.constraints.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    Ok(Snapshot {
+        migrations: vec![],
+        tables,
+    })
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use sqlx::Sqlite;
+
+    use super::{Drift, Snapshot, SnapshotConstraint, SnapshotTable};
+    use crate::error::Error;
+    use crate::migrator::{DatabaseOperation, Info, Migrate, Migrator, Plan};
+    use crate::test_utils::sqlite_pool;
+    use crate::vec_box;
+
+    #[tokio::test]
+    async fn schema_snapshot_drift() {
+        struct A;
+        crate::sqlite_migration!(
+            A,
+            app = "test",
+            name = "a",
+            parents = vec_box![],
+            operations = vec_box![(
+                "CREATE TABLE authors (id INTEGER PRIMARY KEY, name TEXT NOT NULL DEFAULT ''); \
+                 CREATE TABLE books (id INTEGER PRIMARY KEY, author_id INTEGER REFERENCES authors \
+                 (id), title TEXT, UNIQUE (author_id, title)); CREATE INDEX books_title ON books \
+                 (title);",
+                "DROP TABLE books; DROP TABLE authors;"
+            )]
+        );
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box![A]);
+        migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+
+        let snapshot = migrator.take_snapshot(&mut conn).await.unwrap();
+        assert_eq!(snapshot.migrations(), ["test:a"]);
+        let table_names = snapshot
+            .tables()
+            .iter()
+            .map(SnapshotTable::name)
+            .collect::<Vec<_>>();
+        assert_eq!(table_names, vec!["authors", "books"]);
+        let authors = snapshot.table("authors").unwrap();
+        assert_eq!(authors.columns()[1].name(), "name");
+        assert!(!authors.columns()[1].nullable());
+        assert_eq!(authors.columns()[1].default(), Some("''"));
+        let books = snapshot.table("books").unwrap();
+        let index_names = books
+            .indexes()
+            .iter()
+            .map(|index| (index.name(), index.unique()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            index_names,
+            vec![("books_title", false), ("sqlite_autoindex_books_1", true)]
+        );
+        let constraints = books
+            .constraints()
+            .iter()
+            .map(SnapshotConstraint::definition)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            constraints,
+            vec![
+                "FOREIGN KEY (author_id) REFERENCES authors (id)",
+                "PRIMARY KEY (id)"
+            ]
+        );
+
+        let snapshot = Snapshot::from_json(&snapshot.to_json().unwrap()).unwrap();
+        assert!(migrator
+            .detect_drift(&mut conn, &snapshot)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // manual hotfix applied directly to database
+        sqlx::query("ALTER TABLE books ADD COLUMN isbn TEXT")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        sqlx::query("DROP INDEX books_title")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE hotfix (id INTEGER)")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        let drifts = migrator.detect_drift(&mut conn, &snapshot).await.unwrap();
+        assert_eq!(
+            drifts,
+            vec![
+                Drift::Unexpected {
+                    object: "column books.isbn".to_string()
+                },
+                Drift::Missing {
+                    object: "index books.books_title".to_string()
+                },
+                Drift::Unexpected {
+                    object: "table hotfix".to_string()
+                },
+            ]
+        );
+
+        migrator
+            .run(&mut conn, &Plan::revert_all().fake(true))
+            .await
+            .unwrap();
+        assert!(matches!(
+            migrator.detect_drift(&mut conn, &snapshot).await,
+            Err(Error::SnapshotError { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn schema_snapshot_without_migration_table() {
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        sqlx::query("CREATE TABLE authors (id INTEGER)")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        let migrator = Migrator::<Sqlite>::default();
+
+        let snapshot = migrator.take_snapshot(&mut conn).await.unwrap();
+        assert!(snapshot.migrations().is_empty());
+        assert_eq!(snapshot.tables().len(), 1);
+        assert!(migrator
+            .detect_drift(&mut conn, &snapshot)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(!migrator.migration_table_exists(&mut conn).await.unwrap());
+    }
+}
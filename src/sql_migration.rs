@@ -0,0 +1,435 @@
+//! Module for defining migrations using plain SQL
+//!
+//! [`SqlMigration`] is a migration whose single operation runs up and down
+//! SQL. SQL migrations can be loaded from a directory containing
+//! `NNNN_name.up.sql` and optional `NNNN_name.down.sql` files using
+//! [`load_directory`] and mixed with rust migrations in
+//! [`Info::add_migrations`](crate::migrator::Info::add_migrations).
+//!
+//! Name of migration is file name without `.up.sql` extension. Parents of
+//! migration defaults to previous file of same app when files are sorted by
+//! number. Up file can start with front matter comments to configure migration
+//! ```sql
+//! -- app: main
+//! -- parents: 0001_create_users, other_app:0003_create_books
+//! -- atomic: false
+//! -- destructible: true
+//! CREATE INDEX CONCURRENTLY users_name ON users (name);
+//! ```
+//! Parent without app uses app of migration. Migration without down file is
+//! irreversible. Number of file must be unique within app.
+#![cfg_attr(
+    feature = "postgres",
+    doc = r#"
+# Example
+```rust,no_run
+use sqlx::Postgres;
+use sqlx_migrator::migrator::{Info, Migrator};
+
+# fn run() -> Result<(), sqlx_migrator::Error> {
+let mut migrator = Migrator::<Postgres>::default();
+migrator.add_migrations(sqlx_migrator::sql_migration::load_directory("migrations", "main")?);
+# Ok(())
+# }
+```
+"#
+)]
+
+use std::path::Path;
+
+use sqlx::Database;
+use sqlx_migrator_sql_file::{build_migrations, parse_file_name, parse_front_matter, SqlFile};
+
+use crate::error::Error;
+use crate::migration::Migration;
+use crate::operation::Operation;
+use crate::script::execute_script;
+
+/// Migration which runs plain up and down SQL
+#[derive(Debug, Clone)]
+pub struct SqlMigration {
+    app: String,
+    name: String,
+    parents: Vec<(String, String)>,
+    up: String,
+    down: Option<String>,
+    atomic: bool,
+    destructible: bool,
+}
+
+impl SqlMigration {
+    /// Create new SQL migration with no parents which is atomic and not
+    /// destructible
+    #[must_use]
+    pub fn new(app: impl Into<String>, name: impl Into<String>, up: impl Into<String>) -> Self {
+        Self {
+            app: app.into(),
+            name: name.into(),
+            parents: vec![],
+            up: up.into(),
+            down: None,
+            atomic: true,
+            destructible: false,
+        }
+    }
+
+    /// Create SQL migration from SQL content. Front matter comments present in
+    /// up SQL overrides app, parents, atomic and destructible values
+    ///
+    /// # Errors
+    /// If front matter contains invalid value
+    pub fn from_sql(
+        app: impl Into<String>,
+        name: impl Into<String>,
+        up: impl Into<String>,
+        down: Option<String>,
+    ) -> Result<Self, Error> {
+        let mut migration = Self::new(app, name, up);
+        migration.down = down;
+        let front_matter = parse_front_matter(&migration.up).map_err(|message| {
+            Error::SqlMigrationError {
+                message: format!("{message} for {}", migration.name),
+            }
+        })?;
+        if let Some(app) = front_matter.app.clone() {
+            migration.app = app;
+        }
+        if let Some(parents) = front_matter.resolve_parents(&migration.app) {
+            migration.parents = parents;
+        }
+        if let Some(atomic) = front_matter.atomic {
+            migration.atomic = atomic;
+        }
+        if let Some(destructible) = front_matter.destructible {
+            migration.destructible = destructible;
+        }
+        Ok(migration)
+    }
+
+    /// Set down SQL of migration. Migration without down SQL is irreversible
+    #[must_use]
+    pub fn down(self, down: impl Into<String>) -> Self {
+        let mut migration = self;
+        migration.down = Some(down.into());
+        migration
+    }
+
+    /// Add parent using app and name of parent migration
+    #[must_use]
+    pub fn parent(self, app: impl Into<String>, name: impl Into<String>) -> Self {
+        let mut migration = self;
+        migration.parents.push((app.into(), name.into()));
+        migration
+    }
+
+    /// Set whether migration is atomic
+    #[must_use]
+    pub fn atomic(self, atomic: bool) -> Self {
+        let mut migration = self;
+        migration.atomic = atomic;
+        migration
+    }
+
+    /// Set whether up SQL of migration is destructible
+    #[must_use]
+    pub fn destructible(self, destructible: bool) -> Self {
+        let mut migration = self;
+        migration.destructible = destructible;
+        migration
+    }
+
+    /// Return up SQL
+    #[must_use]
+    pub fn up_sql(&self) -> &str {
+        &self.up
+    }
+
+    /// Return down SQL
+    #[must_use]
+    pub fn down_sql(&self) -> Option<&str> {
+        self.down.as_deref()
+    }
+
+    /// Return app and name of parents
+    #[must_use]
+    pub fn parent_names(&self) -> &[(String, String)] {
+        &self.parents
+    }
+}
+
+/// Operation which runs plain up and down SQL
+#[derive(Debug, Clone)]
+pub struct SqlOperation {
+    up: String,
+    down: Option<String>,
+    destructible: bool,
+}
+
+impl SqlOperation {
+    /// Create new SQL operation which is not destructible. Operation without
+    /// down SQL is irreversible
+    #[must_use]
+    pub fn new(up: impl Into<String>, down: Option<String>) -> Self {
+        Self {
+            up: up.into(),
+            down,
+            destructible: false,
+        }
+    }
+
+    /// Set whether up SQL of operation is destructible
+    #[must_use]
+    pub fn destructible(self, destructible: bool) -> Self {
+        let mut operation = self;
+        operation.destructible = destructible;
+        operation
+    }
+}
+
+#[async_trait::async_trait]
+impl<DB> Operation<DB> for SqlOperation
+where
+    DB: Database,
+    <DB as Database>::Connection: 'static,
+    for<'c> &'c mut <DB as Database>::Connection: sqlx::Executor<'c, Database = DB>,
+{
+    async fn up(&self, connection: &mut <DB as Database>::Connection) -> Result<(), Error> {
+        execute_script::<DB>(connection, &self.up).await
+    }
+
+    async fn down(&self, connection: &mut <DB as Database>::Connection) -> Result<(), Error> {
+        let Some(down) = &self.down else {
+            return Err(Error::IrreversibleOperation);
+        };
+        execute_script::<DB>(connection, down).await
+    }
+
+    fn is_reversible(&self) -> bool {
+        self.down.is_some()
+    }
+
+    fn sql(&self) -> Option<(String, Option<String>)> {
+        Some((self.up.clone(), self.down.clone()))
+    }
+
+    fn is_destructible(&self) -> bool {
+        self.destructible
+    }
+}
+
+impl<DB> Migration<DB> for SqlMigration
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: sqlx::Executor<'c, Database = DB>,
+{
+    fn app(&self) -> &str {
+        &self.app
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<DB>>> {
+        self.parents
+            .iter()
+            .map(|parent| Box::new(parent.clone()) as Box<dyn Migration<DB>>)
+            .collect()
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<DB>>> {
+        vec![Box::new(
+            SqlOperation::new(self.up.clone(), self.down.clone()).destructible(self.destructible),
+        )]
+    }
+
+    fn is_atomic(&self) -> bool {
+        self.atomic
+    }
+}
+
+/// Build SQL migrations from list of file name and content. Migration
+/// without explicit parents uses previous migration of same app as parent
+///
+/// # Errors
+/// If file name or front matter is invalid, down file is present without up
+/// file or same number is used by multiple migrations of app
+pub fn from_files(files: Vec<(String, String)>, app: &str) -> Result<Vec<SqlMigration>, Error> {
+    let files = files
+        .into_iter()
+        .map(|(file_name, content)| {
+            SqlFile {
+                file_name,
+                content,
+                source: (),
+            }
+        })
+        .collect();
+    let migrations =
+        build_migrations(files, app).map_err(|message| Error::SqlMigrationError { message })?;
+    Ok(migrations
+        .into_iter()
+        .map(|migration| {
+            SqlMigration {
+                app: migration.app,
+                name: migration.name,
+                parents: migration.parents,
+                up: migration.up.content,
+                down: migration.down.map(|down| down.content),
+                atomic: migration.atomic,
+                destructible: migration.destructible,
+            }
+        })
+        .collect())
+}
+
+/// Load SQL migrations from directory. Files other than `.up.sql` and
+/// `.down.sql` files are ignored. Migrations uses provided app unless app is
+/// set in front matter
+///
+/// # Errors
+/// If directory cannot be read or files are not valid SQL migration
+pub fn load_directory<DB>(
+    path: impl AsRef<Path>,
+    app: &str,
+) -> Result<Vec<Box<dyn Migration<DB>>>, Error>
+where
+    SqlMigration: Migration<DB>,
+{
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(path.as_ref()).map_err(|error| {
+        Error::SqlMigrationError {
+            message: format!("failed to read {}: {error}", path.as_ref().display()),
+        }
+    })? {
+        let entry = entry.map_err(|error| {
+            Error::SqlMigrationError {
+                message: error.to_string(),
+            }
+        })?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if parse_file_name(&file_name).is_none() || !entry.path().is_file() {
+            continue;
+        }
+        let content = std::fs::read_to_string(entry.path()).map_err(|error| {
+            Error::SqlMigrationError {
+                message: format!("failed to read {file_name}: {error}"),
+            }
+        })?;
+        files.push((file_name, content));
+    }
+    Ok(from_files(files, app)?
+        .into_iter()
+        .map(|migration| Box::new(migration) as Box<dyn Migration<DB>>)
+        .collect())
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use sqlx::Sqlite;
+
+    use super::from_files;
+    #[cfg(feature = "macros")]
+    use crate::embed_migrations;
+    use crate::error::Error;
+    use crate::migrator::{Info, Migrate, Migrator, Plan};
+    use crate::test_utils::sqlite_pool;
+
+    #[tokio::test]
+    async fn sql_migration_files() {
+        let files = vec![
+            (
+                "0002_add_books.up.sql".to_string(),
+                "CREATE TABLE books (id INTEGER PRIMARY KEY)".to_string(),
+            ),
+            (
+                "0001_add_users.up.sql".to_string(),
+                "CREATE TABLE users (id INTEGER PRIMARY KEY)".to_string(),
+            ),
+            (
+                "0001_add_users.down.sql".to_string(),
+                "DROP TABLE users".to_string(),
+            ),
+            (
+                "0001_add_authors.up.sql".to_string(),
+                "-- app: library\n-- parents: main:0001_add_users\n-- atomic: false\nCREATE TABLE \
+                 authors (id INTEGER PRIMARY KEY)"
+                    .to_string(),
+            ),
+            ("README.md".to_string(), String::new()),
+        ];
+        let sql_migrations = from_files(files, "main").unwrap();
+        let mut migrator = Migrator::<Sqlite>::default();
+        for sql_migration in sql_migrations {
+            migrator.add_migration(Box::new(sql_migration));
+        }
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        let plan = migrator
+            .generate_migration_plan(&mut conn, Some(&Plan::apply_all()))
+            .await
+            .unwrap();
+        let names = plan
+            .iter()
+            .map(|migration| format!("{}:{}", migration.app(), migration.name()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "main:0001_add_users",
+                "main:0002_add_books",
+                "library:0001_add_authors"
+            ]
+        );
+        assert!(!plan[2].is_atomic());
+        migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+        let revert = migrator.run(&mut conn, &Plan::revert_all()).await;
+        assert!(matches!(revert, Err(Error::PlanError { .. })));
+        assert!(from_files(vec![("1_.up.sql".to_string(), String::new())], "main").is_err());
+        // same number cannot be used by two migrations of same app
+        let duplicate_number = from_files(
+            vec![
+                ("0001_a.up.sql".to_string(), String::new()),
+                ("0001_b.up.sql".to_string(), String::new()),
+            ],
+            "main",
+        );
+        assert!(matches!(
+            duplicate_number,
+            Err(Error::SqlMigrationError { message }) if message.contains("0001_a and 0001_b")
+        ));
+        assert!(from_files(
+            vec![
+                ("0001_a.up.sql".to_string(), String::new()),
+                ("0001_b.up.sql".to_string(), "-- app: other".to_string()),
+            ],
+            "main"
+        )
+        .is_ok());
+    }
+
+    #[cfg(feature = "macros")]
+    #[tokio::test]
+    async fn embed_migrations() {
+        let migrations = embed_migrations!("tests/fixtures/embed", Sqlite, app = "main");
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(migrations);
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        let plan = migrator
+            .generate_migration_plan(&mut conn, Some(&Plan::apply_all()))
+            .await
+            .unwrap();
+        let names = plan
+            .iter()
+            .map(|migration| (migration.name(), migration.is_atomic()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![("0001_create_users", false), ("0002_create_books", true)]
+        );
+        migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+        let result = migrator.run(&mut conn, &Plan::revert_all()).await;
+        assert!(matches!(result, Err(Error::PlanError { .. })));
+    }
+}
@@ -0,0 +1,399 @@
+//! Module for squashing migrations of app into single migration
+//!
+//! [`SquashedMigration`] combines SQL of consecutive migrations of app into
+//! single migration which [`replaces`](crate::migration::Migration::replaces)
+//! squashed migrations. Only operations which return SQL from
+//! [`Operation::sql`](crate::operation::Operation::sql) can be squashed,
+//! squashing migration containing rust only operation fails listing such
+//! migrations. [Schema operations](crate::schema) of `sqlx::Any` only provide
+//! SQL when it is same for every enabled database. Squashed migration inherits
+//! parents and run before migrations of squashed migrations which are not part
+//! of squash and can be written as rust source file using
+//! [`SquashedMigration::to_rust_source`].
+//!
+//! Squashed migrations need to be kept in migration list until all databases
+//! have applied squashed migration.
+#![cfg_attr(
+    feature = "sqlite",
+    doc = r#"
+# Example
+```rust,no_run
+use sqlx::Sqlite;
+use sqlx_migrator::migrator::{Migrate, Migrator};
+use sqlx_migrator::squash::SquashedMigration;
+
+# async fn run(connection: &mut sqlx::SqliteConnection) -> Result<(), sqlx_migrator::Error> {
+let migrator = Migrator::<Sqlite>::default();
+let plan = migrator.generate_migration_plan(connection, None).await?;
+let squashed =
+    SquashedMigration::new(&plan, "main", "m0001_simple", "m0003_use_macros", "m0006_squash")?;
+std::fs::write(
+    "src/migrations/m0006_squash.rs",
+    squashed.to_rust_source("sqlx::Sqlite", "M0006Migration"),
+)?;
+# Ok(())
+# }
+```
+"#
+)]
+
+use sqlx::Database;
+
+use crate::error::Error;
+use crate::migration::Migration;
+
+/// Migration created by squashing consecutive migrations of app
+#[derive(Debug, Clone)]
+pub struct SquashedMigration {
+    app: String,
+    name: String,
+    parents: Vec<(String, String)>,
+    run_before: Vec<(String, String)>,
+    replaces: Vec<(String, String)>,
+    up: String,
+    down: Option<String>,
+    atomic: bool,
+    destructible: bool,
+}
+
+impl SquashedMigration {
+    /// Squash migrations of app from `from` migration to `to` migration
+    /// including both. Plan should be list of all migrations in order of
+    /// apply generated using
+    /// [`Migrate::generate_migration_plan`](crate::migrator::Migrate::generate_migration_plan)
+    /// without plan. Migrations of other apps present between range are not
+    /// squashed.
+    ///
+    /// # Errors
+    /// If range is not present in plan or any operation of range doesn't
+    /// provide SQL
+    #[expect(clippy::borrowed_box)]
+    pub fn new<DB>(
+        plan: &[&Box<dyn Migration<DB>>],
+        app: &str,
+        from: &str,
+        to: &str,
+        name: &str,
+    ) -> Result<Self, Error>
+    where
+        DB: Database,
+    {
+        let position = |migration_name: &str| {
+            plan.iter()
+                .position(|migration| migration.app() == app && migration.name() == migration_name)
+                .ok_or(Error::SquashError {
+                    message: format!("migration {app}:{migration_name} doesn't exists"),
+                })
+        };
+        let (start, end) = (position(from)?, position(to)?);
+        if start > end {
+            return Err(Error::SquashError {
+                message: format!("migration {app}:{from} is applied after {app}:{to}"),
+            });
+        }
+        let range = plan[start..=end]
+            .iter()
+            .filter(|migration| migration.app() == app)
+            .collect::<Vec<_>>();
+
+        let rust_only = range
+            .iter()
+            .filter(|migration| {
+                migration
+                    .operations()
+                    .iter()
+                    .any(|operation| operation.sql().is_none())
+            })
+            .map(|migration| format!("{}:{}", migration.app(), migration.name()))
+            .collect::<Vec<_>>();
+        if !rust_only.is_empty() {
+            return Err(Error::SquashError {
+                message: format!(
+                    "migrations {} contains operations which doesn't provide SQL",
+                    rust_only.join(", ")
+                ),
+            });
+        }
+
+        let in_range = |migration: &Box<dyn Migration<DB>>| {
+            range.iter().any(|&&squashed| squashed == migration)
+        };
+        let mut squashed = Self {
+            app: app.to_string(),
+            name: name.to_string(),
+            parents: vec![],
+            run_before: vec![],
+            replaces: vec![],
+            up: String::new(),
+            down: Some(String::new()),
+            atomic: true,
+            destructible: false,
+        };
+        let mut up_scripts = vec![];
+        let mut down_scripts = vec![];
+        for migration in &range {
+            let key = (migration.app().to_string(), migration.name().to_string());
+            for parent in migration.parents() {
+                let parent_key = (parent.app().to_string(), parent.name().to_string());
+                if !in_range(&parent) && !squashed.parents.contains(&parent_key) {
+                    squashed.parents.push(parent_key);
+                }
+            }
+            for run_before in migration.run_before() {
+                let run_before_key = (run_before.app().to_string(), run_before.name().to_string());
+                if !in_range(&run_before) && !squashed.run_before.contains(&run_before_key) {
+                    squashed.run_before.push(run_before_key);
+                }
+            }
+            squashed.atomic &= migration.is_atomic();
+            let mut up_sql = vec![];
+            let mut down_sql = vec![];
+            for operation in migration.operations() {
+                squashed.destructible |= operation.is_destructible();
+                if let Some((up, down)) = operation.sql() {
+                    up_sql.push(terminate_statement(&up));
+                    match down {
+                        Some(down) => down_sql.push(terminate_statement(&down)),
+                        None => squashed.down = None,
+                    }
+                }
+            }
+            down_sql.reverse();
+            up_scripts.push(format!("-- {}:{}\n{}", key.0, key.1, up_sql.join("\n")));
+            down_scripts.push(format!("-- {}:{}\n{}", key.0, key.1, down_sql.join("\n")));
+            squashed.replaces.push(key);
+        }
+        down_scripts.reverse();
+        squashed.up = up_scripts.join("\n\n");
+        squashed.down = squashed.down.map(|_| down_scripts.join("\n\n"));
+        Ok(squashed)
+    }
+
+    /// Return app of squashed migration
+    #[must_use]
+    pub fn app(&self) -> &str {
+        &self.app
+    }
+
+    /// Return name of squashed migration
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return parents of squashed migrations which are not squashed
+    #[must_use]
+    pub fn parents(&self) -> &[(String, String)] {
+        &self.parents
+    }
+
+    /// Return run before migrations of squashed migrations which are not
+    /// squashed
+    #[must_use]
+    pub fn run_before(&self) -> &[(String, String)] {
+        &self.run_before
+    }
+
+    /// Return app and name of squashed migrations
+    #[must_use]
+    pub fn replaces(&self) -> &[(String, String)] {
+        &self.replaces
+    }
+
+    /// Return combined up SQL
+    #[must_use]
+    pub fn up_sql(&self) -> &str {
+        &self.up
+    }
+
+    /// Return combined down SQL. Returns `None` if any squashed operation is
+    /// irreversible
+    #[must_use]
+    pub fn down_sql(&self) -> Option<&str> {
+        self.down.as_deref()
+    }
+
+    /// Return whether all squashed migrations are atomic
+    #[must_use]
+    pub fn is_atomic(&self) -> bool {
+        self.atomic
+    }
+
+    /// Return whether any squashed operation is destructible
+    #[must_use]
+    pub fn is_destructible(&self) -> bool {
+        self.destructible
+    }
+
+    /// Return rust source of squashed migration which uses
+    /// [`migration`](crate::migration!) macro. Database is type path of
+    /// database such as `sqlx::Postgres` and struct name is name of struct
+    /// for which migration is implemented
+    #[must_use]
+    pub fn to_rust_source(&self, database: &str, struct_name: &str) -> String {
+        let migration_list = |migrations: &[(String, String)]| {
+            migrations
+                .iter()
+                .map(|(app, name)| format!("({app:?}, {name:?})"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let down = self.down.as_ref().map_or_else(
+            || "None".to_string(),
+            |down| format!("Some({}.to_string())", raw_string(down)),
+        );
+        let mut arguments = vec![
+            format!("    {database}"),
+            format!("    {struct_name}"),
+            format!("    app = {:?}", self.app),
+            format!("    name = {:?}", self.name),
+            format!(
+                "    parents = sqlx_migrator::vec_box![{}]",
+                migration_list(&self.parents)
+            ),
+            format!(
+                "    operations = sqlx_migrator::vec_box![\n        \
+                 sqlx_migrator::sql_migration::SqlOperation::new(\n            {},\n            \
+                 {down},\n        )\n        .destructible({})\n    ]",
+                raw_string(&self.up),
+                self.destructible
+            ),
+            format!(
+                "    replaces = sqlx_migrator::vec_box![{}]",
+                migration_list(&self.replaces)
+            ),
+        ];
+        if !self.run_before.is_empty() {
+            arguments.push(format!(
+                "    run_before = sqlx_migrator::vec_box![{}]",
+                migration_list(&self.run_before)
+            ));
+        }
+        if !self.atomic {
+            arguments.push("    atomic = false".to_string());
+        }
+        format!(
+            "pub(crate) struct {struct_name};\n\nsqlx_migrator::migration!(\n{}\n);\n",
+            arguments.join(",\n")
+        )
+    }
+}
+
+/// Trim SQL and end it with semicolon so SQL of multiple operations can be
+/// joined
+fn terminate_statement(sql: &str) -> String {
+    let sql = sql.trim();
+    if sql.is_empty() || sql.ends_with(';') {
+        sql.to_string()
+    } else {
+        format!("{sql};")
+    }
+}
+
+/// Return rust raw string literal for value using enough `#` so value can
+/// contain quote
+fn raw_string(value: &str) -> String {
+    let mut hashes = "#".to_string();
+    while value.contains(&format!("\"{hashes}")) {
+        hashes.push('#');
+    }
+    format!("r{hashes}\"{value}\"{hashes}")
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use sqlx::Sqlite;
+
+    use super::SquashedMigration;
+    use crate::backfill::Backfill;
+    use crate::error::Error;
+    use crate::migration::Migration;
+    use crate::schema::{Column, CreateTable};
+    use crate::script::execute_script;
+    use crate::test_utils::sqlite_pool;
+    use crate::vec_box;
+
+    #[tokio::test]
+    async fn squash_migrations() {
+        struct A;
+        crate::sqlite_migration!(
+            A,
+            app = "test",
+            name = "a",
+            parents = vec_box![("other", "base")],
+            operations = vec_box![CreateTable::new("books").column(Column::new("id", "INTEGER"))]
+        );
+        struct B;
+        crate::sqlite_migration!(
+            B,
+            app = "test",
+            name = "b",
+            parents = vec_box![A],
+            operations = vec_box![
+                (
+                    "INSERT INTO books (id) VALUES (1)",
+                    "DELETE FROM books WHERE id = 1"
+                ),
+                (
+                    "INSERT INTO books (id) VALUES (2)",
+                    "DELETE FROM books WHERE id = 2"
+                )
+            ]
+        );
+        struct C;
+        crate::sqlite_migration!(
+            C,
+            app = "test",
+            name = "c",
+            parents = vec_box![B],
+            operations = vec_box![Backfill::new("backfill", "books", "id", "SELECT 1")],
+            atomic = false
+        );
+        let migrations: Vec<Box<dyn Migration<Sqlite>>> = vec_box![A, B, C];
+        let plan = migrations.iter().collect::<Vec<_>>();
+
+        let squashed = SquashedMigration::new(&plan, "test", "a", "b", "squash").unwrap();
+        assert_eq!(
+            squashed.parents(),
+            [("other".to_string(), "base".to_string())]
+        );
+        assert_eq!(
+            squashed.replaces(),
+            [
+                ("test".to_string(), "a".to_string()),
+                ("test".to_string(), "b".to_string())
+            ]
+        );
+        assert!(squashed.is_atomic());
+        let source = squashed.to_rust_source("sqlx::Sqlite", "Squash");
+        assert!(source
+            .contains("replaces = sqlx_migrator::vec_box![(\"test\", \"a\"), (\"test\", \"b\")]"));
+
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        execute_script::<Sqlite>(&mut conn, squashed.up_sql())
+            .await
+            .unwrap();
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM books")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+        execute_script::<Sqlite>(&mut conn, squashed.down_sql().unwrap())
+            .await
+            .unwrap();
+        assert!(sqlx::query("SELECT * FROM books")
+            .fetch_all(&mut *conn)
+            .await
+            .is_err());
+
+        assert!(matches!(
+            SquashedMigration::new(&plan, "test", "b", "c", "squash"),
+            Err(Error::SquashError { message }) if message.contains("test:c")
+        ));
+        assert!(matches!(
+            SquashedMigration::new(&plan, "test", "b", "a", "squash"),
+            Err(Error::SquashError { .. })
+        ));
+    }
+}
@@ -0,0 +1,634 @@
+//! Module for applying same migration plan to multiple tenants
+//!
+//! A tenant is a schema (for postgres) or a database (for mysql) which
+//! contains an identical set of migrations. [`TenantRunner`] switches
+//! connection to each tenant using
+//! [`DatabaseOperation::switch_schema`](crate::migrator::DatabaseOperation::switch_schema) so
+//! every tenant uses its own migrator table and its own lock, and runs
+//! provided [`Plan`] inside it.
+#![cfg_attr(
+    feature = "postgres",
+    doc = r#"
+# Example
+```rust,no_run
+use sqlx::Postgres;
+use sqlx_migrator::migrator::{Migrator, Plan};
+use sqlx_migrator::tenant::{FailurePolicy, TenantRunner, Tenants};
+
+# async fn run(pool: sqlx::Pool<Postgres>) -> Result<(), sqlx_migrator::Error> {
+let migrator = Migrator::<Postgres>::default();
+let reports = TenantRunner::new(&migrator)
+    .concurrency(4)
+    .failure_policy(FailurePolicy::Continue)
+    .run(
+        &pool,
+        &Tenants::Query("SELECT schema_name FROM tenants".to_string()),
+        &Plan::apply_all(),
+    )
+    .await?;
+for report in reports {
+    println!("{}: {:?}", report.tenant(), report.outcome());
+}
+# Ok(())
+# }
+```
+"#
+)]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use futures_util::StreamExt;
+use sqlx::{Database, Pool};
+
+use crate::error::Error;
+use crate::migrator::{run_migrations, Migrate, Plan};
+
+/// Source of tenants for which plan is run
+#[derive(Debug, Clone)]
+pub enum Tenants {
+    /// List of tenant schema name
+    Schemas(Vec<String>),
+    /// Query which returns single text column containing tenant schema name
+    Query(String),
+}
+
+/// Policy which determines what happens to remaining tenants when running plan
+/// fails for one tenant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailurePolicy {
+    /// Stop running plan for tenants which are not yet started. Tenants which
+    /// are already running are completed
+    #[default]
+    Stop,
+    /// Continue running plan for all remaining tenants
+    Continue,
+}
+
+/// Outcome of running plan for a tenant
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TenantOutcome {
+    /// Plan was successfully run for tenant
+    Succeeded,
+    /// Plan failed for tenant with error
+    Failed(Error),
+    /// Plan was not run for tenant since previous tenant failed
+    Skipped,
+}
+
+/// Report of running plan for a single tenant
+#[derive(Debug)]
+pub struct TenantReport {
+    tenant: String,
+    migrations: Vec<(String, String)>,
+    outcome: TenantOutcome,
+}
+
+impl TenantReport {
+    /// Return name of tenant
+    #[must_use]
+    pub fn tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    /// Return list of app and name of migrations which were part of plan for
+    /// tenant
+    #[must_use]
+    pub fn migrations(&self) -> &[(String, String)] {
+        &self.migrations
+    }
+
+    /// Return outcome of running plan for tenant
+    #[must_use]
+    pub fn outcome(&self) -> &TenantOutcome {
+        &self.outcome
+    }
+
+    /// Return true if plan failed for tenant
+    #[must_use]
+    pub fn is_failed(&self) -> bool {
+        matches!(self.outcome, TenantOutcome::Failed(_))
+    }
+}
+
+/// Runner which runs same plan for multiple tenants
+pub struct TenantRunner<'migrator, DB>
+where
+    DB: Database,
+{
+    migrator: &'migrator dyn Migrate<DB>,
+    concurrency: usize,
+    failure_policy: FailurePolicy,
+}
+
+impl<'migrator, DB> TenantRunner<'migrator, DB>
+where
+    DB: Database,
+{
+    /// Create new tenant runner for migrator. By default tenants are run one
+    /// at a time and runner stops on first failure
+    #[must_use]
+    pub fn new(migrator: &'migrator dyn Migrate<DB>) -> Self {
+        Self {
+            migrator,
+            concurrency: 1,
+            failure_policy: FailurePolicy::default(),
+        }
+    }
+
+    /// Set maximum number of tenants for which plan is run concurrently. Value
+    /// of 0 is treated as 1
+    #[must_use]
+    pub fn concurrency(self, concurrency: usize) -> Self {
+        let mut runner = self;
+        runner.concurrency = concurrency.max(1);
+        runner
+    }
+
+    /// Set failure policy of runner
+    #[must_use]
+    pub fn failure_policy(self, failure_policy: FailurePolicy) -> Self {
+        let mut runner = self;
+        runner.failure_policy = failure_policy;
+        runner
+    }
+
+    /// Resolve tenants to list of tenant schema name
+    ///
+    /// # Errors
+    /// If tenants query fails
+    pub async fn resolve_tenants(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        tenants: &Tenants,
+    ) -> Result<Vec<String>, Error> {
+        match tenants {
+            Tenants::Schemas(schemas) => Ok(schemas.clone()),
+            Tenants::Query(query) => self.migrator.fetch_schemas(connection, query).await,
+        }
+    }
+
+    /// Return app and name of destructible migrations which will be applied
+    /// by plan for any of tenants. Connection is switched to each tenant one
+    /// after another and its schema is restored afterwards
+    ///
+    /// # Errors
+    /// If tenants cannot be resolved, plan cannot be generated for tenant or
+    /// schema of connection cannot be restored
+    pub async fn destructible_migrations(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        tenants: &Tenants,
+        plan: &Plan,
+    ) -> Result<Vec<(String, String)>, Error> {
+        let schemas = self.resolve_tenants(connection, tenants).await?;
+        let current_schema = self.migrator.fetch_current_schema(connection).await?;
+        let result = async {
+            let mut destructible_migrations = vec![];
+            for schema in schemas {
+                self.migrator.switch_schema(connection, &schema).await?;
+                for migration in self
+                    .migrator
+                    .generate_migration_plan(connection, Some(plan))
+                    .await?
+                {
+                    let key = (migration.app().to_string(), migration.name().to_string());
+                    if migration
+                        .operations()
+                        .iter()
+                        .any(|operation| operation.is_destructible())
+                        && !destructible_migrations.contains(&key)
+                    {
+                        destructible_migrations.push(key);
+                    }
+                }
+            }
+            Ok(destructible_migrations)
+        }
+        .await;
+        self.migrator
+            .restore_schema(connection, current_schema.as_deref())
+            .await?;
+        result
+    }
+
+    /// Run plan for all tenants using connection from pool. Each tenant uses
+    /// its own connection which is closed after completion instead of being
+    /// returned to pool, since its schema was switched.
+    ///
+    /// Returns report for each tenant in same order as tenants
+    ///
+    /// # Errors
+    /// If tenants cannot be resolved
+    pub async fn run(
+        &self,
+        pool: &Pool<DB>,
+        tenants: &Tenants,
+        plan: &Plan,
+    ) -> Result<Vec<TenantReport>, Error> {
+        let schemas = {
+            let mut connection = pool.acquire().await?;
+            self.resolve_tenants(&mut connection, tenants).await?
+        };
+        let stop = AtomicBool::new(false);
+        let stop = &stop;
+        let reports = futures_util::stream::iter(schemas)
+            .map(|schema| {
+                async move {
+                    if stop.load(Ordering::SeqCst) {
+                        return TenantReport {
+                            tenant: schema,
+                            migrations: vec![],
+                            outcome: TenantOutcome::Skipped,
+                        };
+                    }
+                    let report = match pool.acquire().await {
+                        Ok(mut connection) => {
+                            connection.close_on_drop();
+                            self.run_tenant(&mut connection, schema, plan).await
+                        }
+                        Err(error) => {
+                            TenantReport {
+                                tenant: schema,
+                                migrations: vec![],
+                                outcome: TenantOutcome::Failed(error.into()),
+                            }
+                        }
+                    };
+                    if report.is_failed() && self.failure_policy == FailurePolicy::Stop {
+                        stop.store(true, Ordering::SeqCst);
+                    }
+                    report
+                }
+            })
+            .buffered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+        Ok(reports)
+    }
+
+    /// Run plan for all tenants one after another using single connection.
+    /// After completion schema of connection is restored to schema used
+    /// before running plan.
+    ///
+    /// Returns report for each tenant in same order as tenants
+    ///
+    /// # Errors
+    /// If tenants cannot be resolved or schema of connection cannot be restored
+    pub async fn run_on_connection(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        tenants: &Tenants,
+        plan: &Plan,
+    ) -> Result<Vec<TenantReport>, Error> {
+        let schemas = self.resolve_tenants(connection, tenants).await?;
+        let current_schema = self.migrator.fetch_current_schema(connection).await?;
+        let mut reports = Vec::with_capacity(schemas.len());
+        let mut stopped = false;
+        for schema in schemas {
+            if stopped {
+                reports.push(TenantReport {
+                    tenant: schema,
+                    migrations: vec![],
+                    outcome: TenantOutcome::Skipped,
+                });
+                continue;
+            }
+            let report = self.run_tenant(connection, schema, plan).await;
+            if report.is_failed() && self.failure_policy == FailurePolicy::Stop {
+                stopped = true;
+            }
+            reports.push(report);
+        }
+        self.migrator
+            .restore_schema(connection, current_schema.as_deref())
+            .await?;
+        Ok(reports)
+    }
+
+    async fn run_tenant(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        tenant: String,
+        plan: &Plan,
+    ) -> TenantReport {
+        tracing::debug!("running plan for tenant {tenant}");
+        let mut migrations = vec![];
+        let result = async {
+            self.migrator.switch_schema(connection, &tenant).await?;
+            // report migrations of plan which is generated and run while holding lock
+            self.migrator.lock(connection).await?;
+            let plan_migrations = self
+                .migrator
+                .generate_migration_plan(connection, Some(plan))
+                .await?;
+            migrations = plan_migrations
+                .iter()
+                .map(|migration| (migration.app().to_string(), migration.name().to_string()))
+                .collect();
+            run_migrations(self.migrator, connection, plan, &plan_migrations).await?;
+            self.migrator.unlock(connection).await
+        }
+        .await;
+        let outcome = match result {
+            Ok(()) => TenantOutcome::Succeeded,
+            Err(error) => {
+                tracing::debug!("running plan for tenant {tenant} failed: {error}");
+                TenantOutcome::Failed(error)
+            }
+        };
+        TenantReport {
+            tenant,
+            migrations,
+            outcome,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use sqlx::{Database, Sqlite};
+
+    use super::{FailurePolicy, TenantOutcome, TenantReport, TenantRunner, Tenants};
+    use crate::error::Error;
+    use crate::migration::{AppliedMigrationSqlRow, Migration};
+    use crate::migrator::{DatabaseOperation, Info, Migrate, Plan};
+    use crate::test_utils::{migration, sqlite_pool};
+    use crate::vec_box;
+
+    /// Migrator which keeps applied migrations of each tenant in memory. Tenant
+    /// of connection is stored in temporary table so each pooled connection
+    /// keeps its own tenant
+    #[derive(Default)]
+    struct TenantMigrator {
+        migrations: Vec<Box<dyn Migration<Sqlite>>>,
+        applied: Mutex<HashMap<String, Vec<AppliedMigrationSqlRow>>>,
+        failing_tenant: Option<String>,
+    }
+
+    impl TenantMigrator {
+        fn applied_count(&self, tenant: &str) -> usize {
+            self.applied.lock().unwrap().get(tenant).map_or(0, Vec::len)
+        }
+    }
+
+    async fn current_tenant(connection: &mut sqlx::SqliteConnection) -> Result<String, Error> {
+        Ok(sqlx::query_scalar("SELECT name FROM current_tenant")
+            .fetch_one(connection)
+            .await?)
+    }
+
+    impl Info<Sqlite> for TenantMigrator {
+        fn migrations(&self) -> &Vec<Box<dyn Migration<Sqlite>>> {
+            &self.migrations
+        }
+
+        fn migrations_mut(&mut self) -> &mut Vec<Box<dyn Migration<Sqlite>>> {
+            &mut self.migrations
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl DatabaseOperation<Sqlite> for TenantMigrator {
+        async fn ensure_migration_table_exists(
+            &self,
+            _connection: &mut <Sqlite as Database>::Connection,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn migration_table_exists(
+            &self,
+            _connection: &mut <Sqlite as Database>::Connection,
+        ) -> Result<bool, Error> {
+            Ok(true)
+        }
+
+        async fn drop_migration_table_if_exists(
+            &self,
+            _connection: &mut <Sqlite as Database>::Connection,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn add_migration_to_db_table(
+            &self,
+            connection: &mut <Sqlite as Database>::Connection,
+            migration: &Box<dyn Migration<Sqlite>>,
+        ) -> Result<(), Error> {
+            let tenant = current_tenant(connection).await?;
+            let mut applied = self.applied.lock().unwrap();
+            let rows = applied.entry(tenant).or_default();
+            let id = i32::try_from(rows.len()).unwrap() + 1;
+            rows.push(AppliedMigrationSqlRow::new(
+                id,
+                migration.app(),
+                migration.name(),
+            ));
+            Ok(())
+        }
+
+        async fn delete_migration_from_db_table(
+            &self,
+            connection: &mut <Sqlite as Database>::Connection,
+            migration: &Box<dyn Migration<Sqlite>>,
+        ) -> Result<(), Error> {
+            let tenant = current_tenant(connection).await?;
+            if let Some(rows) = self.applied.lock().unwrap().get_mut(&tenant) {
+                rows.retain(|row| row != migration);
+            }
+            Ok(())
+        }
+
+        async fn fetch_applied_migration_from_db(
+            &self,
+            connection: &mut <Sqlite as Database>::Connection,
+        ) -> Result<Vec<AppliedMigrationSqlRow>, Error> {
+            let tenant = current_tenant(connection).await?;
+            Ok(self
+                .applied
+                .lock()
+                .unwrap()
+                .get(&tenant)
+                .cloned()
+                .unwrap_or_default())
+        }
+
+        async fn lock(
+            &self,
+            _connection: &mut <Sqlite as Database>::Connection,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn unlock(
+            &self,
+            _connection: &mut <Sqlite as Database>::Connection,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn switch_schema(
+            &self,
+            connection: &mut <Sqlite as Database>::Connection,
+            schema: &str,
+        ) -> Result<(), Error> {
+            if self.failing_tenant.as_deref() == Some(schema) {
+                return Err(Error::PlanError {
+                    message: format!("tenant {schema} is not reachable"),
+                });
+            }
+            sqlx::query("CREATE TEMP TABLE IF NOT EXISTS current_tenant (name TEXT)")
+                .execute(&mut *connection)
+                .await?;
+            sqlx::query("DELETE FROM current_tenant")
+                .execute(&mut *connection)
+                .await?;
+            sqlx::query("INSERT INTO current_tenant (name) VALUES ($1)")
+                .bind(schema)
+                .execute(&mut *connection)
+                .await?;
+            Ok(())
+        }
+
+        async fn fetch_schemas(
+            &self,
+            connection: &mut <Sqlite as Database>::Connection,
+            query: &str,
+        ) -> Result<Vec<String>, Error> {
+            Ok(sqlx::query_scalar(query).fetch_all(connection).await?)
+        }
+
+        async fn fetch_current_schema(
+            &self,
+            connection: &mut <Sqlite as Database>::Connection,
+        ) -> Result<Option<String>, Error> {
+            sqlx::query("CREATE TEMP TABLE IF NOT EXISTS current_tenant (name TEXT)")
+                .execute(&mut *connection)
+                .await?;
+            Ok(sqlx::query_scalar("SELECT name FROM current_tenant")
+                .fetch_optional(connection)
+                .await?)
+        }
+
+        async fn restore_schema(
+            &self,
+            connection: &mut <Sqlite as Database>::Connection,
+            schema: Option<&str>,
+        ) -> Result<(), Error> {
+            if let Some(schema) = schema {
+                return self.switch_schema(connection, schema).await;
+            }
+            sqlx::query("DELETE FROM current_tenant")
+                .execute(connection)
+                .await?;
+            Ok(())
+        }
+    }
+
+    impl Migrate<Sqlite> for TenantMigrator {}
+
+    #[tokio::test]
+    async fn tenant_runner_failure_policy() {
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        struct B;
+        migration!(B, "b", vec_box!(A), vec_box!(), vec_box!());
+        let mut migrator = TenantMigrator {
+            failing_tenant: Some("second".to_string()),
+            ..TenantMigrator::default()
+        };
+        migrator.add_migrations(vec_box!(A, B));
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+        let tenants = Tenants::Schemas(vec![
+            "first".to_string(),
+            "second".to_string(),
+            "third".to_string(),
+        ]);
+        migrator.switch_schema(&mut conn, "caller").await.unwrap();
+
+        let reports = TenantRunner::new(&migrator)
+            .run_on_connection(&mut conn, &tenants, &Plan::apply_all())
+            .await
+            .unwrap();
+        assert_eq!(current_tenant(&mut conn).await.unwrap(), "caller");
+        assert_eq!(
+            reports.iter().map(TenantReport::tenant).collect::<Vec<_>>(),
+            vec!["first", "second", "third"]
+        );
+        assert!(matches!(reports[0].outcome(), TenantOutcome::Succeeded));
+        assert_eq!(
+            reports[0].migrations(),
+            [
+                ("test".to_string(), "a".to_string()),
+                ("test".to_string(), "b".to_string())
+            ]
+        );
+        assert!(reports[1].is_failed());
+        assert!(matches!(reports[2].outcome(), TenantOutcome::Skipped));
+        assert_eq!(migrator.applied_count("first"), 2);
+        assert_eq!(migrator.applied_count("third"), 0);
+
+        let reports = TenantRunner::new(&migrator)
+            .failure_policy(FailurePolicy::Continue)
+            .run_on_connection(&mut conn, &tenants, &Plan::apply_all())
+            .await
+            .unwrap();
+        assert!(matches!(reports[0].outcome(), TenantOutcome::Succeeded));
+        assert!(reports[0].migrations().is_empty());
+        assert!(reports[1].is_failed());
+        assert!(matches!(reports[2].outcome(), TenantOutcome::Succeeded));
+        assert_eq!(migrator.applied_count("third"), 2);
+        assert_eq!(current_tenant(&mut conn).await.unwrap(), "caller");
+
+        // schema is restored even when switching to tenant fails
+        assert!(TenantRunner::new(&migrator)
+            .destructible_migrations(&mut conn, &tenants, &Plan::apply_all())
+            .await
+            .is_err());
+        assert_eq!(current_tenant(&mut conn).await.unwrap(), "caller");
+    }
+
+    #[tokio::test]
+    async fn tenant_runner_concurrency() {
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        struct B;
+        migration!(B, "b", vec_box!(A), vec_box!(), vec_box!());
+        let mut migrator = TenantMigrator::default();
+        migrator.add_migrations(vec_box!(A, B));
+        let sqlite = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(3)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        let reports = TenantRunner::new(&migrator)
+            .concurrency(3)
+            .run(
+                &sqlite,
+                &Tenants::Query(
+                    "SELECT 'first' UNION ALL SELECT 'second' UNION ALL SELECT 'third' UNION ALL \
+                     SELECT 'fourth'"
+                        .to_string(),
+                ),
+                &Plan::apply_all(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            reports.iter().map(TenantReport::tenant).collect::<Vec<_>>(),
+            vec!["first", "second", "third", "fourth"]
+        );
+        assert!(reports
+            .iter()
+            .all(|report| matches!(report.outcome(), TenantOutcome::Succeeded)));
+        for tenant in ["first", "second", "third", "fourth"] {
+            assert_eq!(migrator.applied_count(tenant), 2);
+        }
+    }
+}
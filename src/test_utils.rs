@@ -0,0 +1,46 @@
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::SqlitePool;
+
+/// Create migration with `test` app which doesn't contain any operation
+macro_rules! migration {
+    ($op:ty, $name:literal, $parents:expr, $replaces:expr, $run_before:expr) => {
+        #[async_trait::async_trait]
+        impl crate::migration::Migration<sqlx::Sqlite> for $op {
+            fn app(&self) -> &str {
+                "test"
+            }
+
+            fn name(&self) -> &str {
+                $name
+            }
+
+            fn parents(&self) -> Vec<Box<dyn crate::migration::Migration<sqlx::Sqlite>>> {
+                $parents
+            }
+
+            fn operations(&self) -> Vec<Box<dyn crate::operation::Operation<sqlx::Sqlite>>> {
+                vec![]
+            }
+
+            fn replaces(&self) -> Vec<Box<dyn crate::migration::Migration<sqlx::Sqlite>>> {
+                $replaces
+            }
+
+            fn run_before(&self) -> Vec<Box<dyn crate::migration::Migration<sqlx::Sqlite>>> {
+                $run_before
+            }
+        }
+    };
+}
+
+pub(crate) use migration;
+
+/// Create in memory sqlite pool. Pool only holds single connection since
+/// every new connection of in memory sqlite opens new empty database
+pub(crate) async fn sqlite_pool() -> SqlitePool {
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap()
+}
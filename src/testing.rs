@@ -0,0 +1,581 @@
+//! Module containing helpers for testing migrations
+//!
+//! [`check_round_trip`] verifies that `down` of each migration undoes its
+//! `up`. Migrations are applied one at a time to an empty database and for
+//! each migration schema snapshot taken after reverting migration is compared
+//! against snapshot taken before applying it.
+//!
+//! [`InMemoryMigrator`] stores applied migrations in memory instead of
+//! migrator table so `parents`, `replaces` and `run_before` wiring of
+//! migrations can be tested using [`assert_plan_order`] and
+//! [`assert_runs_before`] without creating migrator table.
+#![cfg_attr(
+    feature = "sqlite",
+    doc = r#"
+# Example
+```rust,no_run
+use sqlx::Sqlite;
+use sqlx_migrator::migrator::Migrator;
+use sqlx_migrator::testing::{check_round_trip, sqlite_memory_connection};
+
+# async fn run(migrator: Migrator<Sqlite>) -> Result<(), sqlx_migrator::Error> {
+let report = check_round_trip(&migrator, sqlite_memory_connection).await?;
+assert!(report.is_success(), "{report}");
+# Ok(())
+# }
+```
+"#
+)]
+
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use sqlx::Database;
+
+use crate::error::Error;
+use crate::migration::{AppliedMigrationSqlRow, Migration};
+use crate::migrator::{is_reversible, DatabaseOperation, Info, Migrate, Plan};
+use crate::snapshot::Drift;
+
+/// Migration whose revert leaves schema different from schema before
+/// migration was applied
+#[derive(Debug, Clone)]
+pub struct RoundTripFailure {
+    app: String,
+    name: String,
+    drifts: Vec<Drift>,
+}
+
+impl RoundTripFailure {
+    /// Return app of migration
+    #[must_use]
+    pub fn app(&self) -> &str {
+        &self.app
+    }
+
+    /// Return name of migration
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return differences between schema before applying migration and schema
+    /// after reverting migration
+    #[must_use]
+    pub fn drifts(&self) -> &[Drift] {
+        &self.drifts
+    }
+}
+
+/// Report of round trip check
+#[derive(Debug, Clone, Default)]
+pub struct RoundTripReport {
+    checked: Vec<String>,
+    skipped: Vec<String>,
+    failure: Option<RoundTripFailure>,
+}
+
+impl RoundTripReport {
+    /// Return migrations in `app:name` format whose round trip succeeded
+    #[must_use]
+    pub fn checked(&self) -> &[String] {
+        &self.checked
+    }
+
+    /// Return irreversible migrations in `app:name` format which were only
+    /// applied
+    #[must_use]
+    pub fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+
+    /// Return first migration whose revert leaves schema different. Remaining
+    /// migrations are not checked after failure
+    #[must_use]
+    pub fn failure(&self) -> Option<&RoundTripFailure> {
+        self.failure.as_ref()
+    }
+
+    /// Return whether round trip of all checked migrations succeeded
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+impl std::fmt::Display for RoundTripReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.failure {
+            Some(failure) => {
+                write!(
+                    f,
+                    "reverting migration {}:{} leaves schema different:",
+                    failure.app, failure.name
+                )?;
+                for drift in &failure.drifts {
+                    write!(f, "\n  {drift}")?;
+                }
+                Ok(())
+            }
+            None => {
+                write!(
+                    f,
+                    "round trip succeeded for {} migrations, skipped {} irreversible migrations",
+                    self.checked.len(),
+                    self.skipped.len()
+                )
+            }
+        }
+    }
+}
+
+/// Check that `down` of each migration undoes its `up`.
+///
+/// Connection is created using `connect` and must point to an empty database.
+/// Migrations are applied one at a time in order of apply plan and for each
+/// migration `up`, schema snapshot, `down`, comparison of snapshot with
+/// snapshot taken before applying migration and `up` again is run.
+/// Irreversible migrations are only applied. Checking stops at first migration
+/// whose revert leaves schema different
+///
+/// # Errors
+/// If connection cannot be created, applying or reverting migration fails or
+/// snapshot of schema cannot be taken
+pub async fn check_round_trip<DB, M, F, Fut, E>(
+    migrator: &M,
+    connect: F,
+) -> Result<RoundTripReport, Error>
+where
+    DB: Database,
+    M: Migrate<DB> + ?Sized,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<<DB as Database>::Connection, E>>,
+    Error: From<E>,
+{
+    let mut connection = connect().await?;
+    let migrations = migrator
+        .generate_migration_plan(&mut connection, Some(&Plan::apply_all()))
+        .await?
+        .into_iter()
+        .map(|migration| {
+            (
+                migration.app().to_string(),
+                migration.name().to_string(),
+                is_reversible(migration),
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut report = RoundTripReport::default();
+    let mut before = migrator.take_snapshot(&mut connection).await?;
+    for (app, name, reversible) in migrations {
+        let migration_name = Some(name.clone());
+        let apply_plan = Plan::apply_name(&app, &migration_name);
+        migrator.run(&mut connection, &apply_plan).await?;
+        if !reversible {
+            report.skipped.push(format!("{app}:{name}"));
+            before = migrator.take_snapshot(&mut connection).await?;
+            continue;
+        }
+        migrator
+            .run(&mut connection, &Plan::revert_name(&app, &migration_name))
+            .await?;
+        let after_revert = migrator.take_snapshot(&mut connection).await?;
+        let drifts = before.diff(&after_revert);
+        if !drifts.is_empty() {
+            report.failure = Some(RoundTripFailure { app, name, drifts });
+            return Ok(report);
+        }
+        migrator.run(&mut connection, &apply_plan).await?;
+        report.checked.push(format!("{app}:{name}"));
+        before = migrator.take_snapshot(&mut connection).await?;
+    }
+    Ok(report)
+}
+
+/// Create connection to new in memory sqlite database which can be used as
+/// connection factory for [`check_round_trip`]
+///
+/// # Errors
+/// If connection cannot be created
+#[cfg(feature = "sqlite")]
+pub async fn sqlite_memory_connection() -> Result<sqlx::SqliteConnection, Error> {
+    use sqlx::Connection;
+
+    Ok(sqlx::SqliteConnection::connect("sqlite::memory:").await?)
+}
+
+/// Action recorded by [`InMemoryMigrator`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MigratorCall {
+    /// Migration was marked as applied
+    Apply {
+        /// App of migration
+        app: String,
+        /// Name of migration
+        name: String,
+    },
+    /// Migration was marked as reverted
+    Revert {
+        /// App of migration
+        app: String,
+        /// Name of migration
+        name: String,
+    },
+}
+
+impl MigratorCall {
+    /// Create apply call for app and name
+    #[must_use]
+    pub fn apply(app: &str, name: &str) -> Self {
+        Self::Apply {
+            app: app.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    /// Create revert call for app and name
+    #[must_use]
+    pub fn revert(app: &str, name: &str) -> Self {
+        Self::Revert {
+            app: app.to_string(),
+            name: name.to_string(),
+        }
+    }
+}
+
+/// Migrator which stores applied migrations in memory instead of migrator
+/// table.
+///
+/// Applied migrations can be seeded before generating plan and every
+/// migration marked as applied or reverted while running plan is recorded in
+/// call log. Connection passed to migrator is only used for running
+/// operations and transactions, so use fake plan for only recording calls
+/// without running operations.
+pub struct InMemoryMigrator<DB> {
+    migrations: Vec<Box<dyn Migration<DB>>>,
+    applied: Mutex<Vec<AppliedMigrationSqlRow>>,
+    calls: Mutex<Vec<MigratorCall>>,
+}
+
+impl<DB> Default for InMemoryMigrator<DB> {
+    fn default() -> Self {
+        Self {
+            migrations: vec![],
+            applied: Mutex::new(vec![]),
+            calls: Mutex::new(vec![]),
+        }
+    }
+}
+
+impl<DB> InMemoryMigrator<DB> {
+    /// Create new in memory migrator without any migrations
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seed migration as already applied. Seeding is not recorded in call log
+    pub fn add_applied_migration(&mut self, app: &str, name: &str) {
+        let applied = self
+            .applied
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        let id = applied
+            .iter()
+            .map(AppliedMigrationSqlRow::id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        applied.push(AppliedMigrationSqlRow::new(id, app, name));
+    }
+
+    /// Seed list of migrations as already applied
+    pub fn add_applied_migrations(&mut self, migrations: Vec<Box<dyn Migration<DB>>>) {
+        for migration in migrations {
+            self.add_applied_migration(migration.app(), migration.name());
+        }
+    }
+
+    /// Return currently applied migrations in `app:name` format in order of
+    /// applying
+    #[must_use]
+    pub fn applied_migrations(&self) -> Vec<String> {
+        lock(&self.applied)
+            .iter()
+            .map(|migration| format!("{}:{}", migration.app(), migration.name()))
+            .collect()
+    }
+
+    /// Return recorded apply and revert calls in order of call
+    #[must_use]
+    pub fn calls(&self) -> Vec<MigratorCall> {
+        lock(&self.calls).clone()
+    }
+
+    /// Clear recorded call log
+    pub fn clear_calls(&self) {
+        lock(&self.calls).clear();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<DB> Info<DB> for InMemoryMigrator<DB> {
+    fn migrations(&self) -> &Vec<Box<dyn Migration<DB>>> {
+        &self.migrations
+    }
+
+    fn migrations_mut(&mut self) -> &mut Vec<Box<dyn Migration<DB>>> {
+        &mut self.migrations
+    }
+}
+
+#[async_trait::async_trait]
+impl<DB> DatabaseOperation<DB> for InMemoryMigrator<DB>
+where
+    DB: Database,
+{
+    async fn ensure_migration_table_exists(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn migration_table_exists(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+    ) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    async fn drop_migration_table_if_exists(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+    ) -> Result<(), Error> {
+        lock(&self.applied).clear();
+        Ok(())
+    }
+
+    async fn add_migration_to_db_table(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        migration: &Box<dyn Migration<DB>>,
+    ) -> Result<(), Error> {
+        let mut applied = lock(&self.applied);
+        let id = applied
+            .iter()
+            .map(AppliedMigrationSqlRow::id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        applied.push(AppliedMigrationSqlRow::new(
+            id,
+            migration.app(),
+            migration.name(),
+        ));
+        lock(&self.calls).push(MigratorCall::apply(migration.app(), migration.name()));
+        Ok(())
+    }
+
+    async fn delete_migration_from_db_table(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        migration: &Box<dyn Migration<DB>>,
+    ) -> Result<(), Error> {
+        lock(&self.applied).retain(|applied| applied != migration);
+        lock(&self.calls).push(MigratorCall::revert(migration.app(), migration.name()));
+        Ok(())
+    }
+
+    async fn fetch_applied_migration_from_db(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+    ) -> Result<Vec<AppliedMigrationSqlRow>, Error> {
+        Ok(lock(&self.applied).clone())
+    }
+
+    async fn lock(&self, _connection: &mut <DB as Database>::Connection) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn unlock(&self, _connection: &mut <DB as Database>::Connection) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<DB> Migrate<DB> for InMemoryMigrator<DB> where DB: Database {}
+
+#[expect(clippy::borrowed_box)]
+fn plan_names<DB>(plan: &[&Box<dyn Migration<DB>>]) -> Vec<String> {
+    plan.iter()
+        .map(|migration| format!("{}:{}", migration.app(), migration.name()))
+        .collect()
+}
+
+/// Assert that plan contains exactly provided migrations in provided order.
+/// Migrations are provided as `(app, name)` pair
+///
+/// # Panics
+/// If plan differs from expected migrations
+#[track_caller]
+#[expect(clippy::borrowed_box)]
+pub fn assert_plan_order<DB>(plan: &[&Box<dyn Migration<DB>>], expected: &[(&str, &str)]) {
+    let expected = expected
+        .iter()
+        .map(|(app, name)| format!("{app}:{name}"))
+        .collect::<Vec<_>>();
+    let actual = plan_names(plan);
+    assert!(
+        actual == expected,
+        "plan order differs\n expected: {expected:?}\n   actual: {actual:?}"
+    );
+}
+
+/// Assert that `first` migration is present before `second` migration in
+/// plan. Migrations are provided as `(app, name)` pair
+///
+/// # Panics
+/// If any migration is not present in plan or `first` is present after
+/// `second`
+#[track_caller]
+#[expect(clippy::borrowed_box)]
+pub fn assert_runs_before<DB>(
+    plan: &[&Box<dyn Migration<DB>>],
+    first: (&str, &str),
+    second: (&str, &str),
+) {
+    let actual = plan_names(plan);
+    let position = |(app, name): (&str, &str)| {
+        let migration = format!("{app}:{name}");
+        let index = actual
+            .iter()
+            .position(|plan_migration| plan_migration == &migration);
+        assert!(
+            index.is_some(),
+            "migration {migration} is not present in plan {actual:?}"
+        );
+        index.unwrap_or_default()
+    };
+    let first_position = position(first);
+    let second_position = position(second);
+    assert!(
+        first_position < second_position,
+        "migration {}:{} runs after {}:{} in plan {actual:?}",
+        first.0,
+        first.1,
+        second.0,
+        second.1
+    );
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use sqlx::Sqlite;
+
+    use super::{
+        assert_plan_order, assert_runs_before, check_round_trip, sqlite_memory_connection,
+        InMemoryMigrator, MigratorCall,
+    };
+    use crate::migrator::{Info, Migrate, Migrator, Plan};
+    use crate::snapshot::Drift;
+    use crate::test_utils::{migration, sqlite_pool};
+    use crate::vec_box;
+
+    #[tokio::test]
+    async fn round_trip_check() {
+        struct A;
+        crate::sqlite_migration!(
+            A,
+            app = "test",
+            name = "a",
+            parents = vec_box![],
+            operations = vec_box![("CREATE TABLE a (id INTEGER PRIMARY KEY)", "DROP TABLE a")]
+        );
+        struct B;
+        crate::sqlite_migration!(
+            B,
+            app = "test",
+            name = "b",
+            parents = vec_box![A],
+            operations = vec_box![(
+                "CREATE TABLE b (id INTEGER); CREATE INDEX b_id ON b (id);",
+                "DROP INDEX b_id; DROP TABLE b;"
+            )]
+        );
+        struct C;
+        crate::sqlite_migration!(
+            C,
+            app = "test",
+            name = "c",
+            parents = vec_box![B],
+            operations = vec_box![("ALTER TABLE a ADD COLUMN note TEXT", "SELECT 1")]
+        );
+
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box![A, B]);
+        let report = check_round_trip(&migrator, sqlite_memory_connection)
+            .await
+            .unwrap();
+        assert!(report.is_success(), "{report}");
+        assert_eq!(report.checked(), ["test:a", "test:b"]);
+
+        migrator.add_migrations(vec_box![C]);
+        let report = check_round_trip(&migrator, sqlite_memory_connection)
+            .await
+            .unwrap();
+        let failure = report.failure().unwrap();
+        assert_eq!((failure.app(), failure.name()), ("test", "c"));
+        assert_eq!(
+            failure.drifts(),
+            [Drift::Unexpected {
+                object: "column a.note".to_string()
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn in_memory_migrator() {
+        struct A;
+        migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+        struct B;
+        migration!(B, "b", vec_box!(A), vec_box!(), vec_box!());
+        struct C;
+        migration!(C, "c", vec_box!(A), vec_box!(), vec_box!(B));
+        let mut migrator = InMemoryMigrator::<Sqlite>::new();
+        migrator.add_migrations(vec_box![A, B, C]);
+        migrator.add_applied_migrations(vec_box![A]);
+        let sqlite = sqlite_pool().await;
+        let mut conn = sqlite.acquire().await.unwrap();
+
+        let plan = migrator
+            .generate_migration_plan(&mut conn, Some(&Plan::apply_all()))
+            .await
+            .unwrap();
+        assert_plan_order(&plan, &[("test", "c"), ("test", "b")]);
+        assert_runs_before(&plan, ("test", "c"), ("test", "b"));
+
+        migrator
+            .run(&mut conn, &Plan::apply_all().fake(true))
+            .await
+            .unwrap();
+        migrator
+            .run(&mut conn, &Plan::revert_count(1))
+            .await
+            .unwrap();
+        assert_eq!(migrator.applied_migrations(), ["test:a", "test:c"]);
+        assert_eq!(
+            migrator.calls(),
+            [
+                MigratorCall::apply("test", "c"),
+                MigratorCall::apply("test", "b"),
+                MigratorCall::revert("test", "b")
+            ]
+        );
+        migrator.clear_calls();
+        assert!(migrator.calls().is_empty());
+    }
+}
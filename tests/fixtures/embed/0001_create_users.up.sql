@@ -0,0 +1,2 @@
+-- atomic: false
+CREATE TABLE users (id INTEGER PRIMARY KEY);
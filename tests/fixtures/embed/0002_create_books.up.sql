@@ -0,0 +1 @@
+CREATE TABLE books (id INTEGER PRIMARY KEY);